@@ -2,6 +2,7 @@
 use libfuzzer_sys::fuzz_target;
 
 fuzz_target!(|data: &[u8]| {
-    // fuzzed code goes here
-    let _ = nastran::bdf::v0::parser::parse_buffer(data);
+    if let Ok(cards) = nastran::bdf::parser::parse_buffer(data) {
+        cards.for_each(drop);
+    }
 });
@@ -1,5 +1,8 @@
+pub mod case_control;
 pub mod deck;
+pub mod document;
 mod error;
 pub mod parser;
+pub mod sections;
 
-pub use error::{Error, Result};
+pub use error::{Diagnostic, Error, Location, Result, Severity};
@@ -0,0 +1,634 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use bstr::ByteSlice;
+
+use crate::bdf::parser::{BulkCard, Field, SourceLine};
+use crate::bdf::sections::{keyword_len, statements};
+use crate::bdf::{Error, Result};
+
+/// The parsed case control section. Selections made above the first SUBCASE
+/// are held in `global` and are inherited by every subcase that doesn't
+/// override them
+#[derive(Debug, Default, Clone)]
+pub struct CaseControl {
+    pub global: Subcase,
+    /// Subcases in the order they were defined with inheritance from `global`
+    /// already applied. A case control section without any SUBCASE
+    /// statements has a single subcase 1
+    pub subcases: Vec<Subcase>,
+    /// Any lines following an `OUTPUT(PLOT)` or similar statement, which are
+    /// left unparsed
+    pub plot: Vec<SourceLine>,
+}
+
+/// The selections and output requests for a single subcase
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Subcase {
+    /// Subcase id, which is 0 for the global subcase
+    pub id: u32,
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub label: Option<String>,
+    pub load: Option<u32>,
+    pub spc: Option<u32>,
+    pub mpc: Option<u32>,
+    pub method: Option<u32>,
+    /// Temperature set used for thermal loading. Set by `TEMP(LOAD)`,
+    /// `TEMP(BOTH)` and `TEMP`
+    pub temp_load: Option<u32>,
+    /// Temperature set used for material properties. Set by
+    /// `TEMP(MATERIAL)`, `TEMP(BOTH)` and `TEMP`
+    pub temp_material: Option<u32>,
+    pub temp_init: Option<u32>,
+    pub outputs: Vec<OutputRequest>,
+    pub sets: BTreeMap<u32, Set>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputKind {
+    Displacement,
+    Stress,
+    Force,
+    GpForce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sort {
+    #[default]
+    Sort1,
+    Sort2,
+}
+
+/// Which ids an output request applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputSet {
+    All,
+    None,
+    Set(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputRequest {
+    pub kind: OutputKind,
+    pub sort: Sort,
+    pub print: bool,
+    pub plot: bool,
+    pub punch: bool,
+    /// Any other describers, such as `REAL` or `VONMISES`, uppercased
+    pub options: Vec<String>,
+    pub set: OutputSet,
+}
+
+/// An integer set defined by a `SET` statement, stored as sorted,
+/// non-overlapping inclusive ranges
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Set {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl Set {
+    pub fn contains(&self, id: u32) -> bool {
+        self.ranges
+            .binary_search_by(|&(start, end)| {
+                if end < id {
+                    std::cmp::Ordering::Less
+                } else if start > id {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    pub fn ranges(&self) -> &[(u32, u32)] {
+        &self.ranges
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.ranges.iter().flat_map(|&(start, end)| start..=end)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges
+            .iter()
+            .map(|&(start, end)| (end - start) as usize + 1)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Parses the right hand side of a SET statement such as
+    /// `1 THRU 100 EXCEPT 50, 200`. An EXCEPT list applies to the preceding
+    /// THRU range and ends at the first id outside of that range
+    fn parse(text: &[u8]) -> Option<Self> {
+        let mut tokens = text
+            .split(|&c| c == b',' || c == b' ' || c == b'\t')
+            .filter(|t| !t.is_empty());
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        let mut excepts = Vec::new();
+        let mut except_range = None;
+        while let Some(token) = tokens.next() {
+            if token.eq_ignore_ascii_case(b"THRU") {
+                let end = parse_id(tokens.next()?)?;
+                let last = ranges.last_mut()?;
+                if end < last.0 || last.0 != last.1 {
+                    return None;
+                }
+                last.1 = end;
+                except_range = None;
+            } else if token.eq_ignore_ascii_case(b"EXCEPT") {
+                let &(start, end) = ranges.last()?;
+                if start == end {
+                    return None;
+                }
+                except_range = Some((start, end));
+            } else {
+                let id = parse_id(token)?;
+                match except_range {
+                    Some((start, end)) if id >= start && id <= end => excepts.push(id),
+                    _ => {
+                        except_range = None;
+                        ranges.push((id, id));
+                    }
+                }
+            }
+        }
+        for id in excepts {
+            if let Some(i) = ranges.iter().position(|&(s, e)| s <= id && id <= e) {
+                let (start, end) = ranges.remove(i);
+                if id < end {
+                    ranges.insert(i, (id + 1, end));
+                }
+                if id > start {
+                    ranges.insert(i, (start, id - 1));
+                }
+            }
+        }
+        ranges.sort_unstable();
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        Some(Set { ranges: merged })
+    }
+}
+
+/// The kinds of bulk data set a case control selection can refer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SetKind {
+    Load,
+    Spc,
+    Mpc,
+    Method,
+    Temperature,
+}
+
+impl SetKind {
+    /// The kind of set a card belongs to and whether it combines other sets
+    /// of the same kind
+    fn of_card(card_type: &[u8; 7]) -> Option<(Self, bool)> {
+        let kind = match card_type.trim() {
+            b"LOAD" => (SetKind::Load, true),
+            b"FORCE" | b"FORCE1" | b"FORCE2" | b"MOMENT" | b"MOMENT1" | b"MOMENT2" | b"PLOAD"
+            | b"PLOAD1" | b"PLOAD2" | b"PLOAD4" | b"GRAV" | b"RFORCE" | b"SPCD" => {
+                (SetKind::Load, false)
+            }
+            b"SPCADD" => (SetKind::Spc, true),
+            b"SPC" | b"SPC1" => (SetKind::Spc, false),
+            b"MPCADD" => (SetKind::Mpc, true),
+            b"MPC" => (SetKind::Mpc, false),
+            b"EIGRL" | b"EIGR" | b"EIGB" => (SetKind::Method, false),
+            b"TEMP" | b"TEMPD" | b"TEMPP1" | b"TEMPRB" => (SetKind::Temperature, false),
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+impl Subcase {
+    /// Looks up an output request by kind
+    pub fn output(&self, kind: OutputKind) -> Option<&OutputRequest> {
+        self.outputs.iter().find(|o| o.kind == kind)
+    }
+
+    fn selections(&self) -> Vec<(SetKind, u32)> {
+        [
+            (SetKind::Load, self.load),
+            (SetKind::Spc, self.spc),
+            (SetKind::Mpc, self.mpc),
+            (SetKind::Method, self.method),
+            (SetKind::Temperature, self.temp_load),
+            (SetKind::Temperature, self.temp_material),
+            (SetKind::Temperature, self.temp_init),
+        ]
+        .iter()
+        .filter_map(|&(kind, id)| Some((kind, id?)))
+        .collect()
+    }
+
+    /// Returns the bulk data cards this subcase uses through its LOAD, SPC,
+    /// MPC, METHOD and TEMP selections, in the order they appear in `cards`.
+    /// Sets referenced through LOAD, SPCADD and MPCADD combinations are
+    /// followed
+    pub fn used_cards<'a>(
+        &self,
+        cards: impl IntoIterator<Item = &'a BulkCard>,
+    ) -> Vec<&'a BulkCard> {
+        let mut by_set = HashMap::<_, Vec<_>>::new();
+        for (i, card) in cards.into_iter().enumerate() {
+            let (kind, combines) = match card.card_type().as_ref().and_then(SetKind::of_card) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            if let Some(sid) = card.fields().first().copied().and_then(field_id) {
+                by_set
+                    .entry((kind, sid))
+                    .or_default()
+                    .push((i, card, combines));
+            }
+        }
+        let mut visited = HashSet::new();
+        let mut pending = self.selections();
+        let mut used = Vec::new();
+        while let Some(set) = pending.pop() {
+            if !visited.insert(set) {
+                continue;
+            }
+            for &(i, card, combines) in by_set.get(&set).into_iter().flatten() {
+                used.push((i, card));
+                if !combines {
+                    continue;
+                }
+                // LOAD has an overall scale factor then pairs of scale
+                // factors and set ids. SPCADD and MPCADD list set ids
+                let referenced: Vec<_> = match set.0 {
+                    SetKind::Load => card.fields().iter().skip(3).step_by(2).collect(),
+                    _ => card.fields().iter().skip(1).collect(),
+                };
+                pending.extend(
+                    referenced
+                        .into_iter()
+                        .filter_map(|f| field_id(*f))
+                        .map(|id| (set.0, id)),
+                );
+            }
+        }
+        used.sort_by_key(|&(i, _)| i);
+        used.into_iter().map(|(_, card)| card).collect()
+    }
+
+    /// Fills in anything not set on this subcase from `global`
+    fn inherit(&mut self, global: &Subcase) {
+        fn or<T: Clone>(value: &mut Option<T>, global: &Option<T>) {
+            if value.is_none() {
+                *value = global.clone();
+            }
+        }
+        or(&mut self.title, &global.title);
+        or(&mut self.subtitle, &global.subtitle);
+        or(&mut self.label, &global.label);
+        or(&mut self.load, &global.load);
+        or(&mut self.spc, &global.spc);
+        or(&mut self.mpc, &global.mpc);
+        or(&mut self.method, &global.method);
+        or(&mut self.temp_load, &global.temp_load);
+        or(&mut self.temp_material, &global.temp_material);
+        or(&mut self.temp_init, &global.temp_init);
+        for output in &global.outputs {
+            if self.output(output.kind).is_none() {
+                self.outputs.push(output.clone());
+            }
+        }
+        for (id, set) in &global.sets {
+            self.sets.entry(*id).or_insert_with(|| set.clone());
+        }
+    }
+
+    fn apply(&mut self, statement: Statement) {
+        match statement {
+            Statement::Title(s) => self.title = Some(s),
+            Statement::Subtitle(s) => self.subtitle = Some(s),
+            Statement::Label(s) => self.label = Some(s),
+            Statement::Load(id) => self.load = Some(id),
+            Statement::Spc(id) => self.spc = Some(id),
+            Statement::Mpc(id) => self.mpc = Some(id),
+            Statement::Method(id) => self.method = Some(id),
+            Statement::TempLoad(id) => self.temp_load = Some(id),
+            Statement::TempMaterial(id) => self.temp_material = Some(id),
+            Statement::TempBoth(id) => {
+                self.temp_load = Some(id);
+                self.temp_material = Some(id);
+            }
+            Statement::TempInit(id) => self.temp_init = Some(id),
+            Statement::Output(output) => {
+                self.outputs.retain(|o| o.kind != output.kind);
+                self.outputs.push(output);
+            }
+            Statement::Set(id, set) => {
+                self.sets.insert(id, set);
+            }
+            Statement::Subcase(_) | Statement::Plot | Statement::Other => {}
+        }
+    }
+}
+
+impl CaseControl {
+    /// Parses case control lines, such as `Sections::case_control`
+    pub fn from_lines(lines: &[SourceLine]) -> Result<Self> {
+        let mut case_control = CaseControl::default();
+        let mut subcases = Vec::new();
+        for (line, text) in statements(lines) {
+            let statement = Statement::parse(&text).map_err(|e| e.at(line.location()))?;
+            match statement {
+                Statement::Subcase(id) => subcases.push(Subcase {
+                    id,
+                    ..Default::default()
+                }),
+                Statement::Plot => {
+                    let start = lines
+                        .iter()
+                        .position(|l| std::ptr::eq(l, line))
+                        .unwrap_or(0);
+                    case_control.plot = lines[start..].to_vec();
+                    break;
+                }
+                statement => match subcases.last_mut() {
+                    Some(subcase) => subcase.apply(statement),
+                    None => case_control.global.apply(statement),
+                },
+            }
+        }
+        if subcases.is_empty() {
+            subcases.push(Subcase {
+                id: 1,
+                ..Default::default()
+            });
+        }
+        for subcase in &mut subcases {
+            subcase.inherit(&case_control.global);
+        }
+        case_control.subcases = subcases;
+        Ok(case_control)
+    }
+
+    pub fn subcase(&self, id: u32) -> Option<&Subcase> {
+        self.subcases.iter().find(|s| s.id == id)
+    }
+}
+
+enum Statement {
+    Subcase(u32),
+    Title(String),
+    Subtitle(String),
+    Label(String),
+    Load(u32),
+    Spc(u32),
+    Mpc(u32),
+    Method(u32),
+    TempLoad(u32),
+    TempMaterial(u32),
+    TempBoth(u32),
+    TempInit(u32),
+    Output(OutputRequest),
+    Set(u32, Set),
+    Plot,
+    Other,
+}
+
+/// Case control keywords only need their first 4 characters
+fn abbreviates(name: &[u8], full: &[u8]) -> bool {
+    name == full || (name.len() >= 4 && full.starts_with(name))
+}
+
+fn parse_id(text: &[u8]) -> Option<u32> {
+    text.trim().to_str().ok()?.parse().ok()
+}
+
+fn field_id(field: Field) -> Option<u32> {
+    match field {
+        Field::Int(i) if i > 0 => Some(i as u32),
+        Field::IntOrId(i) => Some(i),
+        _ => None,
+    }
+}
+
+impl Statement {
+    fn parse(text: &[u8]) -> Result<Self> {
+        let invalid = || Error::InvalidStatement(text.to_vec());
+        let name = text.trim_start();
+        let name = name[..keyword_len(name)].to_ascii_uppercase();
+        let (lhs, rhs) = match text.find_byte(b'=') {
+            Some(i) => (&text[..i], Some(text[i + 1..].trim())),
+            None => (text, None),
+        };
+        let lhs = lhs.trim_start()[name.len()..].trim();
+        // Describers in parentheses following the keyword
+        let describers: Vec<String> = match lhs.strip_prefix(b"(") {
+            Some(inner) => {
+                let end = inner.find_byte(b')').ok_or_else(invalid)?;
+                inner[..end]
+                    .split(|&c| c == b',')
+                    .map(|d| d.trim().to_str_lossy().to_ascii_uppercase())
+                    .filter(|d| !d.is_empty())
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let id = || rhs.and_then(parse_id).ok_or_else(invalid);
+        let text_value = || rhs.unwrap_or_default().to_str_lossy().into_owned();
+        let name = name.as_slice();
+        let statement = if abbreviates(name, b"SUBCASE") {
+            Statement::Subcase(parse_id(lhs).ok_or_else(invalid)?)
+        } else if name == b"SET" {
+            let set_id = parse_id(lhs).ok_or_else(invalid)?;
+            let set = rhs.and_then(Set::parse).ok_or_else(invalid)?;
+            Statement::Set(set_id, set)
+        } else if abbreviates(name, b"TITLE") {
+            Statement::Title(text_value())
+        } else if abbreviates(name, b"SUBTITLE") {
+            Statement::Subtitle(text_value())
+        } else if abbreviates(name, b"LABEL") {
+            Statement::Label(text_value())
+        } else if name == b"LOAD" {
+            Statement::Load(id()?)
+        } else if name == b"SPC" {
+            Statement::Spc(id()?)
+        } else if name == b"MPC" {
+            Statement::Mpc(id()?)
+        } else if abbreviates(name, b"METHOD") {
+            match describers.first().map(String::as_str) {
+                None | Some("STRUCTURE") => Statement::Method(id()?),
+                Some(_) => Statement::Other,
+            }
+        } else if abbreviates(name, b"TEMPERATURE") {
+            let describer = describers.first().map(String::as_bytes);
+            match describer {
+                None => Statement::TempBoth(id()?),
+                Some(d) if abbreviates(d, b"BOTH") => Statement::TempBoth(id()?),
+                Some(d) if abbreviates(d, b"LOAD") => Statement::TempLoad(id()?),
+                Some(d) if abbreviates(d, b"MATERIAL") => Statement::TempMaterial(id()?),
+                Some(d) if abbreviates(d, b"INITIAL") => Statement::TempInit(id()?),
+                Some(_) => return Err(invalid()),
+            }
+        } else if name == b"OUTPUT" {
+            if describers.is_empty() {
+                Statement::Other
+            } else {
+                Statement::Plot
+            }
+        } else if let Some(kind) = output_kind(name) {
+            let set = match rhs.map(|r| r.to_ascii_uppercase()) {
+                Some(r) if r == b"ALL" => OutputSet::All,
+                Some(r) if r == b"NONE" => OutputSet::None,
+                _ => OutputSet::Set(id()?),
+            };
+            Statement::Output(OutputRequest::new(kind, describers, set))
+        } else {
+            Statement::Other
+        };
+        Ok(statement)
+    }
+}
+
+fn output_kind(name: &[u8]) -> Option<OutputKind> {
+    if abbreviates(name, b"DISPLACEMENT") {
+        Some(OutputKind::Displacement)
+    } else if abbreviates(name, b"STRESS") || abbreviates(name, b"ELSTRESS") {
+        Some(OutputKind::Stress)
+    } else if name == b"FORCE" || abbreviates(name, b"ELFORCE") {
+        Some(OutputKind::Force)
+    } else if name == b"GPFORCE" {
+        Some(OutputKind::GpForce)
+    } else {
+        None
+    }
+}
+
+impl OutputRequest {
+    fn new(kind: OutputKind, describers: Vec<String>, set: OutputSet) -> Self {
+        let mut request = OutputRequest {
+            kind,
+            sort: Sort::Sort1,
+            print: false,
+            plot: false,
+            punch: false,
+            options: Vec::new(),
+            set,
+        };
+        for describer in describers {
+            match describer.as_str() {
+                "SORT1" => request.sort = Sort::Sort1,
+                "SORT2" => request.sort = Sort::Sort2,
+                "PRINT" => request.print = true,
+                "PLOT" => request.plot = true,
+                "PUNCH" => request.punch = true,
+                _ => request.options.push(describer),
+            }
+        }
+        // Output is printed unless it's only requested for plotting or punching
+        if !request.plot && !request.punch {
+            request.print = true;
+        }
+        request
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::parser::parse_buffer;
+    use crate::bdf::sections::Sections;
+
+    const INPUT: &[u8] = b"SOL 101
+CEND
+TITLE = MODEL
+SPC = 1
+DISP(PLOT) = ALL
+SET 10 = 1 THRU 100 EXCEPT 50 51,
+   200
+SUBCASE 1
+  LOAD = 2
+  STRESS(SORT2,PUNCH,VONMISES) = 10
+SUBCASE 2
+  LOAD = 3
+  SPC = 4
+  DISP = NONE
+BEGIN BULK
+SPC1,1,123,1
+SPC1,4,123,2
+SPCADD,5,1
+LOAD,2,1.,2.,20,3.,21
+FORCE,20,1,0,1.,1.,0.,0.
+MOMENT,21,1,0,1.,1.,0.,0.
+FORCE,3,1,0,1.,1.,0.,0.
+FORCE,22,1,0,1.,1.,0.,0.
+ENDDATA
+";
+
+    #[test]
+    fn test_set() {
+        let set = Set::parse(b"1 THRU 100 EXCEPT 50 51, 200 5").unwrap();
+        assert_eq!(set.ranges(), &[(1, 49), (52, 100), (200, 200)]);
+        assert!(set.contains(1) && set.contains(200) && !set.contains(50));
+        assert_eq!(set.len(), 99);
+        assert!(Set::parse(b"EXCEPT 5").is_none());
+        assert!(Set::parse(b"1 THRU").is_none());
+    }
+
+    #[test]
+    fn test_case_control() {
+        let sections = Sections::from_buffer(INPUT).unwrap();
+        let case_control = sections.parse_case_control().unwrap();
+        assert_eq!(case_control.subcases.len(), 2);
+
+        let subcase = case_control.subcase(1).unwrap();
+        assert_eq!(subcase.title.as_deref(), Some("MODEL"));
+        assert_eq!((subcase.load, subcase.spc), (Some(2), Some(1)));
+        let disp = subcase.output(OutputKind::Displacement).unwrap();
+        assert_eq!(
+            (disp.plot, disp.print, disp.set),
+            (true, false, OutputSet::All)
+        );
+        let stress = subcase.output(OutputKind::Stress).unwrap();
+        assert_eq!(stress.sort, Sort::Sort2);
+        assert_eq!(stress.options, vec!["VONMISES".to_string()]);
+        assert_eq!(stress.set, OutputSet::Set(10));
+        assert!(subcase.sets[&10].contains(200));
+
+        let subcase = case_control.subcase(2).unwrap();
+        assert_eq!((subcase.load, subcase.spc), (Some(3), Some(4)));
+        let disp = subcase.output(OutputKind::Displacement).unwrap();
+        assert_eq!(disp.set, OutputSet::None);
+        assert!(subcase.output(OutputKind::Stress).is_none());
+
+        let cards = parse_buffer(INPUT)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let lines = |s: &Subcase| {
+            s.used_cards(&cards)
+                .iter()
+                .map(|c| c.line())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lines(case_control.subcase(1).unwrap()),
+            vec![16, 19, 20, 21]
+        );
+        assert_eq!(lines(case_control.subcase(2).unwrap()), vec![17, 22]);
+
+        let error = CaseControl::from_lines(
+            &Sections::from_buffer(b"CEND\nLOAD = X\n")
+                .unwrap()
+                .case_control,
+        )
+        .unwrap_err();
+        assert_eq!(error.location().unwrap().line(), 2);
+    }
+}
@@ -1,21 +1,70 @@
-use std::collections::HashMap;
-use std::convert::{TryFrom, TryInto};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io;
+
+use bstr::ByteSlice;
 
 use crate::bdf::{
-    parser::{parse_file, BulkCard, Field, FieldConv},
-    Error, Result,
+    case_control::Subcase,
+    parser::{
+        parse_buffer, parse_buffer_lenient_with, parse_buffer_with, parse_file,
+        parse_file_lenient_with, parse_file_with, parse_reader_lenient_with, parse_reader_with,
+        BulkCard, Field, FieldConv, FieldKind, ParseReport, ParserOptions,
+    },
+    sections::Partition,
+    Diagnostic, Error, Result,
 };
 use crate::util::{CoordSys, Vec3, Xyz};
 
+mod bush;
+mod composite;
+mod dof;
+mod line;
+mod load;
+mod mass;
+mod material;
+mod mpc;
+mod rigid;
+mod scalar;
+mod section;
+mod shell;
+mod solid;
+mod spc;
+mod table;
+
+pub use bush::{CBUSH, PBUSH};
+pub use composite::{Lam, Laminate, Moduli, Ply, PCOMP, PCOMPG};
+pub use dof::{Dof, DofConflict, Source, SpcSource};
+pub use line::{
+    Frame, Orientation, Station, StressOutput, CBAR, CBEAM, CONROD, CROD, CTUBE, PBAR, PBEAM, PROD,
+    PTUBE,
+};
+pub use load::{
+    Resultant, SkippedLoad, FORCE, FORCE1, FORCE2, GRAV, LOAD, MOMENT, MOMENT1, MOMENT2, PLOAD,
+    PLOAD2, PLOAD4, RFORCE,
+};
+pub use mass::{CONM1, CONM2};
+pub use material::{Material, MAT10, MAT2, MAT8, MAT9, MATT1, MATT8};
+pub use mpc::{MPC, MPCADD};
+pub use rigid::{Weight, RBAR, RBE1, RBE2, RBE3, RROD};
+pub use scalar::{
+    CDAMP1, CDAMP2, CDAMP3, CDAMP4, CELAS1, CELAS2, CELAS3, CELAS4, CMASS1, CMASS2, CMASS3, CMASS4,
+    PDAMP, PELAS, PMASS,
+};
+pub use section::{Section, Shape, PBARL, PBEAML};
+pub use shell::{MaterialOrientation, CQUAD4, CQUAD8, CQUADR, CTRIA3, CTRIA6, CTRIAR, PSHELL};
+pub use solid::{CHEXA, CPENTA, CPYRAM, CTETRA};
+pub use spc::{GRDSET, SPC, SPC1, SPCADD, SPCD};
+pub use table::{Axis, MaterialTable, TABLEM1, TABLEM2, TABLEM3, TABLEM4};
+
 #[derive(Debug, Clone)]
 pub struct GRID {
     id: u32,
     cp: u32,
     xyz: Xyz,
-    #[allow(dead_code)]
     cd: u32,
-    #[allow(dead_code)]
-    ps: [bool; 6],
+    /// Blank when the permanent constraints come from the GRDSET
+    ps: Option<[bool; 6]>,
     #[allow(dead_code)]
     seid: u32,
 }
@@ -28,15 +77,18 @@ impl StorageItem for GRID {
     }
 }
 
-impl TryFrom<BulkCard> for GRID {
+impl TryFrom<&BulkCard> for GRID {
     type Error = Error;
-    fn try_from(card: BulkCard) -> Result<Self> {
+    fn try_from(card: &BulkCard) -> Result<Self> {
         match card.card_type().as_ref() {
             Some(b"GRID   ") => {}
             Some(c) => return Err(Error::UnexpectedCardType(*b"GRID   ", *c)),
             None => return Err(Error::UnexpectedCardType(*b"GRID   ", *b"       ")),
         }
-        let mut iter = card.fields().iter().cloned();
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Real, Real, Real, Int, Int, Int])?
+            .into_iter();
         let id = iter.next().id()?;
         let cp = iter.next().id_or(0)?;
         let x = iter.next().float_or(0.0)?;
@@ -45,7 +97,10 @@ impl TryFrom<BulkCard> for GRID {
         let xyz = Xyz::new(x, y, z);
         // is this the right default?
         let cd = iter.next().id_or(0)?;
-        let ps = iter.next().dof()?;
+        let ps = match iter.next().unwrap_or_default() {
+            Field::Blank => None,
+            field => Some(field.dof()?),
+        };
         let seid = iter.next().id_or(0)?;
         Ok(GRID {
             id,
@@ -74,7 +129,9 @@ pub struct CORD2R {
 }
 
 impl CORD2R {
-    fn rotation_matrix(&self) -> CoordSys {
+    /// The system in basic coordinates, where `reference` is the system RID
+    /// its points are given in, or `None` for the basic system
+    fn rotation_matrix(&self, reference: Option<&CoordSys>) -> CoordSys {
         let Self {
             x0,
             y0,
@@ -87,9 +144,13 @@ impl CORD2R {
             z2,
             ..
         } = *self;
-        let g0 = Vec3::new(x0, y0, z0);
-        let g1 = Vec3::new(x1, y1, z1);
-        let g2 = Vec3::new(x2, y2, z2);
+        let point = |x, y, z| {
+            let xyz = Xyz::new(x, y, z);
+            reference.map_or(xyz, |r| r.forward(xyz)).to_vec()
+        };
+        let g0 = point(x0, y0, z0);
+        let g1 = point(x1, y1, z1);
+        let g2 = point(x2, y2, z2);
         let z = (g1 - g0).normalize();
         let x = g2 - g0;
         let y = z.cross(x).normalize();
@@ -107,15 +168,20 @@ impl StorageItem for CORD2R {
     }
 }
 
-impl TryFrom<BulkCard> for CORD2R {
+impl TryFrom<&BulkCard> for CORD2R {
     type Error = Error;
-    fn try_from(card: BulkCard) -> Result<Self> {
+    fn try_from(card: &BulkCard) -> Result<Self> {
         match card.card_type().as_ref() {
             Some(b"CORD2R ") => {}
             Some(c) => return Err(Error::UnexpectedCardType(*b"CORD2R ", *c)),
             None => return Err(Error::UnexpectedCardType(*b"CORD2R ", *b"       ")),
         }
-        let mut iter = card.fields().iter().cloned();
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Real, Real, Real, Real, Real, Real, Real, Real, Real,
+            ])?
+            .into_iter();
         let id = iter.next().id()?;
         let rid = iter.next().id_or(0)?;
         let x0 = iter.next().float_or(0.0)?;
@@ -143,36 +209,6 @@ impl TryFrom<BulkCard> for CORD2R {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct CTETRA {
-    eid: u32,
-    pid: u32,
-    g1: u32,
-    g2: u32,
-    g3: u32,
-    g4: u32,
-}
-
-impl CTETRA {
-    fn volume(&self, location: &GlobalLocation) -> Option<f64> {
-        let xyz1 = location.get_grid(self.g1)?.to_vec();
-        let xyz2 = location.get_grid(self.g2)?.to_vec();
-        let xyz3 = location.get_grid(self.g3)?.to_vec();
-        let xyz4 = location.get_grid(self.g4)?.to_vec();
-        let volume = (xyz2 - xyz1).cross(xyz3 - xyz1).dot(xyz4 - xyz1) / 6.;
-        Some(volume)
-    }
-    fn volume_cg(&self, location: &GlobalLocation) -> Option<(f64, Vec3)> {
-        let xyz1 = location.get_grid(self.g1)?.to_vec();
-        let xyz2 = location.get_grid(self.g2)?.to_vec();
-        let xyz3 = location.get_grid(self.g3)?.to_vec();
-        let xyz4 = location.get_grid(self.g4)?.to_vec();
-        let volume = (xyz2 - xyz1).cross(xyz3 - xyz1).dot(xyz4 - xyz1) / 6.;
-        let cg = (xyz1 + xyz2 + xyz3 + xyz4) / 4.;
-        Some((volume, cg))
-    }
-}
-
 #[derive(Default)]
 struct MassMoment {
     mass: f64,
@@ -201,58 +237,6 @@ impl std::iter::Sum for MassMoment {
     }
 }
 
-impl DeckRef<'_, CTETRA> {
-    fn mass(&self, location: &GlobalLocation) -> Option<f64> {
-        let density = self.density()?;
-        let volume = self.volume(location)?;
-        Some(density * volume)
-    }
-    fn mass_cg(&self, location: &GlobalLocation) -> Option<(f64, Vec3)> {
-        let density = self.density()?;
-        let (volume, cg) = self.volume_cg(location)?;
-        Some((density * volume, cg))
-    }
-    fn mass_moment(&self, location: &GlobalLocation) -> Option<MassMoment> {
-        let (mass, cg) = self.mass_cg(location)?;
-        let moment = mass * cg;
-        Some(MassMoment { mass, moment })
-    }
-}
-
-impl StorageItem for CTETRA {
-    type Id = u32;
-
-    fn id(&self) -> Self::Id {
-        self.eid
-    }
-}
-
-impl TryFrom<BulkCard> for CTETRA {
-    type Error = Error;
-    fn try_from(card: BulkCard) -> Result<Self> {
-        match card.card_type().as_ref() {
-            Some(b"CTETRA ") => {}
-            Some(c) => return Err(Error::UnexpectedCardType(*b"CTETRA ", *c)),
-            None => return Err(Error::UnexpectedCardType(*b"CTETRA ", *b"       ")),
-        }
-        let mut iter = card.fields().iter().cloned();
-        let eid = iter.next().id()?;
-        let pid = iter.next().id()?;
-        let g1 = iter.next().id()?;
-        let g2 = iter.next().id()?;
-        let g3 = iter.next().id()?;
-        let g4 = iter.next().id()?;
-        Ok(CTETRA {
-            eid,
-            pid,
-            g1,
-            g2,
-            g3,
-            g4,
-        })
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct PSOLID {
     pid: u32,
@@ -277,15 +261,18 @@ impl StorageItem for PSOLID {
     }
 }
 
-impl TryFrom<BulkCard> for PSOLID {
+impl TryFrom<&BulkCard> for PSOLID {
     type Error = Error;
-    fn try_from(card: BulkCard) -> Result<Self> {
+    fn try_from(card: &BulkCard) -> Result<Self> {
         match card.card_type().as_ref() {
             Some(b"PSOLID ") => {}
             Some(c) => return Err(Error::UnexpectedCardType(*b"PSOLID ", *c)),
             None => return Err(Error::UnexpectedCardType(*b"PSOLID ", *b"       ")),
         }
-        let mut iter = card.fields().iter().cloned();
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Int, IntOrChar, IntOrChar, IntOrChar, Char])?
+            .into_iter();
         let pid = iter.next().id()?;
         let mid = iter.next().id()?;
         let cordm = iter.next().id_or(0)?;
@@ -307,12 +294,12 @@ impl TryFrom<BulkCard> for PSOLID {
 #[derive(Debug, Clone)]
 pub struct MAT1 {
     mid: u32,
-    #[allow(dead_code)]
     e: f64,
-    #[allow(dead_code)]
     g: f64,
-    #[allow(dead_code)]
     nu: f64,
+    /// E, G and NU as given on the card, before any blank is found from the
+    /// others
+    given: [Option<f64>; 3],
     rho: f64,
     #[allow(dead_code)]
     a: f64,
@@ -323,10 +310,23 @@ pub struct MAT1 {
 }
 
 impl MAT1 {
-    #[allow(dead_code)]
-    fn density(&self) -> f64 {
+    pub fn density(&self) -> f64 {
         self.rho
     }
+
+    /// E, G and NU, where a blank is found from the other two. When only E
+    /// or only G is given the others are 0. NU can't be found when G is 0
+    fn elastic(given: [Option<f64>; 3]) -> Option<(f64, f64, f64)> {
+        match given {
+            [None, None, _] => None,
+            [Some(_), Some(0.), None] => None,
+            [Some(e), Some(g), nu] => Some((e, g, nu.unwrap_or(e / (2. * g) - 1.))),
+            [Some(e), None, Some(nu)] => Some((e, e / (2. * (1. + nu)), nu)),
+            [Some(e), None, None] => Some((e, 0., 0.)),
+            [None, Some(g), Some(nu)] => Some((2. * (1. + nu) * g, g, nu)),
+            [None, Some(g), None] => Some((0., g, 0.)),
+        }
+    }
 }
 
 impl StorageItem for MAT1 {
@@ -337,15 +337,20 @@ impl StorageItem for MAT1 {
     }
 }
 
-impl TryFrom<BulkCard> for MAT1 {
+impl TryFrom<&BulkCard> for MAT1 {
     type Error = Error;
-    fn try_from(card: BulkCard) -> Result<Self> {
+    fn try_from(card: &BulkCard) -> Result<Self> {
         match card.card_type().as_ref() {
             Some(b"MAT1   ") => {}
             Some(c) => return Err(Error::UnexpectedCardType(*b"MAT1   ", *c)),
             None => return Err(Error::UnexpectedCardType(*b"MAT1   ", *b"       ")),
         }
-        let mut iter = card.fields().iter().cloned();
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real, Int,
+            ])?
+            .into_iter();
         let mid = iter.next().id()?;
         let field_e = iter.next().unwrap_or_default();
         let field_g = iter.next().unwrap_or_default();
@@ -353,28 +358,10 @@ impl TryFrom<BulkCard> for MAT1 {
         let e = field_e.maybe_float()?;
         let g = field_g.maybe_float()?;
         let nu = field_nu.maybe_float()?;
-        let (e, g, nu) = if e.is_none() && g.is_none() {
-            return Err(Error::InvalidMaterialCard(field_e, field_g, field_nu));
-        } else if e.is_some() && g.is_some() {
-            let e = e.unwrap();
-            let g = g.unwrap();
-            let nu = nu.unwrap_or(1.0 - e / (2.0 * g));
-            (e, g, nu)
-        } else if let Some(e) = e {
-            if let Some(nu) = nu {
-                let g = e / (2. * (1. + nu));
-                (e, g, nu)
-            } else {
-                (e, 0.0, 0.0)
-            }
-        } else {
-            let g = g.unwrap();
-            if let Some(nu) = nu {
-                let e = 2. * (1. + nu) * g;
-                (e, g, nu)
-            } else {
-                (0.0, g, 0.0)
-            }
+        let given = [e, g, nu];
+        let (e, g, nu) = match MAT1::elastic(given) {
+            Some(elastic) => elastic,
+            None => return Err(Error::InvalidMaterialCard(field_e, field_g, field_nu)),
         };
 
         let rho = iter.next().float_or(0.)?;
@@ -386,6 +373,7 @@ impl TryFrom<BulkCard> for MAT1 {
             e,
             g,
             nu,
+            given,
             rho,
             a,
             tref,
@@ -394,6 +382,36 @@ impl TryFrom<BulkCard> for MAT1 {
     }
 }
 
+/// Converts a card to its typed representation, attaching the card's location
+/// to any error
+fn convert<'a, T>(card: &'a BulkCard) -> Result<T>
+where
+    T: TryFrom<&'a BulkCard, Error = Error>,
+{
+    T::try_from(card).map_err(|e| e.at(card.location()))
+}
+
+/// Reads a list of ids, where `THRU` between two ids stands for every id in
+/// between
+fn id_list(mut iter: impl Iterator<Item = Field>, card: &'static str) -> Result<Vec<u32>> {
+    let mut ids = Vec::new();
+    while let Some(field) = iter.next() {
+        match field {
+            Field::Blank => {}
+            Field::IntOrId(id) => ids.push(id),
+            Field::Text(text) if text.trim_end() == b"THRU" => {
+                let end = iter.next().unwrap_or_default();
+                match (ids.last().copied(), end.id()?) {
+                    (Some(start), last) if last > start => ids.extend(start + 1..=last),
+                    _ => return Err(Error::UnexpectedField("THRU", end)),
+                }
+            }
+            field => return Err(Error::UnexpectedField(card, field)),
+        }
+    }
+    Ok(ids)
+}
+
 pub trait StorageItem: Clone {
     type Id: std::hash::Hash + Eq;
     fn id(&self) -> Self::Id;
@@ -450,7 +468,6 @@ where
         self.map.get(&id).and_then(|i| self.data[*i].as_ref())
     }
 
-    #[cfg_attr(feature = "parallel", allow(dead_code))]
     fn replace(&mut self, item: T) -> Option<T> {
         let i = self.data.len();
         let id = item.id();
@@ -461,7 +478,6 @@ where
         }
     }
 
-    #[cfg_attr(feature = "parallel", allow(dead_code))]
     fn insert(&mut self, item: T) -> Result<()> {
         match self.replace(item) {
             Some(_item) => Err(Error::DuplicateCard),
@@ -469,6 +485,11 @@ where
         }
     }
 
+    /// Inserts the items of a card holding more than one
+    fn insert_all(&mut self, items: impl IntoIterator<Item = T>) -> Result<()> {
+        items.into_iter().try_for_each(|item| self.insert(item))
+    }
+
     #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
     fn extend_from_raw(&mut self, raw: RawStorage<T>) -> Result<()> {
         let n = self.data.len();
@@ -510,6 +531,95 @@ where
     }
 }
 
+/// A card that shares its id with others of the same set, like the
+/// equations of an MPC set
+pub trait SetItem: Clone {
+    fn sid(&self) -> u32;
+}
+
+#[derive(Debug)]
+pub struct SetStorage<T> {
+    data: Vec<T>,
+    map: HashMap<u32, Vec<usize>>,
+}
+
+impl<T> From<RawStorage<T>> for SetStorage<T>
+where
+    T: SetItem,
+{
+    fn from(raw: RawStorage<T>) -> Self {
+        let mut storage = Self::with_capacity(raw.data.len());
+        storage.extend(raw.data.into_iter().flatten());
+        storage
+    }
+}
+
+impl<T> SetStorage<T>
+where
+    T: SetItem,
+{
+    fn with_capacity(n: usize) -> Self {
+        Self {
+            data: Vec::with_capacity(n),
+            map: HashMap::new(),
+        }
+    }
+
+    fn get(&self, sid: u32) -> impl Iterator<Item = &T> {
+        self.map
+            .get(&sid)
+            .into_iter()
+            .flatten()
+            .map(move |&i| &self.data[i])
+    }
+
+    fn extend(&mut self, items: impl IntoIterator<Item = T>) {
+        for item in items {
+            self.map
+                .entry(item.sid())
+                .or_default()
+                .push(self.data.len());
+            self.data.push(item);
+        }
+    }
+
+    /// Sets can be given by any number of cards so this never fails
+    fn insert(&mut self, item: T) -> Result<()> {
+        self.extend(std::iter::once(item));
+        Ok(())
+    }
+
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    fn extend_from_raw(&mut self, raw: RawStorage<T>) -> Result<()> {
+        self.extend(raw.data.into_iter().flatten());
+        Ok(())
+    }
+}
+
+impl<T> Default for SetStorage<T> {
+    fn default() -> Self {
+        Self {
+            data: Vec::new(),
+            map: HashMap::new(),
+        }
+    }
+}
+
+/// A set and every set it combines, through cards like MPCADD and SPCADD
+fn combined_sets<'a>(sid: u32, combines: impl Fn(u32) -> Option<&'a [u32]>) -> Vec<u32> {
+    let mut sets = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![sid];
+    while let Some(sid) = pending.pop() {
+        if !visited.insert(sid) {
+            continue;
+        }
+        sets.push(sid);
+        pending.extend(combines(sid).into_iter().flatten().rev());
+    }
+    sets
+}
+
 pub struct GlobalLocation {
     xyz: HashMap<u32, Xyz>,
     csys: HashMap<u32, CoordSys>,
@@ -527,7 +637,7 @@ impl GlobalLocation {
 
 #[derive(Clone)]
 struct DeckRef<'a, T> {
-    deck: &'a Deck,
+    bulk: &'a BulkData,
     item: &'a T,
 }
 
@@ -544,47 +654,44 @@ trait HasProperty<'a> {
     fn property(&'a self) -> Option<DeckRef<'a, Self::Property>>;
 }
 
-impl<'a> HasProperty<'a> for DeckRef<'a, CTETRA> {
-    type Property = PSOLID;
+/// An element that refers to a property
+trait Element {
+    type Property: Property;
+    fn pid(&self) -> u32;
+}
 
-    fn property(&'a self) -> Option<DeckRef<'a, Self::Property>> {
-        self.deck.psolid(self.item.pid)
-    }
+/// A property, which is looked up in the same partition as the element.
+/// Some can also be given by other cards, like a PBAR by a PBARL
+trait Property: StorageItem<Id = u32> {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self>;
 }
 
-trait HasDensity {
-    fn density(&self) -> f64;
+impl<'a, T: Element> HasProperty<'a> for DeckRef<'a, T> {
+    type Property = T::Property;
+
+    fn property(&'a self) -> Option<DeckRef<'a, Self::Property>> {
+        T::Property::get(self.bulk, self.item.pid()).map(|p| self.bulk.with(p))
+    }
 }
 
-impl HasDensity for MAT1 {
-    fn density(&self) -> f64 {
-        self.rho
+impl Property for PSOLID {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.psolid.get(id)
     }
 }
 
+/// A property that refers to a material, which can be of any type
 trait HasMaterial<'a> {
-    type Material: HasDensity;
-
-    fn material(&'a self) -> Option<DeckRef<'a, Self::Material>>;
+    fn material(&'a self) -> Option<Material<'a>>;
 
     fn density(&'a self) -> Option<f64> {
-        self.material().map(|r| r.density())
+        self.material().map(|m| m.density())
     }
 }
 
 impl<'a> HasMaterial<'a> for DeckRef<'a, PSOLID> {
-    type Material = MAT1;
-
-    fn material(&self) -> Option<DeckRef<Self::Material>> {
-        self.deck.mat1(self.item.mid)
-    }
-}
-
-impl<'a> HasMaterial<'a> for DeckRef<'a, CTETRA> {
-    type Material = MAT1;
-
-    fn material(&self) -> Option<DeckRef<Self::Material>> {
-        self.property().and_then(|p| self.deck.mat1(p.id()))
+    fn material(&self) -> Option<Material<'_>> {
+        self.bulk.get_material(self.item.mid)
     }
 }
 
@@ -598,6 +705,11 @@ impl<T> RawStorage<T> {
     fn insert(&mut self, value: T) {
         self.data.push(Some(value))
     }
+
+    #[allow(dead_code)]
+    fn insert_all(&mut self, values: impl IntoIterator<Item = T>) {
+        self.data.extend(values.into_iter().map(Some))
+    }
 }
 
 impl<T> Default for RawStorage<T> {
@@ -612,110 +724,991 @@ pub struct DeckCounts {
     cord2r: usize,
     psolid: usize,
     mat1: usize,
+    mat2: usize,
+    mat8: usize,
+    mat9: usize,
+    mat10: usize,
+    matt1: usize,
+    matt8: usize,
+    tablem1: usize,
+    tablem2: usize,
+    tablem3: usize,
+    tablem4: usize,
     ctetra: usize,
+    cpenta: usize,
+    cpyram: usize,
+    chexa: usize,
+    cquad4: usize,
+    cquadr: usize,
+    ctria3: usize,
+    ctriar: usize,
+    cquad8: usize,
+    ctria6: usize,
+    pshell: usize,
+    pcomp: usize,
+    pcompg: usize,
+    cbar: usize,
+    cbeam: usize,
+    crod: usize,
+    conrod: usize,
+    ctube: usize,
+    pbar: usize,
+    pbeam: usize,
+    prod: usize,
+    ptube: usize,
+    pbarl: usize,
+    pbeaml: usize,
+    conm1: usize,
+    conm2: usize,
+    celas1: usize,
+    celas2: usize,
+    celas3: usize,
+    celas4: usize,
+    pelas: usize,
+    cdamp1: usize,
+    cdamp2: usize,
+    cdamp3: usize,
+    cdamp4: usize,
+    pdamp: usize,
+    cmass1: usize,
+    cmass2: usize,
+    cmass3: usize,
+    cmass4: usize,
+    pmass: usize,
+    cbush: usize,
+    pbush: usize,
+    rbar: usize,
+    rbe1: usize,
+    rbe2: usize,
+    rbe3: usize,
+    rrod: usize,
+    mpc: usize,
+    mpcadd: usize,
+    grdset: usize,
+    spc: usize,
+    spc1: usize,
+    spcadd: usize,
+    spcd: usize,
+    force: usize,
+    force1: usize,
+    force2: usize,
+    moment: usize,
+    moment1: usize,
+    moment2: usize,
+    pload: usize,
+    pload2: usize,
+    pload4: usize,
+    grav: usize,
+    rforce: usize,
+    load: usize,
 }
 
 #[derive(Debug, Default)]
-pub struct RawDeck {
+pub struct RawBulkData {
     grid: RawStorage<GRID>,
     cord2r: RawStorage<CORD2R>,
     psolid: RawStorage<PSOLID>,
     mat1: RawStorage<MAT1>,
+    mat2: RawStorage<MAT2>,
+    mat8: RawStorage<MAT8>,
+    mat9: RawStorage<MAT9>,
+    mat10: RawStorage<MAT10>,
+    matt1: RawStorage<MATT1>,
+    matt8: RawStorage<MATT8>,
+    tablem1: RawStorage<TABLEM1>,
+    tablem2: RawStorage<TABLEM2>,
+    tablem3: RawStorage<TABLEM3>,
+    tablem4: RawStorage<TABLEM4>,
     ctetra: RawStorage<CTETRA>,
+    cpenta: RawStorage<CPENTA>,
+    cpyram: RawStorage<CPYRAM>,
+    chexa: RawStorage<CHEXA>,
+    cquad4: RawStorage<CQUAD4>,
+    cquadr: RawStorage<CQUADR>,
+    ctria3: RawStorage<CTRIA3>,
+    ctriar: RawStorage<CTRIAR>,
+    cquad8: RawStorage<CQUAD8>,
+    ctria6: RawStorage<CTRIA6>,
+    pshell: RawStorage<PSHELL>,
+    pcomp: RawStorage<PCOMP>,
+    pcompg: RawStorage<PCOMPG>,
+    cbar: RawStorage<CBAR>,
+    cbeam: RawStorage<CBEAM>,
+    crod: RawStorage<CROD>,
+    conrod: RawStorage<CONROD>,
+    ctube: RawStorage<CTUBE>,
+    pbar: RawStorage<PBAR>,
+    pbeam: RawStorage<PBEAM>,
+    prod: RawStorage<PROD>,
+    ptube: RawStorage<PTUBE>,
+    pbarl: RawStorage<PBARL>,
+    pbeaml: RawStorage<PBEAML>,
+    conm1: RawStorage<CONM1>,
+    conm2: RawStorage<CONM2>,
+    celas1: RawStorage<CELAS1>,
+    celas2: RawStorage<CELAS2>,
+    celas3: RawStorage<CELAS3>,
+    celas4: RawStorage<CELAS4>,
+    pelas: RawStorage<PELAS>,
+    cdamp1: RawStorage<CDAMP1>,
+    cdamp2: RawStorage<CDAMP2>,
+    cdamp3: RawStorage<CDAMP3>,
+    cdamp4: RawStorage<CDAMP4>,
+    pdamp: RawStorage<PDAMP>,
+    cmass1: RawStorage<CMASS1>,
+    cmass2: RawStorage<CMASS2>,
+    cmass3: RawStorage<CMASS3>,
+    cmass4: RawStorage<CMASS4>,
+    pmass: RawStorage<PMASS>,
+    cbush: RawStorage<CBUSH>,
+    pbush: RawStorage<PBUSH>,
+    rbar: RawStorage<RBAR>,
+    rbe1: RawStorage<RBE1>,
+    rbe2: RawStorage<RBE2>,
+    rbe3: RawStorage<RBE3>,
+    rrod: RawStorage<RROD>,
+    mpc: RawStorage<MPC>,
+    mpcadd: RawStorage<MPCADD>,
+    grdset: RawStorage<GRDSET>,
+    spc: RawStorage<SPC>,
+    spc1: RawStorage<SPC1>,
+    spcadd: RawStorage<SPCADD>,
+    spcd: RawStorage<SPCD>,
+    force: RawStorage<FORCE>,
+    force1: RawStorage<FORCE1>,
+    force2: RawStorage<FORCE2>,
+    moment: RawStorage<MOMENT>,
+    moment1: RawStorage<MOMENT1>,
+    moment2: RawStorage<MOMENT2>,
+    pload: RawStorage<PLOAD>,
+    pload2: RawStorage<PLOAD2>,
+    pload4: RawStorage<PLOAD4>,
+    grav: RawStorage<GRAV>,
+    rforce: RawStorage<RFORCE>,
+    load: RawStorage<LOAD>,
+}
+
+impl RawBulkData {
+    /// Like `BulkData::insert_card` but without checking for duplicates
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    fn insert_card(&mut self, card: &BulkCard) -> Result<bool> {
+        match card.card_type().as_ref() {
+            Some(b"GRID   ") => self.grid.insert(convert(card)?),
+            Some(b"CORD2R ") => self.cord2r.insert(convert(card)?),
+            Some(b"PSOLID ") => self.psolid.insert(convert(card)?),
+            Some(b"MAT1   ") => self.mat1.insert(convert(card)?),
+            Some(b"MAT2   ") => self.mat2.insert(convert(card)?),
+            Some(b"MAT8   ") => self.mat8.insert(convert(card)?),
+            Some(b"MAT9   ") => self.mat9.insert(convert(card)?),
+            Some(b"MAT10  ") => self.mat10.insert(convert(card)?),
+            Some(b"MATT1  ") => self.matt1.insert(convert(card)?),
+            Some(b"MATT8  ") => self.matt8.insert(convert(card)?),
+            Some(b"TABLEM1") => self.tablem1.insert(convert(card)?),
+            Some(b"TABLEM2") => self.tablem2.insert(convert(card)?),
+            Some(b"TABLEM3") => self.tablem3.insert(convert(card)?),
+            Some(b"TABLEM4") => self.tablem4.insert(convert(card)?),
+            Some(b"CTETRA ") => self.ctetra.insert(convert(card)?),
+            Some(b"CPENTA ") => self.cpenta.insert(convert(card)?),
+            Some(b"CPYRAM ") => self.cpyram.insert(convert(card)?),
+            Some(b"CHEXA  ") => self.chexa.insert(convert(card)?),
+            Some(b"CQUAD4 ") => self.cquad4.insert(convert(card)?),
+            Some(b"CQUADR ") => self.cquadr.insert(convert(card)?),
+            Some(b"CTRIA3 ") => self.ctria3.insert(convert(card)?),
+            Some(b"CTRIAR ") => self.ctriar.insert(convert(card)?),
+            Some(b"CQUAD8 ") => self.cquad8.insert(convert(card)?),
+            Some(b"CTRIA6 ") => self.ctria6.insert(convert(card)?),
+            Some(b"PSHELL ") => self.pshell.insert(convert(card)?),
+            Some(b"PCOMP  ") => self.pcomp.insert(convert(card)?),
+            Some(b"PCOMPG ") => self.pcompg.insert(convert(card)?),
+            Some(b"CBAR   ") => self.cbar.insert(convert(card)?),
+            Some(b"CBEAM  ") => self.cbeam.insert(convert(card)?),
+            Some(b"CROD   ") => self.crod.insert(convert(card)?),
+            Some(b"CONROD ") => self.conrod.insert(convert(card)?),
+            Some(b"CTUBE  ") => self.ctube.insert(convert(card)?),
+            Some(b"PBAR   ") => self.pbar.insert(convert(card)?),
+            Some(b"PBEAM  ") => self.pbeam.insert(convert(card)?),
+            Some(b"PROD   ") => self.prod.insert(convert(card)?),
+            Some(b"PTUBE  ") => self.ptube.insert(convert(card)?),
+            Some(b"PBARL  ") => self.pbarl.insert(convert(card)?),
+            Some(b"PBEAML ") => self.pbeaml.insert(convert(card)?),
+            Some(b"CONM1  ") => self.conm1.insert(convert(card)?),
+            Some(b"CONM2  ") => self.conm2.insert(convert(card)?),
+            Some(b"CELAS1 ") => self.celas1.insert(convert(card)?),
+            Some(b"CELAS2 ") => self.celas2.insert(convert(card)?),
+            Some(b"CELAS3 ") => self.celas3.insert(convert(card)?),
+            Some(b"CELAS4 ") => self.celas4.insert(convert(card)?),
+            Some(b"PELAS  ") => self.pelas.insert_all(convert::<Vec<_>>(card)?),
+            Some(b"CDAMP1 ") => self.cdamp1.insert(convert(card)?),
+            Some(b"CDAMP2 ") => self.cdamp2.insert(convert(card)?),
+            Some(b"CDAMP3 ") => self.cdamp3.insert(convert(card)?),
+            Some(b"CDAMP4 ") => self.cdamp4.insert(convert(card)?),
+            Some(b"PDAMP  ") => self.pdamp.insert_all(convert::<Vec<_>>(card)?),
+            Some(b"CMASS1 ") => self.cmass1.insert(convert(card)?),
+            Some(b"CMASS2 ") => self.cmass2.insert(convert(card)?),
+            Some(b"CMASS3 ") => self.cmass3.insert(convert(card)?),
+            Some(b"CMASS4 ") => self.cmass4.insert(convert(card)?),
+            Some(b"PMASS  ") => self.pmass.insert_all(convert::<Vec<_>>(card)?),
+            Some(b"CBUSH  ") => self.cbush.insert(convert(card)?),
+            Some(b"PBUSH  ") => self.pbush.insert(convert(card)?),
+            Some(b"RBAR   ") => self.rbar.insert(convert(card)?),
+            Some(b"RBE1   ") => self.rbe1.insert(convert(card)?),
+            Some(b"RBE2   ") => self.rbe2.insert(convert(card)?),
+            Some(b"RBE3   ") => self.rbe3.insert(convert(card)?),
+            Some(b"RROD   ") => self.rrod.insert(convert(card)?),
+            Some(b"MPC    ") => self.mpc.insert(convert(card)?),
+            Some(b"MPCADD ") => self.mpcadd.insert(convert(card)?),
+            Some(b"GRDSET ") => self.grdset.insert(convert(card)?),
+            Some(b"SPC    ") => self.spc.insert(convert(card)?),
+            Some(b"SPC1   ") => self.spc1.insert(convert(card)?),
+            Some(b"SPCADD ") => self.spcadd.insert(convert(card)?),
+            Some(b"SPCD   ") => self.spcd.insert(convert(card)?),
+            Some(b"FORCE  ") => self.force.insert(convert(card)?),
+            Some(b"FORCE1 ") => self.force1.insert(convert(card)?),
+            Some(b"FORCE2 ") => self.force2.insert(convert(card)?),
+            Some(b"MOMENT ") => self.moment.insert(convert(card)?),
+            Some(b"MOMENT1") => self.moment1.insert(convert(card)?),
+            Some(b"MOMENT2") => self.moment2.insert(convert(card)?),
+            Some(b"PLOAD  ") => self.pload.insert(convert(card)?),
+            Some(b"PLOAD2 ") => self.pload2.insert(convert(card)?),
+            Some(b"PLOAD4 ") => self.pload4.insert(convert(card)?),
+            Some(b"GRAV   ") => self.grav.insert(convert(card)?),
+            Some(b"RFORCE ") => self.rforce.insert(convert(card)?),
+            Some(b"LOAD   ") => self.load.insert(convert(card)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
 }
 
-impl From<RawDeck> for Deck {
-    fn from(other: RawDeck) -> Deck {
-        Deck {
+impl From<RawBulkData> for BulkData {
+    fn from(other: RawBulkData) -> BulkData {
+        BulkData {
             grid: other.grid.into(),
             cord2r: other.cord2r.into(),
             psolid: other.psolid.into(),
             mat1: other.mat1.into(),
+            mat2: other.mat2.into(),
+            mat8: other.mat8.into(),
+            mat9: other.mat9.into(),
+            mat10: other.mat10.into(),
+            matt1: other.matt1.into(),
+            matt8: other.matt8.into(),
+            tablem1: other.tablem1.into(),
+            tablem2: other.tablem2.into(),
+            tablem3: other.tablem3.into(),
+            tablem4: other.tablem4.into(),
             ctetra: other.ctetra.into(),
+            cpenta: other.cpenta.into(),
+            cpyram: other.cpyram.into(),
+            chexa: other.chexa.into(),
+            cquad4: other.cquad4.into(),
+            cquadr: other.cquadr.into(),
+            ctria3: other.ctria3.into(),
+            ctriar: other.ctriar.into(),
+            cquad8: other.cquad8.into(),
+            ctria6: other.ctria6.into(),
+            pshell: other.pshell.into(),
+            pcomp: other.pcomp.into(),
+            pcompg: other.pcompg.into(),
+            cbar: other.cbar.into(),
+            cbeam: other.cbeam.into(),
+            crod: other.crod.into(),
+            conrod: other.conrod.into(),
+            ctube: other.ctube.into(),
+            pbar: other.pbar.into(),
+            pbeam: other.pbeam.into(),
+            prod: other.prod.into(),
+            ptube: other.ptube.into(),
+            pbarl: other.pbarl.into(),
+            pbeaml: other.pbeaml.into(),
+            conm1: other.conm1.into(),
+            conm2: other.conm2.into(),
+            celas1: other.celas1.into(),
+            celas2: other.celas2.into(),
+            celas3: other.celas3.into(),
+            celas4: other.celas4.into(),
+            pelas: other.pelas.into(),
+            cdamp1: other.cdamp1.into(),
+            cdamp2: other.cdamp2.into(),
+            cdamp3: other.cdamp3.into(),
+            cdamp4: other.cdamp4.into(),
+            pdamp: other.pdamp.into(),
+            cmass1: other.cmass1.into(),
+            cmass2: other.cmass2.into(),
+            cmass3: other.cmass3.into(),
+            cmass4: other.cmass4.into(),
+            pmass: other.pmass.into(),
+            cbush: other.cbush.into(),
+            pbush: other.pbush.into(),
+            rbar: other.rbar.into(),
+            rbe1: other.rbe1.into(),
+            rbe2: other.rbe2.into(),
+            rbe3: other.rbe3.into(),
+            rrod: other.rrod.into(),
+            mpc: other.mpc.into(),
+            mpcadd: other.mpcadd.into(),
+            grdset: other.grdset.into(),
+            spc: other.spc.into(),
+            spc1: other.spc1.into(),
+            spcadd: other.spcadd.into(),
+            spcd: other.spcd.into(),
+            force: other.force.into(),
+            force1: other.force1.into(),
+            force2: other.force2.into(),
+            moment: other.moment.into(),
+            moment1: other.moment1.into(),
+            moment2: other.moment2.into(),
+            pload: other.pload.into(),
+            pload2: other.pload2.into(),
+            pload4: other.pload4.into(),
+            grav: other.grav.into(),
+            rforce: other.rforce.into(),
+            load: other.load.into(),
         }
     }
 }
 
+/// The cards of one partition of the bulk data. IDs only need to be unique
+/// within a partition, and cards only refer to cards in the same partition
 #[derive(Debug, Default)]
-pub struct Deck {
+pub struct BulkData {
     grid: Storage<GRID>,
     cord2r: Storage<CORD2R>,
     psolid: Storage<PSOLID>,
     mat1: Storage<MAT1>,
+    mat2: Storage<MAT2>,
+    mat8: Storage<MAT8>,
+    mat9: Storage<MAT9>,
+    mat10: Storage<MAT10>,
+    matt1: Storage<MATT1>,
+    matt8: Storage<MATT8>,
+    tablem1: Storage<TABLEM1>,
+    tablem2: Storage<TABLEM2>,
+    tablem3: Storage<TABLEM3>,
+    tablem4: Storage<TABLEM4>,
     ctetra: Storage<CTETRA>,
+    cpenta: Storage<CPENTA>,
+    cpyram: Storage<CPYRAM>,
+    chexa: Storage<CHEXA>,
+    cquad4: Storage<CQUAD4>,
+    cquadr: Storage<CQUADR>,
+    ctria3: Storage<CTRIA3>,
+    ctriar: Storage<CTRIAR>,
+    cquad8: Storage<CQUAD8>,
+    ctria6: Storage<CTRIA6>,
+    pshell: Storage<PSHELL>,
+    pcomp: Storage<PCOMP>,
+    pcompg: Storage<PCOMPG>,
+    cbar: Storage<CBAR>,
+    cbeam: Storage<CBEAM>,
+    crod: Storage<CROD>,
+    conrod: Storage<CONROD>,
+    ctube: Storage<CTUBE>,
+    pbar: Storage<PBAR>,
+    pbeam: Storage<PBEAM>,
+    prod: Storage<PROD>,
+    ptube: Storage<PTUBE>,
+    pbarl: Storage<PBARL>,
+    pbeaml: Storage<PBEAML>,
+    conm1: Storage<CONM1>,
+    conm2: Storage<CONM2>,
+    celas1: Storage<CELAS1>,
+    celas2: Storage<CELAS2>,
+    celas3: Storage<CELAS3>,
+    celas4: Storage<CELAS4>,
+    pelas: Storage<PELAS>,
+    cdamp1: Storage<CDAMP1>,
+    cdamp2: Storage<CDAMP2>,
+    cdamp3: Storage<CDAMP3>,
+    cdamp4: Storage<CDAMP4>,
+    pdamp: Storage<PDAMP>,
+    cmass1: Storage<CMASS1>,
+    cmass2: Storage<CMASS2>,
+    cmass3: Storage<CMASS3>,
+    cmass4: Storage<CMASS4>,
+    pmass: Storage<PMASS>,
+    cbush: Storage<CBUSH>,
+    pbush: Storage<PBUSH>,
+    rbar: Storage<RBAR>,
+    rbe1: Storage<RBE1>,
+    rbe2: Storage<RBE2>,
+    rbe3: Storage<RBE3>,
+    rrod: Storage<RROD>,
+    mpc: SetStorage<MPC>,
+    mpcadd: Storage<MPCADD>,
+    grdset: Storage<GRDSET>,
+    spc: SetStorage<SPC>,
+    spc1: SetStorage<SPC1>,
+    spcadd: Storage<SPCADD>,
+    spcd: SetStorage<SPCD>,
+    force: SetStorage<FORCE>,
+    force1: SetStorage<FORCE1>,
+    force2: SetStorage<FORCE2>,
+    moment: SetStorage<MOMENT>,
+    moment1: SetStorage<MOMENT1>,
+    moment2: SetStorage<MOMENT2>,
+    pload: SetStorage<PLOAD>,
+    pload2: SetStorage<PLOAD2>,
+    pload4: SetStorage<PLOAD4>,
+    grav: SetStorage<GRAV>,
+    rforce: SetStorage<RFORCE>,
+    load: Storage<LOAD>,
+}
+
+/// A model read from bulk data, with the cards of the main bulk data and of
+/// each `BEGIN SUPER` or `BEGIN BULK AUXMODEL` partition kept apart
+#[derive(Debug)]
+pub struct Deck {
+    partitions: BTreeMap<Partition, BulkData>,
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        let mut partitions = BTreeMap::new();
+        partitions.insert(Partition::Main, BulkData::default());
+        Self { partitions }
+    }
 }
 
 impl Deck {
-    #[cfg(feature = "parallel")]
     pub fn from_filename(filename: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::from_cards(parse_file(filename)?)
+    }
+
+    /// Like `from_filename` but with settings for how lines are read
+    pub fn from_filename_with(
+        filename: impl AsRef<std::path::Path>,
+        options: &ParserOptions,
+    ) -> Result<Self> {
+        Self::from_cards(parse_file_with(filename, options)?)
+    }
+
+    /// Builds a deck from bulk data held in memory
+    pub fn from_buffer(buffer: &[u8]) -> Result<Self> {
+        Self::from_cards(parse_buffer(buffer)?)
+    }
+
+    /// Like `from_buffer` but with settings for how lines are read
+    pub fn from_buffer_with(buffer: &[u8], options: &ParserOptions) -> Result<Self> {
+        Self::from_cards(parse_buffer_with(buffer, options)?)
+    }
+
+    /// Builds a deck from bulk data read from any reader
+    pub fn from_reader(reader: impl io::Read) -> Result<Self> {
+        Self::from_reader_with(reader, &ParserOptions::default())
+    }
+
+    /// Like `from_reader` but with settings for how lines are read
+    pub fn from_reader_with(reader: impl io::Read, options: &ParserOptions) -> Result<Self> {
+        Self::from_cards(parse_reader_with(reader, options)?)
+    }
+
+    #[cfg(feature = "parallel")]
+    fn from_cards(cards: impl Iterator<Item = Result<BulkCard>> + Send) -> Result<Self> {
         use rayon::prelude::*;
-        let decks = parse_file(filename)?
+        let decks = cards
             .par_bridge()
             .into_par_iter()
-            .try_fold(RawDeck::default, |mut deck, card| -> Result<RawDeck> {
-                // This should be ordered by most common card type. Or maybe using a regexset or something
-                let card = card?;
-                match card.card_type().as_ref() {
-                    Some(b"GRID   ") => deck.grid.insert(card.try_into()?),
-                    Some(b"CORD2R ") => deck.cord2r.insert(card.try_into()?),
-                    Some(b"PSOLID ") => deck.psolid.insert(card.try_into()?),
-                    Some(b"MAT1   ") => deck.mat1.insert(card.try_into()?),
-                    Some(b"CTETRA ") => deck.ctetra.insert(card.try_into()?),
-                    _ => {}
-                }
-                Ok(deck)
-            })
+            .try_fold(
+                BTreeMap::<Partition, RawBulkData>::new,
+                |mut decks, card| -> Result<_> {
+                    let card = card?;
+                    decks
+                        .entry(card.partition())
+                        .or_default()
+                        .insert_card(&card)?;
+                    Ok(decks)
+                },
+            )
             .collect::<Result<Vec<_>>>()?;
-        let counts = decks
-            .iter()
-            .fold(DeckCounts::default(), |mut counts, item| {
-                counts.grid += item.grid.data.len();
-                counts.cord2r += item.cord2r.data.len();
-                counts.psolid += item.psolid.data.len();
-                counts.mat1 += item.mat1.data.len();
-                counts.ctetra += item.ctetra.data.len();
-                counts
-            });
+        let mut counts = BTreeMap::<Partition, DeckCounts>::new();
+        for (partition, item) in decks.iter().flatten() {
+            let counts = counts.entry(*partition).or_default();
+            counts.grid += item.grid.data.len();
+            counts.cord2r += item.cord2r.data.len();
+            counts.psolid += item.psolid.data.len();
+            counts.mat1 += item.mat1.data.len();
+            counts.mat2 += item.mat2.data.len();
+            counts.mat8 += item.mat8.data.len();
+            counts.mat9 += item.mat9.data.len();
+            counts.mat10 += item.mat10.data.len();
+            counts.matt1 += item.matt1.data.len();
+            counts.matt8 += item.matt8.data.len();
+            counts.tablem1 += item.tablem1.data.len();
+            counts.tablem2 += item.tablem2.data.len();
+            counts.tablem3 += item.tablem3.data.len();
+            counts.tablem4 += item.tablem4.data.len();
+            counts.ctetra += item.ctetra.data.len();
+            counts.cpenta += item.cpenta.data.len();
+            counts.cpyram += item.cpyram.data.len();
+            counts.chexa += item.chexa.data.len();
+            counts.cquad4 += item.cquad4.data.len();
+            counts.cquadr += item.cquadr.data.len();
+            counts.ctria3 += item.ctria3.data.len();
+            counts.ctriar += item.ctriar.data.len();
+            counts.cquad8 += item.cquad8.data.len();
+            counts.ctria6 += item.ctria6.data.len();
+            counts.pshell += item.pshell.data.len();
+            counts.pcomp += item.pcomp.data.len();
+            counts.pcompg += item.pcompg.data.len();
+            counts.cbar += item.cbar.data.len();
+            counts.cbeam += item.cbeam.data.len();
+            counts.crod += item.crod.data.len();
+            counts.conrod += item.conrod.data.len();
+            counts.ctube += item.ctube.data.len();
+            counts.pbar += item.pbar.data.len();
+            counts.pbeam += item.pbeam.data.len();
+            counts.prod += item.prod.data.len();
+            counts.ptube += item.ptube.data.len();
+            counts.pbarl += item.pbarl.data.len();
+            counts.pbeaml += item.pbeaml.data.len();
+            counts.conm1 += item.conm1.data.len();
+            counts.conm2 += item.conm2.data.len();
+            counts.celas1 += item.celas1.data.len();
+            counts.celas2 += item.celas2.data.len();
+            counts.celas3 += item.celas3.data.len();
+            counts.celas4 += item.celas4.data.len();
+            counts.pelas += item.pelas.data.len();
+            counts.cdamp1 += item.cdamp1.data.len();
+            counts.cdamp2 += item.cdamp2.data.len();
+            counts.cdamp3 += item.cdamp3.data.len();
+            counts.cdamp4 += item.cdamp4.data.len();
+            counts.pdamp += item.pdamp.data.len();
+            counts.cmass1 += item.cmass1.data.len();
+            counts.cmass2 += item.cmass2.data.len();
+            counts.cmass3 += item.cmass3.data.len();
+            counts.cmass4 += item.cmass4.data.len();
+            counts.pmass += item.pmass.data.len();
+            counts.cbush += item.cbush.data.len();
+            counts.pbush += item.pbush.data.len();
+            counts.rbar += item.rbar.data.len();
+            counts.rbe1 += item.rbe1.data.len();
+            counts.rbe2 += item.rbe2.data.len();
+            counts.rbe3 += item.rbe3.data.len();
+            counts.rrod += item.rrod.data.len();
+            counts.mpc += item.mpc.data.len();
+            counts.mpcadd += item.mpcadd.data.len();
+            counts.grdset += item.grdset.data.len();
+            counts.spc += item.spc.data.len();
+            counts.spc1 += item.spc1.data.len();
+            counts.spcadd += item.spcadd.data.len();
+            counts.spcd += item.spcd.data.len();
+            counts.force += item.force.data.len();
+            counts.force1 += item.force1.data.len();
+            counts.force2 += item.force2.data.len();
+            counts.moment += item.moment.data.len();
+            counts.moment1 += item.moment1.data.len();
+            counts.moment2 += item.moment2.data.len();
+            counts.pload += item.pload.data.len();
+            counts.pload2 += item.pload2.data.len();
+            counts.pload4 += item.pload4.data.len();
+            counts.grav += item.grav.data.len();
+            counts.rforce += item.rforce.data.len();
+            counts.load += item.load.data.len();
+        }
+        let mut deck = Deck::default();
+        for (partition, counts) in counts {
+            deck.partitions
+                .insert(partition, BulkData::with_capacity(counts));
+        }
+        for (partition, item) in decks.into_iter().flatten() {
+            deck.partition_mut(partition).extend_from_raw(item)?;
+        }
+        Ok(deck)
+    }
 
-        decks
+    #[cfg(not(feature = "parallel"))]
+    fn from_cards(cards: impl Iterator<Item = Result<BulkCard>>) -> Result<Self> {
+        cards
             .into_iter()
-            .try_fold(Deck::with_capacity(counts), |mut deck, item| {
-                deck.grid.extend_from_raw(item.grid)?;
-                deck.cord2r.extend_from_raw(item.cord2r)?;
-                deck.psolid.extend_from_raw(item.psolid)?;
-                deck.mat1.extend_from_raw(item.mat1)?;
-                deck.ctetra.extend_from_raw(item.ctetra)?;
+            .try_fold(Deck::default(), |mut deck, card| {
+                let card = card?;
+                deck.partition_mut(card.partition()).insert_card(&card)?;
                 Ok(deck)
             })
     }
 
+    /// Builds a deck without stopping at the first error. Cards that can't be
+    /// parsed or converted are skipped and reported as diagnostics, along with
+    /// a warning for each card type that isn't supported
+    pub fn from_filename_lenient(
+        filename: impl AsRef<std::path::Path>,
+    ) -> Result<(Self, Vec<Diagnostic>)> {
+        Self::from_filename_lenient_with(filename, &ParserOptions::default())
+    }
+
+    /// Like `from_filename_lenient` but with settings for how lines are read
+    pub fn from_filename_lenient_with(
+        filename: impl AsRef<std::path::Path>,
+        options: &ParserOptions,
+    ) -> Result<(Self, Vec<Diagnostic>)> {
+        Ok(Self::from_report(parse_file_lenient_with(
+            filename, options,
+        )?))
+    }
+
+    /// Builds a deck from bulk data held in memory without stopping at the
+    /// first error, like `from_filename_lenient`
+    pub fn from_buffer_lenient(buffer: &[u8]) -> Result<(Self, Vec<Diagnostic>)> {
+        Self::from_buffer_lenient_with(buffer, &ParserOptions::default())
+    }
+
+    /// Like `from_buffer_lenient` but with settings for how lines are read
+    pub fn from_buffer_lenient_with(
+        buffer: &[u8],
+        options: &ParserOptions,
+    ) -> Result<(Self, Vec<Diagnostic>)> {
+        Ok(Self::from_report(parse_buffer_lenient_with(
+            buffer, options,
+        )?))
+    }
+
+    /// Builds a deck from bulk data read from any reader without stopping at
+    /// the first error, like `from_filename_lenient`
+    pub fn from_reader_lenient(reader: impl io::Read) -> Result<(Self, Vec<Diagnostic>)> {
+        Self::from_reader_lenient_with(reader, &ParserOptions::default())
+    }
+
+    /// Like `from_reader_lenient` but with settings for how lines are read
+    pub fn from_reader_lenient_with(
+        reader: impl io::Read,
+        options: &ParserOptions,
+    ) -> Result<(Self, Vec<Diagnostic>)> {
+        Ok(Self::from_report(parse_reader_lenient_with(
+            reader, options,
+        )?))
+    }
+
+    fn from_report(report: ParseReport) -> (Self, Vec<Diagnostic>) {
+        let ParseReport {
+            cards,
+            mut diagnostics,
+            ..
+        } = report;
+        let mut deck = Deck::default();
+        let mut unsupported = HashSet::new();
+        for card in cards {
+            match deck.partition_mut(card.partition()).insert_card(&card) {
+                Ok(true) => {}
+                Ok(false) => {
+                    if let Some(card_type) = card.card_type() {
+                        if unsupported.insert(card_type) {
+                            let warning = Error::UnsupportedCard(card_type).at(card.location());
+                            diagnostics.push(Diagnostic::warning(warning));
+                        }
+                    }
+                }
+                Err(e) => diagnostics.push(Diagnostic::error(e.at(card.location()))),
+            }
+        }
+        (deck, diagnostics)
+    }
+
+    /// The cards of the main bulk data, which is the residual structure when
+    /// the model has superelements
+    pub fn main(&self) -> &BulkData {
+        &self.partitions[&Partition::Main]
+    }
+
+    pub fn partition(&self, partition: Partition) -> Option<&BulkData> {
+        self.partitions.get(&partition)
+    }
+
+    /// The cards of a superelement, where 0 is the main bulk data
+    pub fn superelement(&self, seid: u32) -> Option<&BulkData> {
+        match seid {
+            0 => Some(self.main()),
+            seid => self.partition(Partition::Super(seid)),
+        }
+    }
+
+    /// Every partition, starting with the main bulk data
+    pub fn partitions(&self) -> impl Iterator<Item = (Partition, &BulkData)> {
+        self.partitions.iter().map(|(p, bulk)| (*p, bulk))
+    }
+
+    fn partition_mut(&mut self, partition: Partition) -> &mut BulkData {
+        self.partitions.entry(partition).or_default()
+    }
+
+    /// The global locations of the grids of the main bulk data
+    pub fn global_locations(&self) -> GlobalLocation {
+        self.main().global_locations()
+    }
+
+    /// The mass of the main bulk data
+    pub fn mass(&self, location: &GlobalLocation) -> f64 {
+        self.main().mass(location)
+    }
+
+    /// The mass and center of gravity of the main bulk data
+    pub fn mass_cg(&self, location: &GlobalLocation) -> (f64, Vec3) {
+        self.main().mass_cg(location)
+    }
+}
+
+impl BulkData {
     pub fn with_capacity(counts: DeckCounts) -> Self {
         Self {
             grid: Storage::with_capacity(counts.grid),
             cord2r: Storage::with_capacity(counts.cord2r),
             psolid: Storage::with_capacity(counts.psolid),
             mat1: Storage::with_capacity(counts.mat1),
+            mat2: Storage::with_capacity(counts.mat2),
+            mat8: Storage::with_capacity(counts.mat8),
+            mat9: Storage::with_capacity(counts.mat9),
+            mat10: Storage::with_capacity(counts.mat10),
+            matt1: Storage::with_capacity(counts.matt1),
+            matt8: Storage::with_capacity(counts.matt8),
+            tablem1: Storage::with_capacity(counts.tablem1),
+            tablem2: Storage::with_capacity(counts.tablem2),
+            tablem3: Storage::with_capacity(counts.tablem3),
+            tablem4: Storage::with_capacity(counts.tablem4),
             ctetra: Storage::with_capacity(counts.ctetra),
+            cpenta: Storage::with_capacity(counts.cpenta),
+            cpyram: Storage::with_capacity(counts.cpyram),
+            chexa: Storage::with_capacity(counts.chexa),
+            cquad4: Storage::with_capacity(counts.cquad4),
+            cquadr: Storage::with_capacity(counts.cquadr),
+            ctria3: Storage::with_capacity(counts.ctria3),
+            ctriar: Storage::with_capacity(counts.ctriar),
+            cquad8: Storage::with_capacity(counts.cquad8),
+            ctria6: Storage::with_capacity(counts.ctria6),
+            pshell: Storage::with_capacity(counts.pshell),
+            pcomp: Storage::with_capacity(counts.pcomp),
+            pcompg: Storage::with_capacity(counts.pcompg),
+            cbar: Storage::with_capacity(counts.cbar),
+            cbeam: Storage::with_capacity(counts.cbeam),
+            crod: Storage::with_capacity(counts.crod),
+            conrod: Storage::with_capacity(counts.conrod),
+            ctube: Storage::with_capacity(counts.ctube),
+            pbar: Storage::with_capacity(counts.pbar),
+            pbeam: Storage::with_capacity(counts.pbeam),
+            prod: Storage::with_capacity(counts.prod),
+            ptube: Storage::with_capacity(counts.ptube),
+            pbarl: Storage::with_capacity(counts.pbarl),
+            pbeaml: Storage::with_capacity(counts.pbeaml),
+            conm1: Storage::with_capacity(counts.conm1),
+            conm2: Storage::with_capacity(counts.conm2),
+            celas1: Storage::with_capacity(counts.celas1),
+            celas2: Storage::with_capacity(counts.celas2),
+            celas3: Storage::with_capacity(counts.celas3),
+            celas4: Storage::with_capacity(counts.celas4),
+            pelas: Storage::with_capacity(counts.pelas),
+            cdamp1: Storage::with_capacity(counts.cdamp1),
+            cdamp2: Storage::with_capacity(counts.cdamp2),
+            cdamp3: Storage::with_capacity(counts.cdamp3),
+            cdamp4: Storage::with_capacity(counts.cdamp4),
+            pdamp: Storage::with_capacity(counts.pdamp),
+            cmass1: Storage::with_capacity(counts.cmass1),
+            cmass2: Storage::with_capacity(counts.cmass2),
+            cmass3: Storage::with_capacity(counts.cmass3),
+            cmass4: Storage::with_capacity(counts.cmass4),
+            pmass: Storage::with_capacity(counts.pmass),
+            cbush: Storage::with_capacity(counts.cbush),
+            pbush: Storage::with_capacity(counts.pbush),
+            rbar: Storage::with_capacity(counts.rbar),
+            rbe1: Storage::with_capacity(counts.rbe1),
+            rbe2: Storage::with_capacity(counts.rbe2),
+            rbe3: Storage::with_capacity(counts.rbe3),
+            rrod: Storage::with_capacity(counts.rrod),
+            mpc: SetStorage::with_capacity(counts.mpc),
+            mpcadd: Storage::with_capacity(counts.mpcadd),
+            grdset: Storage::with_capacity(counts.grdset),
+            spc: SetStorage::with_capacity(counts.spc),
+            spc1: SetStorage::with_capacity(counts.spc1),
+            spcadd: Storage::with_capacity(counts.spcadd),
+            spcd: SetStorage::with_capacity(counts.spcd),
+            force: SetStorage::with_capacity(counts.force),
+            force1: SetStorage::with_capacity(counts.force1),
+            force2: SetStorage::with_capacity(counts.force2),
+            moment: SetStorage::with_capacity(counts.moment),
+            moment1: SetStorage::with_capacity(counts.moment1),
+            moment2: SetStorage::with_capacity(counts.moment2),
+            pload: SetStorage::with_capacity(counts.pload),
+            pload2: SetStorage::with_capacity(counts.pload2),
+            pload4: SetStorage::with_capacity(counts.pload4),
+            grav: SetStorage::with_capacity(counts.grav),
+            rforce: SetStorage::with_capacity(counts.rforce),
+            load: Storage::with_capacity(counts.load),
         }
     }
 
-    #[cfg(not(feature = "parallel"))]
-    pub fn from_filename(filename: impl AsRef<std::path::Path>) -> Result<Self> {
-        parse_file(filename)?
-            .into_iter()
-            .try_fold(Deck::default(), |mut deck, card| {
-                // This should be ordered by most common card type. Or maybe using a regexset or something
-                let card = card?;
-                match card.card_type().as_ref() {
-                    Some(b"GRID   ") => deck.grid.insert(card.try_into()?)?,
-                    Some(b"CORD2R ") => deck.cord2r.insert(card.try_into()?)?,
-                    Some(b"PSOLID ") => deck.psolid.insert(card.try_into()?)?,
-                    Some(b"MAT1   ") => deck.mat1.insert(card.try_into()?)?,
-                    Some(b"CTETRA ") => deck.ctetra.insert(card.try_into()?)?,
-                    _ => {}
-                };
-                Ok(deck)
-            })
+    /// Converts and stores a card. Returns false for card types that aren't
+    /// supported
+    fn insert_card(&mut self, card: &BulkCard) -> Result<bool> {
+        // This should be ordered by most common card type. Or maybe using a regexset or something
+        match card.card_type().as_ref() {
+            Some(b"GRID   ") => self.grid.insert(convert(card)?)?,
+            Some(b"CORD2R ") => self.cord2r.insert(convert(card)?)?,
+            Some(b"PSOLID ") => self.psolid.insert(convert(card)?)?,
+            Some(b"MAT1   ") => self.mat1.insert(convert(card)?)?,
+            Some(b"MAT2   ") => self.mat2.insert(convert(card)?)?,
+            Some(b"MAT8   ") => self.mat8.insert(convert(card)?)?,
+            Some(b"MAT9   ") => self.mat9.insert(convert(card)?)?,
+            Some(b"MAT10  ") => self.mat10.insert(convert(card)?)?,
+            Some(b"MATT1  ") => self.matt1.insert(convert(card)?)?,
+            Some(b"MATT8  ") => self.matt8.insert(convert(card)?)?,
+            Some(b"TABLEM1") => self.tablem1.insert(convert(card)?)?,
+            Some(b"TABLEM2") => self.tablem2.insert(convert(card)?)?,
+            Some(b"TABLEM3") => self.tablem3.insert(convert(card)?)?,
+            Some(b"TABLEM4") => self.tablem4.insert(convert(card)?)?,
+            Some(b"CTETRA ") => self.ctetra.insert(convert(card)?)?,
+            Some(b"CPENTA ") => self.cpenta.insert(convert(card)?)?,
+            Some(b"CPYRAM ") => self.cpyram.insert(convert(card)?)?,
+            Some(b"CHEXA  ") => self.chexa.insert(convert(card)?)?,
+            Some(b"CQUAD4 ") => self.cquad4.insert(convert(card)?)?,
+            Some(b"CQUADR ") => self.cquadr.insert(convert(card)?)?,
+            Some(b"CTRIA3 ") => self.ctria3.insert(convert(card)?)?,
+            Some(b"CTRIAR ") => self.ctriar.insert(convert(card)?)?,
+            Some(b"CQUAD8 ") => self.cquad8.insert(convert(card)?)?,
+            Some(b"CTRIA6 ") => self.ctria6.insert(convert(card)?)?,
+            Some(b"PSHELL ") => self.pshell.insert(convert(card)?)?,
+            Some(b"PCOMP  ") => self.pcomp.insert(convert(card)?)?,
+            Some(b"PCOMPG ") => self.pcompg.insert(convert(card)?)?,
+            Some(b"CBAR   ") => self.cbar.insert(convert(card)?)?,
+            Some(b"CBEAM  ") => self.cbeam.insert(convert(card)?)?,
+            Some(b"CROD   ") => self.crod.insert(convert(card)?)?,
+            Some(b"CONROD ") => self.conrod.insert(convert(card)?)?,
+            Some(b"CTUBE  ") => self.ctube.insert(convert(card)?)?,
+            Some(b"PBAR   ") => self.pbar.insert(convert(card)?)?,
+            Some(b"PBEAM  ") => self.pbeam.insert(convert(card)?)?,
+            Some(b"PROD   ") => self.prod.insert(convert(card)?)?,
+            Some(b"PTUBE  ") => self.ptube.insert(convert(card)?)?,
+            Some(b"PBARL  ") => self.pbarl.insert(convert(card)?)?,
+            Some(b"PBEAML ") => self.pbeaml.insert(convert(card)?)?,
+            Some(b"CONM1  ") => self.conm1.insert(convert(card)?)?,
+            Some(b"CONM2  ") => self.conm2.insert(convert(card)?)?,
+            Some(b"CELAS1 ") => self.celas1.insert(convert(card)?)?,
+            Some(b"CELAS2 ") => self.celas2.insert(convert(card)?)?,
+            Some(b"CELAS3 ") => self.celas3.insert(convert(card)?)?,
+            Some(b"CELAS4 ") => self.celas4.insert(convert(card)?)?,
+            Some(b"PELAS  ") => self.pelas.insert_all(convert::<Vec<_>>(card)?)?,
+            Some(b"CDAMP1 ") => self.cdamp1.insert(convert(card)?)?,
+            Some(b"CDAMP2 ") => self.cdamp2.insert(convert(card)?)?,
+            Some(b"CDAMP3 ") => self.cdamp3.insert(convert(card)?)?,
+            Some(b"CDAMP4 ") => self.cdamp4.insert(convert(card)?)?,
+            Some(b"PDAMP  ") => self.pdamp.insert_all(convert::<Vec<_>>(card)?)?,
+            Some(b"CMASS1 ") => self.cmass1.insert(convert(card)?)?,
+            Some(b"CMASS2 ") => self.cmass2.insert(convert(card)?)?,
+            Some(b"CMASS3 ") => self.cmass3.insert(convert(card)?)?,
+            Some(b"CMASS4 ") => self.cmass4.insert(convert(card)?)?,
+            Some(b"PMASS  ") => self.pmass.insert_all(convert::<Vec<_>>(card)?)?,
+            Some(b"CBUSH  ") => self.cbush.insert(convert(card)?)?,
+            Some(b"PBUSH  ") => self.pbush.insert(convert(card)?)?,
+            Some(b"RBAR   ") => self.rbar.insert(convert(card)?)?,
+            Some(b"RBE1   ") => self.rbe1.insert(convert(card)?)?,
+            Some(b"RBE2   ") => self.rbe2.insert(convert(card)?)?,
+            Some(b"RBE3   ") => self.rbe3.insert(convert(card)?)?,
+            Some(b"RROD   ") => self.rrod.insert(convert(card)?)?,
+            Some(b"MPC    ") => self.mpc.insert(convert(card)?)?,
+            Some(b"MPCADD ") => self.mpcadd.insert(convert(card)?)?,
+            Some(b"GRDSET ") => self.grdset.insert(convert(card)?)?,
+            Some(b"SPC    ") => self.spc.insert(convert(card)?)?,
+            Some(b"SPC1   ") => self.spc1.insert(convert(card)?)?,
+            Some(b"SPCADD ") => self.spcadd.insert(convert(card)?)?,
+            Some(b"SPCD   ") => self.spcd.insert(convert(card)?)?,
+            Some(b"FORCE  ") => self.force.insert(convert(card)?)?,
+            Some(b"FORCE1 ") => self.force1.insert(convert(card)?)?,
+            Some(b"FORCE2 ") => self.force2.insert(convert(card)?)?,
+            Some(b"MOMENT ") => self.moment.insert(convert(card)?)?,
+            Some(b"MOMENT1") => self.moment1.insert(convert(card)?)?,
+            Some(b"MOMENT2") => self.moment2.insert(convert(card)?)?,
+            Some(b"PLOAD  ") => self.pload.insert(convert(card)?)?,
+            Some(b"PLOAD2 ") => self.pload2.insert(convert(card)?)?,
+            Some(b"PLOAD4 ") => self.pload4.insert(convert(card)?)?,
+            Some(b"GRAV   ") => self.grav.insert(convert(card)?)?,
+            Some(b"RFORCE ") => self.rforce.insert(convert(card)?)?,
+            Some(b"LOAD   ") => self.load.insert(convert(card)?)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    fn extend_from_raw(&mut self, raw: RawBulkData) -> Result<()> {
+        self.grid.extend_from_raw(raw.grid)?;
+        self.cord2r.extend_from_raw(raw.cord2r)?;
+        self.psolid.extend_from_raw(raw.psolid)?;
+        self.mat1.extend_from_raw(raw.mat1)?;
+        self.mat2.extend_from_raw(raw.mat2)?;
+        self.mat8.extend_from_raw(raw.mat8)?;
+        self.mat9.extend_from_raw(raw.mat9)?;
+        self.mat10.extend_from_raw(raw.mat10)?;
+        self.matt1.extend_from_raw(raw.matt1)?;
+        self.matt8.extend_from_raw(raw.matt8)?;
+        self.tablem1.extend_from_raw(raw.tablem1)?;
+        self.tablem2.extend_from_raw(raw.tablem2)?;
+        self.tablem3.extend_from_raw(raw.tablem3)?;
+        self.tablem4.extend_from_raw(raw.tablem4)?;
+        self.ctetra.extend_from_raw(raw.ctetra)?;
+        self.cpenta.extend_from_raw(raw.cpenta)?;
+        self.cpyram.extend_from_raw(raw.cpyram)?;
+        self.chexa.extend_from_raw(raw.chexa)?;
+        self.cquad4.extend_from_raw(raw.cquad4)?;
+        self.cquadr.extend_from_raw(raw.cquadr)?;
+        self.ctria3.extend_from_raw(raw.ctria3)?;
+        self.ctriar.extend_from_raw(raw.ctriar)?;
+        self.cquad8.extend_from_raw(raw.cquad8)?;
+        self.ctria6.extend_from_raw(raw.ctria6)?;
+        self.pshell.extend_from_raw(raw.pshell)?;
+        self.pcomp.extend_from_raw(raw.pcomp)?;
+        self.pcompg.extend_from_raw(raw.pcompg)?;
+        self.cbar.extend_from_raw(raw.cbar)?;
+        self.cbeam.extend_from_raw(raw.cbeam)?;
+        self.crod.extend_from_raw(raw.crod)?;
+        self.conrod.extend_from_raw(raw.conrod)?;
+        self.ctube.extend_from_raw(raw.ctube)?;
+        self.pbar.extend_from_raw(raw.pbar)?;
+        self.pbeam.extend_from_raw(raw.pbeam)?;
+        self.prod.extend_from_raw(raw.prod)?;
+        self.ptube.extend_from_raw(raw.ptube)?;
+        self.pbarl.extend_from_raw(raw.pbarl)?;
+        self.pbeaml.extend_from_raw(raw.pbeaml)?;
+        self.conm1.extend_from_raw(raw.conm1)?;
+        self.conm2.extend_from_raw(raw.conm2)?;
+        self.celas1.extend_from_raw(raw.celas1)?;
+        self.celas2.extend_from_raw(raw.celas2)?;
+        self.celas3.extend_from_raw(raw.celas3)?;
+        self.celas4.extend_from_raw(raw.celas4)?;
+        self.pelas.extend_from_raw(raw.pelas)?;
+        self.cdamp1.extend_from_raw(raw.cdamp1)?;
+        self.cdamp2.extend_from_raw(raw.cdamp2)?;
+        self.cdamp3.extend_from_raw(raw.cdamp3)?;
+        self.cdamp4.extend_from_raw(raw.cdamp4)?;
+        self.pdamp.extend_from_raw(raw.pdamp)?;
+        self.cmass1.extend_from_raw(raw.cmass1)?;
+        self.cmass2.extend_from_raw(raw.cmass2)?;
+        self.cmass3.extend_from_raw(raw.cmass3)?;
+        self.cmass4.extend_from_raw(raw.cmass4)?;
+        self.pmass.extend_from_raw(raw.pmass)?;
+        self.cbush.extend_from_raw(raw.cbush)?;
+        self.pbush.extend_from_raw(raw.pbush)?;
+        self.rbar.extend_from_raw(raw.rbar)?;
+        self.rbe1.extend_from_raw(raw.rbe1)?;
+        self.rbe2.extend_from_raw(raw.rbe2)?;
+        self.rbe3.extend_from_raw(raw.rbe3)?;
+        self.rrod.extend_from_raw(raw.rrod)?;
+        self.mpc.extend_from_raw(raw.mpc)?;
+        self.mpcadd.extend_from_raw(raw.mpcadd)?;
+        self.grdset.extend_from_raw(raw.grdset)?;
+        self.spc.extend_from_raw(raw.spc)?;
+        self.spc1.extend_from_raw(raw.spc1)?;
+        self.spcadd.extend_from_raw(raw.spcadd)?;
+        self.spcd.extend_from_raw(raw.spcd)?;
+        self.force.extend_from_raw(raw.force)?;
+        self.force1.extend_from_raw(raw.force1)?;
+        self.force2.extend_from_raw(raw.force2)?;
+        self.moment.extend_from_raw(raw.moment)?;
+        self.moment1.extend_from_raw(raw.moment1)?;
+        self.moment2.extend_from_raw(raw.moment2)?;
+        self.pload.extend_from_raw(raw.pload)?;
+        self.pload2.extend_from_raw(raw.pload2)?;
+        self.pload4.extend_from_raw(raw.pload4)?;
+        self.grav.extend_from_raw(raw.grav)?;
+        self.rforce.extend_from_raw(raw.rforce)?;
+        self.load.extend_from_raw(raw.load)?;
+        Ok(())
     }
 
     pub fn global_locations(&self) -> GlobalLocation {
@@ -736,13 +1729,25 @@ impl Deck {
         });
         cord2r.retain(|c| {
             if c.rid == 0 {
-                csys.insert(c.rid, c.rotation_matrix());
+                csys.insert(c.id, c.rotation_matrix(None));
                 false
             } else {
                 true
             }
         });
-        while !grid.is_empty() && !cord2r.is_empty() {
+        // Systems can be defined in other systems, so keep going until
+        // nothing more can be placed. Grids in systems that can't be found
+        // are left out
+        loop {
+            let remaining = grid.len() + cord2r.len();
+            cord2r.retain(|c| match csys.get(&c.rid) {
+                Some(r) => {
+                    let system = c.rotation_matrix(Some(r));
+                    csys.insert(c.id, system);
+                    false
+                }
+                None => true,
+            });
             grid.retain(|g| {
                 if let Some(r) = csys.get(&g.cp) {
                     xyz.insert(g.id, r.forward(g.xyz));
@@ -750,65 +1755,634 @@ impl Deck {
                 } else {
                     true
                 }
-            })
+            });
+            if grid.len() + cord2r.len() == remaining {
+                break;
+            }
         }
         println!("global_locations took {} ms", t.elapsed().as_millis());
         GlobalLocation { xyz, csys }
     }
 
     fn with<'a, T>(&'a self, item: &'a T) -> DeckRef<'a, T> {
-        DeckRef { deck: self, item }
+        DeckRef { bulk: self, item }
+    }
+
+    pub fn get_grid(&self, id: u32) -> Option<&GRID> {
+        self.grid.get(id)
+    }
+
+    pub fn get_cord2r(&self, id: u32) -> Option<&CORD2R> {
+        self.cord2r.get(id)
+    }
+
+    pub fn get_ctetra(&self, id: u32) -> Option<&CTETRA> {
+        self.ctetra.get(id)
+    }
+
+    pub fn get_cpenta(&self, id: u32) -> Option<&CPENTA> {
+        self.cpenta.get(id)
+    }
+
+    pub fn get_cpyram(&self, id: u32) -> Option<&CPYRAM> {
+        self.cpyram.get(id)
+    }
+
+    pub fn get_chexa(&self, id: u32) -> Option<&CHEXA> {
+        self.chexa.get(id)
+    }
+
+    pub fn get_psolid(&self, id: u32) -> Option<&PSOLID> {
+        self.psolid.get(id)
+    }
+
+    pub fn get_mat1(&self, id: u32) -> Option<&MAT1> {
+        self.mat1.get(id)
+    }
+
+    pub fn get_mat2(&self, id: u32) -> Option<&MAT2> {
+        self.mat2.get(id)
+    }
+
+    pub fn get_mat8(&self, id: u32) -> Option<&MAT8> {
+        self.mat8.get(id)
+    }
+
+    pub fn get_mat9(&self, id: u32) -> Option<&MAT9> {
+        self.mat9.get(id)
+    }
+
+    pub fn get_mat10(&self, id: u32) -> Option<&MAT10> {
+        self.mat10.get(id)
+    }
+
+    pub fn get_matt1(&self, id: u32) -> Option<&MATT1> {
+        self.matt1.get(id)
+    }
+
+    pub fn get_matt8(&self, id: u32) -> Option<&MATT8> {
+        self.matt8.get(id)
+    }
+
+    pub fn get_tablem1(&self, id: u32) -> Option<&TABLEM1> {
+        self.tablem1.get(id)
+    }
+
+    pub fn get_tablem2(&self, id: u32) -> Option<&TABLEM2> {
+        self.tablem2.get(id)
+    }
+
+    pub fn get_tablem3(&self, id: u32) -> Option<&TABLEM3> {
+        self.tablem3.get(id)
+    }
+
+    pub fn get_tablem4(&self, id: u32) -> Option<&TABLEM4> {
+        self.tablem4.get(id)
+    }
+
+    pub fn get_cquad4(&self, id: u32) -> Option<&CQUAD4> {
+        self.cquad4.get(id)
+    }
+
+    pub fn get_cquadr(&self, id: u32) -> Option<&CQUADR> {
+        self.cquadr.get(id)
+    }
+
+    pub fn get_ctria3(&self, id: u32) -> Option<&CTRIA3> {
+        self.ctria3.get(id)
+    }
+
+    pub fn get_ctriar(&self, id: u32) -> Option<&CTRIAR> {
+        self.ctriar.get(id)
+    }
+
+    pub fn get_cquad8(&self, id: u32) -> Option<&CQUAD8> {
+        self.cquad8.get(id)
+    }
+
+    pub fn get_ctria6(&self, id: u32) -> Option<&CTRIA6> {
+        self.ctria6.get(id)
+    }
+
+    pub fn get_pshell(&self, id: u32) -> Option<&PSHELL> {
+        self.pshell.get(id)
+    }
+
+    pub fn get_pcomp(&self, id: u32) -> Option<&PCOMP> {
+        self.pcomp.get(id)
+    }
+
+    pub fn get_pcompg(&self, id: u32) -> Option<&PCOMPG> {
+        self.pcompg.get(id)
+    }
+
+    pub fn get_cbar(&self, id: u32) -> Option<&CBAR> {
+        self.cbar.get(id)
+    }
+
+    pub fn get_cbeam(&self, id: u32) -> Option<&CBEAM> {
+        self.cbeam.get(id)
+    }
+
+    pub fn get_crod(&self, id: u32) -> Option<&CROD> {
+        self.crod.get(id)
+    }
+
+    pub fn get_conrod(&self, id: u32) -> Option<&CONROD> {
+        self.conrod.get(id)
+    }
+
+    pub fn get_ctube(&self, id: u32) -> Option<&CTUBE> {
+        self.ctube.get(id)
+    }
+
+    pub fn get_pbar(&self, id: u32) -> Option<&PBAR> {
+        self.pbar.get(id)
+    }
+
+    pub fn get_pbeam(&self, id: u32) -> Option<&PBEAM> {
+        self.pbeam.get(id)
+    }
+
+    pub fn get_prod(&self, id: u32) -> Option<&PROD> {
+        self.prod.get(id)
+    }
+
+    pub fn get_ptube(&self, id: u32) -> Option<&PTUBE> {
+        self.ptube.get(id)
+    }
+
+    pub fn get_pbarl(&self, id: u32) -> Option<&PBARL> {
+        self.pbarl.get(id)
+    }
+
+    pub fn get_pbeaml(&self, id: u32) -> Option<&PBEAML> {
+        self.pbeaml.get(id)
+    }
+
+    pub fn get_conm1(&self, id: u32) -> Option<&CONM1> {
+        self.conm1.get(id)
+    }
+
+    pub fn get_conm2(&self, id: u32) -> Option<&CONM2> {
+        self.conm2.get(id)
+    }
+
+    pub fn get_celas1(&self, id: u32) -> Option<&CELAS1> {
+        self.celas1.get(id)
+    }
+
+    pub fn get_celas2(&self, id: u32) -> Option<&CELAS2> {
+        self.celas2.get(id)
+    }
+
+    pub fn get_celas3(&self, id: u32) -> Option<&CELAS3> {
+        self.celas3.get(id)
+    }
+
+    pub fn get_celas4(&self, id: u32) -> Option<&CELAS4> {
+        self.celas4.get(id)
+    }
+
+    pub fn get_pelas(&self, id: u32) -> Option<&PELAS> {
+        self.pelas.get(id)
+    }
+
+    pub fn get_cdamp1(&self, id: u32) -> Option<&CDAMP1> {
+        self.cdamp1.get(id)
+    }
+
+    pub fn get_cdamp2(&self, id: u32) -> Option<&CDAMP2> {
+        self.cdamp2.get(id)
+    }
+
+    pub fn get_cdamp3(&self, id: u32) -> Option<&CDAMP3> {
+        self.cdamp3.get(id)
+    }
+
+    pub fn get_cdamp4(&self, id: u32) -> Option<&CDAMP4> {
+        self.cdamp4.get(id)
+    }
+
+    pub fn get_pdamp(&self, id: u32) -> Option<&PDAMP> {
+        self.pdamp.get(id)
+    }
+
+    pub fn get_cmass1(&self, id: u32) -> Option<&CMASS1> {
+        self.cmass1.get(id)
+    }
+
+    pub fn get_cmass2(&self, id: u32) -> Option<&CMASS2> {
+        self.cmass2.get(id)
+    }
+
+    pub fn get_cmass3(&self, id: u32) -> Option<&CMASS3> {
+        self.cmass3.get(id)
+    }
+
+    pub fn get_cmass4(&self, id: u32) -> Option<&CMASS4> {
+        self.cmass4.get(id)
+    }
+
+    pub fn get_pmass(&self, id: u32) -> Option<&PMASS> {
+        self.pmass.get(id)
+    }
+
+    pub fn get_cbush(&self, id: u32) -> Option<&CBUSH> {
+        self.cbush.get(id)
+    }
+
+    pub fn get_pbush(&self, id: u32) -> Option<&PBUSH> {
+        self.pbush.get(id)
+    }
+
+    pub fn get_rbar(&self, id: u32) -> Option<&RBAR> {
+        self.rbar.get(id)
+    }
+
+    pub fn get_rbe1(&self, id: u32) -> Option<&RBE1> {
+        self.rbe1.get(id)
+    }
+
+    pub fn get_rbe2(&self, id: u32) -> Option<&RBE2> {
+        self.rbe2.get(id)
+    }
+
+    pub fn get_rbe3(&self, id: u32) -> Option<&RBE3> {
+        self.rbe3.get(id)
+    }
+
+    pub fn get_rrod(&self, id: u32) -> Option<&RROD> {
+        self.rrod.get(id)
+    }
+
+    /// The equations of an MPC set
+    pub fn get_mpc(&self, sid: u32) -> impl Iterator<Item = &MPC> {
+        self.mpc.get(sid)
+    }
+
+    pub fn get_mpcadd(&self, id: u32) -> Option<&MPCADD> {
+        self.mpcadd.get(id)
+    }
+
+    pub fn get_grdset(&self) -> Option<&GRDSET> {
+        self.grdset.get(())
+    }
+
+    /// The SPC cards of a set, without those of sets it is combined with
+    pub fn get_spc(&self, sid: u32) -> impl Iterator<Item = &SPC> {
+        self.spc.get(sid)
+    }
+
+    pub fn get_spc1(&self, sid: u32) -> impl Iterator<Item = &SPC1> {
+        self.spc1.get(sid)
+    }
+
+    pub fn get_spcadd(&self, id: u32) -> Option<&SPCADD> {
+        self.spcadd.get(id)
+    }
+
+    pub fn get_spcd(&self, sid: u32) -> impl Iterator<Item = &SPCD> {
+        self.spcd.get(sid)
+    }
+
+    pub fn get_force(&self, sid: u32) -> impl Iterator<Item = &FORCE> {
+        self.force.get(sid)
+    }
+
+    pub fn get_force1(&self, sid: u32) -> impl Iterator<Item = &FORCE1> {
+        self.force1.get(sid)
+    }
+
+    pub fn get_force2(&self, sid: u32) -> impl Iterator<Item = &FORCE2> {
+        self.force2.get(sid)
+    }
+
+    pub fn get_moment(&self, sid: u32) -> impl Iterator<Item = &MOMENT> {
+        self.moment.get(sid)
+    }
+
+    pub fn get_moment1(&self, sid: u32) -> impl Iterator<Item = &MOMENT1> {
+        self.moment1.get(sid)
+    }
+
+    pub fn get_moment2(&self, sid: u32) -> impl Iterator<Item = &MOMENT2> {
+        self.moment2.get(sid)
+    }
+
+    pub fn get_pload(&self, sid: u32) -> impl Iterator<Item = &PLOAD> {
+        self.pload.get(sid)
+    }
+
+    pub fn get_pload2(&self, sid: u32) -> impl Iterator<Item = &PLOAD2> {
+        self.pload2.get(sid)
+    }
+
+    pub fn get_pload4(&self, sid: u32) -> impl Iterator<Item = &PLOAD4> {
+        self.pload4.get(sid)
+    }
+
+    pub fn get_grav(&self, sid: u32) -> impl Iterator<Item = &GRAV> {
+        self.grav.get(sid)
+    }
+
+    pub fn get_rforce(&self, sid: u32) -> impl Iterator<Item = &RFORCE> {
+        self.rforce.get(sid)
+    }
+
+    pub fn get_load(&self, sid: u32) -> Option<&LOAD> {
+        self.load.get(sid)
     }
 
     #[allow(dead_code)]
-    fn grid(&self, id: u32) -> Option<DeckRef<GRID>> {
+    fn grid(&self, id: u32) -> Option<DeckRef<'_, GRID>> {
         self.grid.get(id).map(|grid| self.with(grid))
     }
 
     #[allow(dead_code)]
-    fn tetra(&self, id: u32) -> Option<DeckRef<CTETRA>> {
+    fn tetra(&self, id: u32) -> Option<DeckRef<'_, CTETRA>> {
         self.ctetra.get(id).map(|e| self.with(e))
     }
 
-    fn psolid(&self, id: u32) -> Option<DeckRef<PSOLID>> {
+    #[allow(dead_code)]
+    fn psolid(&self, id: u32) -> Option<DeckRef<'_, PSOLID>> {
         self.psolid.get(id).map(|e| self.with(e))
     }
 
-    fn mat1(&self, id: u32) -> Option<DeckRef<MAT1>> {
-        self.mat1.get(id).map(|e| self.with(e))
+    /// The material with an id, whichever type of material card it is
+    pub fn get_material(&self, id: u32) -> Option<Material<'_>> {
+        None.or_else(|| self.mat1.get(id).map(Material::Mat1))
+            .or_else(|| self.mat2.get(id).map(Material::Mat2))
+            .or_else(|| self.mat8.get(id).map(Material::Mat8))
+            .or_else(|| self.mat9.get(id).map(Material::Mat9))
+            .or_else(|| self.mat10.get(id).map(Material::Mat10))
+    }
+
+    /// The stiffness and mass of the laminate of a PCOMP or PCOMPG. It is
+    /// `None` when the property or the material of a ply is missing, or a ply
+    /// material can't be used for shells
+    pub fn laminate(&self, pid: u32) -> Option<Laminate> {
+        match self.pcomp.get(pid) {
+            Some(pcomp) => pcomp.laminate(self),
+            None => self.pcompg.get(pid)?.laminate(self),
+        }
+    }
+
+    /// The mass per unit area of the laminate of a PCOMP or PCOMPG
+    fn areal_mass(&self, pid: u32) -> Option<f64> {
+        match self.pcomp.get(pid) {
+            Some(pcomp) => pcomp.areal_mass(self),
+            None => self.pcompg.get(pid)?.areal_mass(self),
+        }
+    }
+
+    /// The MAT1 with its properties at a temperature, from the tables of the
+    /// MATT1 with the same id if there is one. It is `None` when the MAT1 or
+    /// one of the tables is missing, or NU can't be found from E and G
+    pub fn mat1_at(&self, mid: u32, temperature: f64) -> Option<MAT1> {
+        let mat = self.mat1.get(mid)?;
+        match self.matt1.get(mid) {
+            Some(matt) => matt.apply(self, mat, temperature),
+            None => Some(mat.clone()),
+        }
+    }
+
+    /// The MAT8 with its properties at a temperature, like
+    /// [`mat1_at`](Self::mat1_at)
+    pub fn mat8_at(&self, mid: u32, temperature: f64) -> Option<MAT8> {
+        let mat = self.mat8.get(mid)?;
+        match self.matt8.get(mid) {
+            Some(matt) => matt.apply(self, mat, temperature),
+            None => Some(mat.clone()),
+        }
+    }
+
+    /// The table for temperature dependent materials with an id, whichever
+    /// type of table it is
+    pub fn get_material_table(&self, id: u32) -> Option<MaterialTable<'_>> {
+        None.or_else(|| self.tablem1.get(id).map(MaterialTable::Tablem1))
+            .or_else(|| self.tablem2.get(id).map(MaterialTable::Tablem2))
+            .or_else(|| self.tablem3.get(id).map(MaterialTable::Tablem3))
+            .or_else(|| self.tablem4.get(id).map(MaterialTable::Tablem4))
     }
 
     pub fn mass(&self, location: &GlobalLocation) -> f64 {
-        self.ctetra
-            .iter()
-            .map(|c| self.with(c).mass(location).unwrap_or_default())
-            .sum()
+        self.mass_moment(location).mass
     }
 
-    #[cfg(feature = "parallel")]
     pub fn mass_cg(&self, location: &GlobalLocation) -> (f64, Vec3) {
+        let mm = self.mass_moment(location);
+        let cg = mm.moment / mm.mass;
+        (mm.mass, cg)
+    }
+
+    /// The total force and moment about `point` of a load set, or of the
+    /// sets combined by a LOAD card with that id, in the basic system.
+    /// Pressures act on shell faces and solid faces, and gravity and
+    /// rotation act on the mass of every element. Loads that refer to missing
+    /// grids, elements or coordinate systems are left out and returned
+    /// alongside it
+    pub fn load_resultant(
+        &self,
+        sid: u32,
+        point: Vec3,
+        location: &GlobalLocation,
+    ) -> (Resultant, Vec<SkippedLoad>) {
+        let (resultant, skipped) = load::resultant(self, sid, location);
+        (resultant.about(point), skipped)
+    }
+
+    /// The components of a grid constrained by its PS field, or by the
+    /// GRDSET when that is blank
+    fn permanent_spc(&self, grid: &GRID) -> [bool; 6] {
+        grid.ps
+            .or_else(|| self.grdset.get(()).map(|g| g.ps))
+            .unwrap_or_default()
+    }
+
+    /// The SPC sets making up a set, following SPCADD cards
+    fn spc_sets(&self, sid: u32) -> Vec<u32> {
+        combined_sets(sid, |sid| self.spcadd.get(sid).map(|a| &a.sets[..]))
+    }
+
+    /// The grids and components constrained by the SPC and SPC1 cards of a
+    /// single set
+    fn spc_constraints(&self, sid: u32) -> impl Iterator<Item = (u32, [bool; 6])> + '_ {
+        let spc = self
+            .spc
+            .get(sid)
+            .flat_map(|spc| spc.constraints().iter().map(|&(g, c, _)| (g, c)));
+        let spc1 = self
+            .spc1
+            .get(sid)
+            .flat_map(|spc1| spc1.grids().iter().map(move |&g| (g, spc1.components())));
+        spc.chain(spc1)
+    }
+
+    /// The constrained components of each grid for an SPC set, following
+    /// SPCADD cards, merged with the permanent constraints of every grid
+    pub fn constrained_dofs(&self, spc: u32) -> BTreeMap<u32, [bool; 6]> {
+        let mut dofs = BTreeMap::<_, [bool; 6]>::new();
+        let permanent = self.grid.iter().map(|g| (g.id, self.permanent_spc(g)));
+        let sets = self.spc_sets(spc);
+        let constraints = sets.iter().flat_map(|&sid| self.spc_constraints(sid));
+        for (grid, components) in permanent.chain(constraints) {
+            if components.contains(&true) {
+                let dof = dofs.entry(grid).or_default();
+                dof.iter_mut().zip(components).for_each(|(d, c)| *d |= c);
+            }
+        }
+        dofs
+    }
+
+    /// Checks how the rigid elements and the MPC set selected by the subcase
+    /// make DOFs dependent, against each other and the SPC set selected, for
+    /// the problems Nastran would stop on
+    pub fn dof_conflicts(&self, subcase: &Subcase) -> Vec<DofConflict> {
+        dof::conflicts(self, subcase)
+    }
+
+    /// The total mass and first moment of mass of every element. Elements
+    /// with missing grids, properties or materials are skipped. Scalar masses
+    /// are not included since they have no direction or position of their own
+    fn mass_moment(&self, location: &GlobalLocation) -> MassMoment {
+        self.mass_sum(location, |m| m)
+    }
+
+    /// Sums a value found from the mass and first moment of mass of each
+    /// element, like [`mass_moment`](Self::mass_moment) does for the masses
+    /// themselves
+    fn mass_sum<A, G>(&self, location: &GlobalLocation, g: G) -> A
+    where
+        A: std::iter::Sum + std::ops::Add<Output = A> + Default + Send,
+        G: Fn(MassMoment) -> A + Sync + Send,
+    {
+        let g = &g;
+        self.sum(&self.ctetra, |e| solid::mass_moment(&e, location).map(g))
+            + self.sum(&self.cpenta, |e| solid::mass_moment(&e, location).map(g))
+            + self.sum(&self.cpyram, |e| solid::mass_moment(&e, location).map(g))
+            + self.sum(&self.chexa, |e| solid::mass_moment(&e, location).map(g))
+            + self.sum(&self.cquad4, |e| shell::mass_moment(&e, location).map(g))
+            + self.sum(&self.cquadr, |e| shell::mass_moment(&e, location).map(g))
+            + self.sum(&self.ctria3, |e| shell::mass_moment(&e, location).map(g))
+            + self.sum(&self.ctriar, |e| shell::mass_moment(&e, location).map(g))
+            + self.sum(&self.cquad8, |e| shell::mass_moment(&e, location).map(g))
+            + self.sum(&self.ctria6, |e| shell::mass_moment(&e, location).map(g))
+            + self.sum(&self.cbar, |e| line::mass_moment(&e, location).map(g))
+            + self.sum(&self.cbeam, |e| line::mass_moment(&e, location).map(g))
+            + self.sum(&self.crod, |e| line::mass_moment(&e, location).map(g))
+            + self.sum(&self.conrod, |e| line::mass_moment(&e, location).map(g))
+            + self.sum(&self.ctube, |e| line::mass_moment(&e, location).map(g))
+            + self.sum(&self.conm1, |e| e.mass_moment(location).map(g))
+            + self.sum(&self.conm2, |e| e.mass_moment(location).map(g))
+    }
+
+    #[cfg(feature = "parallel")]
+    fn sum<'a, T, F, A>(&'a self, storage: &'a Storage<T>, f: F) -> A
+    where
+        T: StorageItem + Sync,
+        F: Fn(DeckRef<'a, T>) -> Option<A> + Sync + Send,
+        A: std::iter::Sum + Default + Send,
+    {
         use rayon::prelude::*;
-        let mm: MassMoment = self
-            .ctetra
+        storage
             .data
             .par_iter()
             .filter_map(|c| c.as_ref())
-            .map(|c| self.with(c).mass_moment(location).unwrap_or_default())
-            .sum();
-        let cg = mm.moment / mm.mass;
-        (mm.mass, cg)
+            .map(|c| f(self.with(c)).unwrap_or_default())
+            .sum()
     }
 
     #[cfg(not(feature = "parallel"))]
-    pub fn mass_cg(&self, location: &GlobalLocation) -> (f64, Vec3) {
-        let mm: MassMoment = self
-            .ctetra
-            .data
+    fn sum<'a, T, F, A>(&'a self, storage: &'a Storage<T>, f: F) -> A
+    where
+        T: StorageItem,
+        F: Fn(DeckRef<'a, T>) -> Option<A>,
+        A: std::iter::Sum + Default,
+    {
+        storage
             .iter()
-            .filter_map(|c| c.as_ref())
-            .map(|c| self.with(c).mass_moment(location).unwrap_or_default())
-            .sum();
-        let cg = mm.moment / mm.mass;
-        (mm.mass, cg)
+            .map(|c| f(self.with(c)).unwrap_or_default())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::Severity;
+
+    const INPUT: &[u8] = b"SOL 101
+CEND
+BEGIN BULK
+GRID,1,,0.,0.,0.
+GRID,2,,1.,0.,0.
+BEGIN SUPER=2
+GRID,1,,5.,0.,0.
+BEGIN BULK AUXMODEL=1
+GRID,1,,9.,0.,0.
+ENDDATA
+";
+
+    #[test]
+    fn test_partitions() {
+        let deck = Deck::from_buffer(INPUT).unwrap();
+        let partitions = deck.partitions().map(|(p, _)| p).collect::<Vec<_>>();
+        assert_eq!(
+            partitions,
+            vec![Partition::Main, Partition::Super(2), Partition::AuxModel(1)]
+        );
+        assert_eq!(deck.main().grid.len(), 2);
+        let x = |bulk: &BulkData| bulk.get_grid(1).unwrap().xyz.x();
+        assert_eq!(x(deck.superelement(0).unwrap()), 0.0);
+        assert_eq!(x(deck.superelement(2).unwrap()), 5.0);
+        assert_eq!(x(deck.partition(Partition::AuxModel(1)).unwrap()), 9.0);
+        assert!(deck.superelement(2).unwrap().get_grid(2).is_none());
+        assert!(deck.superelement(1).is_none());
+
+        let duplicate = b"BEGIN BULK\nGRID,1\nBEGIN SUPER=2\nGRID,1\nGRID,1\n";
+        assert!(Deck::from_buffer(duplicate).is_err());
+    }
+
+    #[test]
+    fn test_from_buffer_lenient() {
+        let input = b"GRID,1,,0.,0.,0.\nGRID,2,,1#,0.,0.\nGRID,3\nFOO,1\n";
+        let (deck, diagnostics) = Deck::from_buffer_lenient(input).unwrap();
+        assert_eq!(deck.main().grid.len(), 2);
+        assert!(deck.main().get_grid(2).is_none());
+        let severities: Vec<_> = diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(severities, vec![Severity::Error, Severity::Warning]);
+        assert_eq!(diagnostics[0].error.location().unwrap().line(), 2);
+
+        let (from_reader, from_reader_diagnostics) = Deck::from_reader_lenient(&input[..]).unwrap();
+        assert_eq!(from_reader.main().grid.len(), 2);
+        assert_eq!(from_reader_diagnostics.len(), diagnostics.len());
+    }
+
+    #[test]
+    fn test_global_locations() {
+        // System 2 is defined in system 1 and turned a quarter turn about z
+        let input = b"CORD2R,1,,1.,0.,0.,1.,0.,1.,
+,2.,0.,0.
+CORD2R,2,1,0.,0.,0.,0.,0.,1.,
+,0.,1.,0.
+GRID,1,2,1.,0.,0.
+GRID,2,3,1.,0.,0.
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        let xyz = locations.get_grid(1).unwrap().to_vec();
+        assert!((xyz - Vec3::new(1., 1., 0.)).length() < 1e-12, "{:?}", xyz);
+        assert!(locations.get_csys(2).is_some());
+        assert!(locations.get_grid(2).is_none());
+    }
+
+    #[test]
+    fn test_mat1_nu() {
+        let deck = Deck::from_buffer(b"MAT1,1,100.,40.").unwrap();
+        let mat = deck.main().get_mat1(1).unwrap();
+        assert!((mat.nu - 0.25).abs() < 1e-12, "{}", mat.nu);
+        assert!(Deck::from_buffer(b"MAT1,1,100.,0.").is_err());
+        assert!(Deck::from_buffer(b"MAT1,1,100.,0.,0.3").is_ok());
     }
 }
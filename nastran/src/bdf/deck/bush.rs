@@ -0,0 +1,260 @@
+use std::convert::TryFrom;
+
+use bstr::ByteSlice;
+
+use super::line::orientation;
+use super::{BulkData, Element, Orientation, Property, StorageItem};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+/// A generalized spring and damper between two grids, or one grid and ground
+#[derive(Debug, Clone)]
+pub struct CBUSH {
+    eid: u32,
+    pid: u32,
+    g: [u32; 2],
+    /// When blank the element system is given by `cid`
+    orientation: Option<Orientation>,
+    cid: Option<u32>,
+    /// The location of the spring and damper along the element, from 0 at GA
+    s: f64,
+    /// The system the offset `s1` is in, or -1 if the offset is given by `s`
+    ocid: i32,
+    s1: [f64; 3],
+}
+
+impl CBUSH {
+    /// GA and GB, where GB is 0 for a bush to ground
+    pub fn g(&self) -> [u32; 2] {
+        self.g
+    }
+
+    /// The orientation vector or grid, `None` when the element system is
+    /// given by `cid`
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.orientation
+    }
+
+    /// The element coordinate system, where 0 is the basic system
+    pub fn cid(&self) -> Option<u32> {
+        self.cid
+    }
+
+    /// The location of the spring and damper along the element, from 0 at GA
+    pub fn s(&self) -> f64 {
+        self.s
+    }
+
+    /// The system the offset `s1` is given in, `None` when the location is
+    /// given by `s`
+    pub fn ocid(&self) -> Option<u32> {
+        u32::try_from(self.ocid).ok()
+    }
+
+    /// The offset of the spring and damper from GA in the system `ocid`
+    pub fn s1(&self) -> [f64; 3] {
+        self.s1
+    }
+}
+
+impl Element for CBUSH {
+    type Property = PBUSH;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl StorageItem for CBUSH {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CBUSH {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CBUSH  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CBUSH  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CBUSH  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Int, Int, IntOrReal, Real, Real, Int, Real, Int, Real, Real, Real,
+            ])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let g = [iter.next().id()?, iter.next().id_or(0)?];
+        let orientation = orientation(&mut iter)?;
+        let cid = match iter.next().unwrap_or_default() {
+            Field::Blank => None,
+            field => Some(field.id_or(0)?),
+        };
+        let s = iter.next().float_or(0.5)?;
+        let ocid = iter.next().int_or(-1)?;
+        let s1 = [
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+        ];
+        Ok(CBUSH {
+            eid,
+            pid,
+            g,
+            orientation,
+            cid,
+            s,
+            ocid,
+            s1,
+        })
+    }
+}
+
+/// The nominal properties of a CBUSH, each given in the element system
+#[derive(Debug, Clone)]
+pub struct PBUSH {
+    pid: u32,
+    /// Stiffnesses in each of the six directions
+    k: [f64; 6],
+    /// Viscous damping in each of the six directions
+    b: [f64; 6],
+    /// Structural damping in each of the six directions
+    ge: [f64; 6],
+    /// Stress and strain recovery coefficients SA, ST, EA and ET
+    rcv: [f64; 4],
+    m: f64,
+}
+
+impl PBUSH {
+    /// The stiffnesses K1 to K6
+    pub fn k(&self) -> [f64; 6] {
+        self.k
+    }
+
+    /// The viscous damping B1 to B6
+    pub fn b(&self) -> [f64; 6] {
+        self.b
+    }
+
+    /// The structural damping GE1 to GE6
+    pub fn ge(&self) -> [f64; 6] {
+        self.ge
+    }
+
+    /// The stress and strain recovery coefficients SA, ST, EA and ET
+    pub fn rcv(&self) -> [f64; 4] {
+        self.rcv
+    }
+
+    /// The lumped mass
+    pub fn m(&self) -> f64 {
+        self.m
+    }
+}
+
+impl StorageItem for PBUSH {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+impl Property for PBUSH {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.pbush.get(id)
+    }
+}
+
+impl TryFrom<&BulkCard> for PBUSH {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PBUSH  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PBUSH  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PBUSH  ", *b"       ")),
+        }
+        use FieldKind::*;
+        // Every line is a keyword followed by up to six values
+        let schema: Vec<_> = [Int, Char, Real, Real, Real, Real, Real, Real]
+            .iter()
+            .copied()
+            .cycle()
+            .take(8 * 5)
+            .collect();
+        let fields = card.decode(&schema)?;
+        let pid = fields.first().copied().unwrap_or_default().id()?;
+        let mut pbush = PBUSH {
+            pid,
+            k: [0.0; 6],
+            b: [0.0; 6],
+            ge: [0.0; 6],
+            rcv: [1.0; 4],
+            m: 0.0,
+        };
+        for line in fields.chunks(8) {
+            let mut iter = line[1..].iter().copied();
+            let keyword = iter.next().unwrap_or_default();
+            let values: &mut [f64] = match keyword {
+                Field::Blank => continue,
+                Field::Text(text) => match text.trim_end() {
+                    b"K" => &mut pbush.k,
+                    b"B" => &mut pbush.b,
+                    b"GE" => &mut pbush.ge,
+                    b"RCV" => &mut pbush.rcv,
+                    b"M" => std::slice::from_mut(&mut pbush.m),
+                    _ => return Err(Error::UnexpectedField("PBUSH", keyword)),
+                },
+                _ => return Err(Error::UnexpectedField("PBUSH", keyword)),
+            };
+            for value in values.iter_mut() {
+                *value = iter.next().float_or(*value)?;
+            }
+        }
+        Ok(pbush)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdf::deck::{Deck, Orientation};
+
+    #[test]
+    fn test_bush() {
+        let input = b"CBUSH,1,2,10,11,0.,1.,0.
+CBUSH,2,2,10,,,,,0
+,,1,0.,0.,1.
+PBUSH,2,K,100.,200.,,,,6.
+,,GE,0.01
+,,RCV,2.
+,,M,3.
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let cbush = bulk.get_cbush(1).unwrap();
+        assert_eq!(cbush.g(), [10, 11]);
+        assert_eq!(cbush.orientation(), Some(Orientation::Vector(0., 1., 0.)));
+        assert_eq!(cbush.cid(), None);
+        assert_eq!(cbush.s(), 0.5);
+        assert_eq!(cbush.ocid(), None);
+        let cbush = bulk.get_cbush(2).unwrap();
+        assert_eq!(cbush.g(), [10, 0]);
+        assert_eq!(cbush.orientation(), None);
+        assert_eq!(cbush.cid(), Some(0));
+        assert_eq!(cbush.ocid(), Some(1));
+        assert_eq!(cbush.s1(), [0., 0., 1.]);
+        let pbush = bulk.get_pbush(2).unwrap();
+        assert_eq!(pbush.k(), [100., 200., 0., 0., 0., 6.]);
+        assert_eq!(pbush.b(), [0.; 6]);
+        assert_eq!(pbush.ge()[0], 0.01f32 as f64);
+        assert_eq!(pbush.rcv(), [2., 1., 1., 1.]);
+        assert_eq!(pbush.m(), 3.);
+
+        assert!(Deck::from_buffer(b"PBUSH,1,KK,1.").is_err());
+    }
+}
@@ -0,0 +1,540 @@
+use std::convert::TryFrom;
+
+use bstr::ByteSlice;
+
+use super::{BulkData, StorageItem};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+type Matrix = [[f64; 3]; 3];
+
+/// Which parts of the stiffness a laminate has
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lam {
+    /// Every ply is given, from the bottom up
+    All,
+    /// Half the plies are given, from the bottom up to the middle, and they
+    /// are mirrored about it
+    Sym,
+    /// Only the membrane stiffness
+    Mem,
+    /// Only the bending stiffness
+    Bend,
+}
+
+impl Lam {
+    fn decode(field: Field) -> Result<Self> {
+        match field {
+            Field::Blank => Ok(Lam::All),
+            Field::Text(text) if text.trim_end() == b"SYM" => Ok(Lam::Sym),
+            Field::Text(text) if text.trim_end() == b"MEM" => Ok(Lam::Mem),
+            Field::Text(text) if text.trim_end() == b"BEND" => Ok(Lam::Bend),
+            field => Err(Error::UnexpectedField("LAM", field)),
+        }
+    }
+}
+
+/// A layer of a laminate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ply {
+    pub mid: u32,
+    pub t: f64,
+    /// The angle in degrees from the material x axis of the element to the
+    /// 1 axis of the ply material
+    pub theta: f64,
+    /// Whether stresses and strains are written for the ply
+    pub sout: bool,
+}
+
+impl Ply {
+    /// Reads a ply, where a blank MID or T is the same as for the ply below
+    fn decode(mut iter: impl Iterator<Item = Field>, below: Option<&Ply>) -> Result<Self> {
+        let mid = match (iter.next().unwrap_or_default(), below) {
+            (Field::Blank, Some(below)) => below.mid,
+            (mid, _) => mid.id()?,
+        };
+        let t = match (iter.next().unwrap_or_default(), below) {
+            (Field::Blank, Some(below)) => below.t,
+            (t, _) => t.float()?,
+        };
+        let theta = iter.next().float_or(0.0)?;
+        let sout = match iter.next().unwrap_or_default() {
+            Field::Blank => false,
+            Field::Text(text) if text.trim_end() == b"NO" => false,
+            Field::Text(text) if text.trim_end() == b"YES" => true,
+            field => return Err(Error::UnexpectedField("SOUT", field)),
+        };
+        Ok(Ply {
+            mid,
+            t,
+            theta,
+            sout,
+        })
+    }
+}
+
+/// The fields PCOMP and PCOMPG share
+#[derive(Debug, Clone)]
+struct Layup {
+    /// The distance from the reference plane to the bottom surface, which is
+    /// half the thickness below it if blank
+    z0: Option<f64>,
+    nsm: f64,
+    #[allow(dead_code)]
+    sb: f64,
+    #[allow(dead_code)]
+    ft: Field,
+    #[allow(dead_code)]
+    tref: f64,
+    #[allow(dead_code)]
+    ge: f64,
+    lam: Lam,
+    plies: Vec<Ply>,
+}
+
+impl Layup {
+    /// Reads the fields of the first line, leaving the plies empty
+    fn decode(fields: &[Field]) -> Result<Self> {
+        let mut iter = fields.iter().copied();
+        iter.next();
+        let z0 = iter.next().maybe_float()?;
+        let nsm = iter.next().float_or(0.0)?;
+        let sb = iter.next().float_or(0.0)?;
+        let ft = iter.next().unwrap_or_default();
+        let tref = iter.next().float_or(0.0)?;
+        let ge = iter.next().float_or(0.0)?;
+        let lam = Lam::decode(iter.next().unwrap_or_default())?;
+        Ok(Layup {
+            z0,
+            nsm,
+            sb,
+            ft,
+            tref,
+            ge,
+            lam,
+            plies: Vec::new(),
+        })
+    }
+
+    /// Every ply from the bottom up, mirroring them for a symmetric laminate
+    fn stack(&self) -> Vec<Ply> {
+        let mut plies = self.plies.clone();
+        if self.lam == Lam::Sym {
+            plies.extend(self.plies.iter().rev());
+        }
+        plies
+    }
+
+    /// The mass per unit area of the plies and the non-structural mass
+    fn areal_mass(&self, bulk: &BulkData) -> Option<f64> {
+        let mut mass = self.nsm;
+        for ply in self.stack() {
+            mass += bulk.get_material(ply.mid)?.density() * ply.t;
+        }
+        Some(mass)
+    }
+
+    fn laminate(&self, bulk: &BulkData) -> Option<Laminate> {
+        let plies = self.stack();
+        let thickness: f64 = plies.iter().map(|p| p.t).sum();
+        let mut z = self.z0.unwrap_or(-thickness / 2.);
+        let mut laminate = Laminate {
+            a: Matrix::default(),
+            b: Matrix::default(),
+            d: Matrix::default(),
+            shear: Default::default(),
+            thickness,
+            areal_mass: self.areal_mass(bulk)?,
+        };
+        for ply in plies {
+            let material = bulk.get_material(ply.mid)?;
+            let (c, s) = {
+                let theta = ply.theta.to_radians();
+                (theta.cos(), theta.sin())
+            };
+            let q = rotate(&material.plane_stress()?, c, s);
+            let (z1, z2) = (z, z + ply.t);
+            z = z2;
+            for (i, row) in q.iter().enumerate() {
+                for (j, q) in row.iter().enumerate() {
+                    laminate.a[i][j] += q * (z2 - z1);
+                    laminate.b[i][j] += q * (z2 * z2 - z1 * z1) / 2.;
+                    laminate.d[i][j] += q * (z2.powi(3) - z1.powi(3)) / 3.;
+                }
+            }
+            // The shear strains in the ply axes, 1z and 2z, from xz and yz
+            let g = material.transverse_shear().unwrap_or_default();
+            let r = [[c, s], [-s, c]];
+            for i in 0..2 {
+                for j in 0..2 {
+                    for k in 0..2 {
+                        for l in 0..2 {
+                            laminate.shear[i][j] += r[k][i] * g[k][l] * r[l][j] * ply.t;
+                        }
+                    }
+                }
+            }
+        }
+        match self.lam {
+            Lam::All | Lam::Sym => {}
+            Lam::Mem => {
+                laminate.b = Matrix::default();
+                laminate.d = Matrix::default();
+                laminate.shear = Default::default();
+            }
+            Lam::Bend => {
+                laminate.a = Matrix::default();
+                laminate.b = Matrix::default();
+            }
+        }
+        Some(laminate)
+    }
+}
+
+/// The plane stress stiffness of a ply in the material axes of the element,
+/// from the stiffness in the ply axes turned by an angle with cosine `c` and
+/// sine `s`
+fn rotate(q: &Matrix, c: f64, s: f64) -> Matrix {
+    // Takes the engineering strains in the element axes to the ply axes. Its
+    // transpose does the same for the stresses in the other direction
+    let t = [
+        [c * c, s * s, c * s],
+        [s * s, c * c, -c * s],
+        [-2. * c * s, 2. * c * s, c * c - s * s],
+    ];
+    let mut rotated = Matrix::default();
+    for (i, row) in rotated.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            for k in 0..3 {
+                for l in 0..3 {
+                    *value += t[k][i] * q[k][l] * t[l][j];
+                }
+            }
+        }
+    }
+    rotated
+}
+
+fn inverse(m: &Matrix) -> Option<Matrix> {
+    let cofactor = |i: usize, j: usize| {
+        let (i1, i2) = ((i + 1) % 3, (i + 2) % 3);
+        let (j1, j2) = ((j + 1) % 3, (j + 2) % 3);
+        m[i1][j1] * m[i2][j2] - m[i1][j2] * m[i2][j1]
+    };
+    let det: f64 = (0..3).map(|j| m[0][j] * cofactor(0, j)).sum();
+    if det == 0.0 {
+        return None;
+    }
+    let mut inverse = Matrix::default();
+    for (i, row) in inverse.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = cofactor(j, i) / det;
+        }
+    }
+    Some(inverse)
+}
+
+/// The stiffness of a laminate in the material axes of the element, with
+/// strains and curvatures in the order xx, yy and xy
+#[derive(Debug, Clone)]
+pub struct Laminate {
+    /// The membrane stiffness
+    pub a: [[f64; 3]; 3],
+    /// The coupling between membrane and bending
+    pub b: [[f64; 3]; 3],
+    /// The bending stiffness
+    pub d: [[f64; 3]; 3],
+    /// The transverse shear stiffness for xz and yz, which is the sum of the
+    /// shear moduli of the plies times their thickness
+    pub shear: [[f64; 2]; 2],
+    pub thickness: f64,
+    /// The mass per unit area, including the non-structural mass
+    pub areal_mass: f64,
+}
+
+/// The moduli of a homogeneous plate as stiff as a laminate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Moduli {
+    pub ex: f64,
+    pub ey: f64,
+    pub gxy: f64,
+    pub nuxy: f64,
+}
+
+impl Laminate {
+    /// The moduli of a plate of the same thickness with the same membrane
+    /// stiffness, or `None` when there isn't any
+    pub fn membrane_moduli(&self) -> Option<Moduli> {
+        moduli(&self.a, self.thickness)
+    }
+
+    /// The moduli of a plate of the same thickness with the same bending
+    /// stiffness, or `None` when there isn't any
+    pub fn bending_moduli(&self) -> Option<Moduli> {
+        moduli(&self.d, self.thickness.powi(3) / 12.)
+    }
+}
+
+/// Moduli from the compliance of a stiffness that is a matrix of moduli
+/// times `scale`
+fn moduli(stiffness: &Matrix, scale: f64) -> Option<Moduli> {
+    let c = inverse(stiffness)?;
+    Some(Moduli {
+        ex: 1. / (scale * c[0][0]),
+        ey: 1. / (scale * c[1][1]),
+        gxy: 1. / (scale * c[2][2]),
+        nuxy: -c[0][1] / c[0][0],
+    })
+}
+
+/// A laminate of plies
+#[derive(Debug, Clone)]
+pub struct PCOMP {
+    pid: u32,
+    layup: Layup,
+}
+
+impl PCOMP {
+    /// The plies as given, which are half of them for a symmetric laminate
+    pub fn plies(&self) -> &[Ply] {
+        &self.layup.plies
+    }
+
+    pub fn lam(&self) -> Lam {
+        self.layup.lam
+    }
+
+    pub(super) fn areal_mass(&self, bulk: &BulkData) -> Option<f64> {
+        self.layup.areal_mass(bulk)
+    }
+
+    pub(super) fn laminate(&self, bulk: &BulkData) -> Option<Laminate> {
+        self.layup.laminate(bulk)
+    }
+}
+
+impl StorageItem for PCOMP {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+impl TryFrom<&BulkCard> for PCOMP {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PCOMP  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PCOMP  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PCOMP  ", *b"       ")),
+        }
+        use FieldKind::*;
+        // Two plies on every line after the first
+        let mut schema = vec![Int, Real, Real, Real, Char, Real, Real, Char];
+        schema.extend(
+            [Int, Real, Real, Char]
+                .iter()
+                .cycle()
+                .take(card.fields().len().saturating_sub(8)),
+        );
+        let fields = card.decode(&schema)?;
+        let pid = fields.first().copied().unwrap_or_default().id()?;
+        let mut layup = Layup::decode(&fields[..8.min(fields.len())])?;
+        for ply in fields.get(8..).unwrap_or_default().chunks(4) {
+            if ply.iter().all(|f| *f == Field::Blank) {
+                continue;
+            }
+            let ply = Ply::decode(ply.iter().copied(), layup.plies.last())?;
+            layup.plies.push(ply);
+        }
+        if layup.plies.is_empty() {
+            return Err(Error::UnexpectedField("MID1", Field::Blank));
+        }
+        Ok(PCOMP { pid, layup })
+    }
+}
+
+/// A laminate of plies that each have an id, so results can be compared
+/// for the same ply across properties
+#[derive(Debug, Clone)]
+pub struct PCOMPG {
+    pid: u32,
+    ids: Vec<u32>,
+    layup: Layup,
+}
+
+impl PCOMPG {
+    pub fn plies(&self) -> &[Ply] {
+        &self.layup.plies
+    }
+
+    /// The global id of each ply
+    pub fn global_ids(&self) -> &[u32] {
+        &self.ids
+    }
+
+    pub fn lam(&self) -> Lam {
+        self.layup.lam
+    }
+
+    pub(super) fn areal_mass(&self, bulk: &BulkData) -> Option<f64> {
+        self.layup.areal_mass(bulk)
+    }
+
+    pub(super) fn laminate(&self, bulk: &BulkData) -> Option<Laminate> {
+        self.layup.laminate(bulk)
+    }
+}
+
+impl StorageItem for PCOMPG {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+impl TryFrom<&BulkCard> for PCOMPG {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PCOMPG ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PCOMPG ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PCOMPG ", *b"       ")),
+        }
+        use FieldKind::*;
+        // One ply on every line after the first
+        let mut schema = vec![Int, Real, Real, Real, Char, Real, Real, Char];
+        schema.extend(
+            [Int, Int, Real, Real, Char, Any, Any, Any]
+                .iter()
+                .cycle()
+                .take(card.fields().len().saturating_sub(8)),
+        );
+        let fields = card.decode(&schema)?;
+        let pid = fields.first().copied().unwrap_or_default().id()?;
+        let mut layup = Layup::decode(&fields[..8.min(fields.len())])?;
+        if layup.lam == Lam::Sym {
+            return Err(Error::UnexpectedField("LAM", fields[7]));
+        }
+        let mut ids = Vec::new();
+        for line in fields.get(8..).unwrap_or_default().chunks(8) {
+            if line.iter().all(|f| *f == Field::Blank) {
+                continue;
+            }
+            let mut iter = line.iter().copied();
+            ids.push(iter.next().id()?);
+            let ply = Ply::decode(iter, layup.plies.last())?;
+            layup.plies.push(ply);
+        }
+        if layup.plies.is_empty() {
+            return Err(Error::UnexpectedField("GPLYID1", Field::Blank));
+        }
+        Ok(PCOMPG { pid, ids, layup })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::deck::Deck;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6 * b.abs().max(1.)
+    }
+
+    #[test]
+    fn test_laminate() {
+        // An isotropic material in plies of the same thickness acts like a
+        // single layer, whatever their angles
+        let input = b"MAT1,1,100.,,0.25,2.
+MAT8,2,100.,10.,0.3,5.,4.,3.,1.
+PCOMP,1,,0.5
+,1,0.1,0.,YES,,,45.
+,,0.2,-30.
+PCOMP,2,,,,,,,SYM
+,2,0.1,0.,,2,0.1,90.
+PCOMPG,3,-0.1,,,,,,MEM
+,101,2,0.1,90.
+,102,,,0.
+PCOMP,4,,,,,,,BEND
+,2,0.1
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+
+        let pcomp = bulk.get_pcomp(1).unwrap();
+        assert_eq!(pcomp.plies().len(), 3);
+        assert!(pcomp.plies()[0].sout);
+        assert_eq!(pcomp.plies()[1].mid, 1);
+        assert!(close(pcomp.plies()[2].t, 0.2));
+        let laminate = bulk.laminate(1).unwrap();
+        assert!(close(laminate.thickness, 0.4));
+        assert!(close(laminate.areal_mass, 2. * 0.4 + 0.5));
+        let membrane = laminate.membrane_moduli().unwrap();
+        assert!(close(membrane.ex, 100.));
+        assert!(close(membrane.ey, 100.));
+        assert!(close(membrane.gxy, 40.));
+        assert!(close(membrane.nuxy, 0.25));
+        assert!(close(laminate.bending_moduli().unwrap().ex, 100.));
+        assert!(laminate.b.iter().flatten().all(|b| b.abs() < 1e-9));
+        assert!(close(laminate.shear[0][0], 40. * 0.4));
+
+        // Plies at 0, 90, 90 and 0 degrees
+        let laminate = bulk.laminate(2).unwrap();
+        let q = bulk.get_mat8(2).unwrap().plane_stress();
+        assert!(close(laminate.thickness, 0.4));
+        assert!(close(laminate.a[0][0], 0.2 * (q[0][0] + q[1][1])));
+        assert!(close(laminate.a[0][1], 0.4 * q[0][1]));
+        assert!(close(laminate.a[2][2], 0.4 * q[2][2]));
+        assert!(close(
+            laminate.d[0][0],
+            (0.014 * q[0][0] + 0.002 * q[1][1]) / 3.
+        ));
+        assert!(close(laminate.shear[0][0], 0.2 * (4. + 3.)));
+        assert!(laminate.a[0][2].abs() < 1e-9);
+        assert!(laminate.b.iter().flatten().all(|b| b.abs() < 1e-9));
+
+        // A 90 and 0 degree ply, offset so the reference plane is at the
+        // bottom, with only the membrane stiffness
+        let pcompg = bulk.get_pcompg(3).unwrap();
+        assert_eq!(pcompg.global_ids(), &[101, 102]);
+        assert_eq!(pcompg.lam(), Lam::Mem);
+        let laminate = bulk.laminate(3).unwrap();
+        assert!(close(laminate.a[0][0], 0.1 * (q[0][0] + q[1][1])));
+        assert!(laminate.bending_moduli().is_none());
+
+        let laminate = bulk.laminate(4).unwrap();
+        assert!(laminate.membrane_moduli().is_none());
+        assert!(close(laminate.d[0][0], q[0][0] * 0.001 / 12.));
+
+        assert!(Deck::from_buffer(b"PCOMP,1").is_err());
+        assert!(Deck::from_buffer(b"PCOMPG,1,,,,,,,SYM\n,1,1,0.1").is_err());
+    }
+
+    #[test]
+    fn test_laminate_mass() {
+        let input = b"GRID,1,,0.,0.,0.
+GRID,2,,2.,0.,0.
+GRID,3,,2.,1.,0.
+GRID,4,,0.,1.,0.
+CQUAD4,1,1,1,2,3,4,,0.5
+CTRIA3,2,2,1,2,3
+PCOMP,1,,0.5
+,1,0.1,,,,0.2,90.
+PCOMPG,2
+,1,1,0.1
+MAT8,1,100.,10.,0.3,,,,10.
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        let (mass, cg) = deck.mass_cg(&locations);
+        let quad = 2. * (10. * 0.3 + 0.5);
+        let tria = 1. * 10. * 0.1;
+        assert!(close(mass, quad + tria));
+        let x = (quad * 1. + tria * 4. / 3.) / (quad + tria);
+        assert!(close(cg.x(), x));
+        assert!(close(cg.z(), quad * 0.5 / (quad + tria)));
+    }
+}
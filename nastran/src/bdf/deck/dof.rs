@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use super::{combined_sets, BulkData};
+use crate::bdf::case_control::Subcase;
+use crate::bdf::parser::{Field, FieldConv};
+use crate::bdf::{Error, Result};
+
+/// A component of a grid, from 1 to 6, or a scalar point with component 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Dof {
+    pub grid: u32,
+    pub component: u8,
+}
+
+impl Dof {
+    pub fn new(grid: u32, component: u8) -> Self {
+        Self { grid, component }
+    }
+}
+
+/// The DOFs of a grid for the components set in `components`
+pub(super) fn dofs(grid: u32, components: [bool; 6]) -> impl Iterator<Item = Dof> {
+    (1..=6)
+        .filter(move |&c| components[c as usize - 1])
+        .map(move |c| Dof::new(grid, c))
+}
+
+/// A single component, which is 0 for a scalar point
+pub(super) fn component(field: Field) -> Result<u8> {
+    match field.int_or(0)? {
+        c @ 0..=6 => Ok(c as u8),
+        _ => Err(Error::UnexpectedDOF(field)),
+    }
+}
+
+/// The card that makes a DOF dependent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    Rbar(u32),
+    Rbe1(u32),
+    Rbe2(u32),
+    Rbe3(u32),
+    Rrod(u32),
+    /// An equation of an MPC set
+    Mpc(u32),
+}
+
+/// What constrains a DOF that is also dependent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpcSource {
+    /// The permanent constraints of a grid, given in its PS field or by the
+    /// GRDSET
+    Grid,
+    /// An SPC set, which is the one holding the SPC or SPC1 card when it is
+    /// combined into the selected set by an SPCADD
+    Set(u32),
+}
+
+/// A problem with how the DOFs of a model depend on each other, each of
+/// which Nastran rejects with a fatal error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DofConflict {
+    /// A DOF made dependent by more than one card
+    DependentTwice {
+        dof: Dof,
+        first: Source,
+        second: Source,
+    },
+    /// A dependent DOF that is also constrained
+    DependentSpc {
+        dof: Dof,
+        dependent: Source,
+        spc: SpcSource,
+    },
+    /// Cards that each depend on a DOF made dependent by the next, with the
+    /// last depending on the first
+    Cycle(Vec<Source>),
+}
+
+/// A card that makes some DOFs depend on others
+pub(super) trait Constraint {
+    fn source(&self) -> Source;
+    /// The dependent DOFs and the independent DOFs they depend on
+    fn dofs(&self) -> (Vec<Dof>, Vec<Dof>);
+}
+
+/// Every rigid element and the equations of the MPC sets the subcase uses
+fn constraints<'a>(bulk: &'a BulkData, subcase: &Subcase) -> Vec<&'a dyn Constraint> {
+    let mut constraints: Vec<&dyn Constraint> = Vec::new();
+    constraints.extend(bulk.rbar.iter().map(|e| e as &dyn Constraint));
+    constraints.extend(bulk.rbe1.iter().map(|e| e as &dyn Constraint));
+    constraints.extend(bulk.rbe2.iter().map(|e| e as &dyn Constraint));
+    constraints.extend(bulk.rbe3.iter().map(|e| e as &dyn Constraint));
+    constraints.extend(bulk.rrod.iter().map(|e| e as &dyn Constraint));
+    let sets = subcase.mpc.map_or_else(Vec::new, |sid| {
+        combined_sets(sid, |sid| bulk.mpcadd.get(sid).map(|a| &a.sets[..]))
+    });
+    for sid in sets {
+        constraints.extend(bulk.mpc.get(sid).map(|e| e as &dyn Constraint));
+    }
+    constraints
+}
+
+pub(super) fn conflicts(bulk: &BulkData, subcase: &Subcase) -> Vec<DofConflict> {
+    let constraints = constraints(bulk, subcase);
+    let links: Vec<_> = constraints.iter().map(|c| c.dofs()).collect();
+    let mut conflicts = Vec::new();
+
+    // Every card that makes each DOF dependent, where the conflicts are
+    // reported against the first
+    let mut dependent = HashMap::<_, Vec<usize>>::new();
+    for (i, (m, _)) in links.iter().enumerate() {
+        for &dof in m {
+            let cards = dependent.entry(dof).or_default();
+            if cards.last() == Some(&i) {
+                continue;
+            }
+            if let Some(&first) = cards.first() {
+                conflicts.push(DofConflict::DependentTwice {
+                    dof,
+                    first: constraints[first].source(),
+                    second: constraints[i].source(),
+                });
+            }
+            cards.push(i);
+        }
+    }
+
+    let permanent = bulk
+        .grid
+        .iter()
+        .map(|g| (SpcSource::Grid, g.id, bulk.permanent_spc(g)));
+    let sets = subcase.spc.map_or_else(Vec::new, |sid| bulk.spc_sets(sid));
+    let spcs = sets.iter().flat_map(|&sid| {
+        bulk.spc_constraints(sid)
+            .map(move |(g, c)| (SpcSource::Set(sid), g, c))
+    });
+    for (spc, grid, components) in permanent.chain(spcs) {
+        for dof in dofs(grid, components) {
+            if let Some(cards) = dependent.get(&dof) {
+                conflicts.push(DofConflict::DependentSpc {
+                    dof,
+                    dependent: constraints[cards[0]].source(),
+                    spc,
+                });
+            }
+        }
+    }
+
+    // A card depends on another when one of its independent DOFs is made
+    // dependent by the other
+    let edges: Vec<Vec<usize>> = links
+        .iter()
+        .map(|(_, n)| {
+            let mut edges: Vec<_> = n
+                .iter()
+                .filter_map(|d| dependent.get(d))
+                .flatten()
+                .copied()
+                .collect();
+            edges.sort_unstable();
+            edges.dedup();
+            edges
+        })
+        .collect();
+    conflicts.extend(
+        cycles(&edges)
+            .into_iter()
+            .map(|c| DofConflict::Cycle(c.into_iter().map(|i| constraints[i].source()).collect())),
+    );
+    conflicts
+}
+
+/// Finds cycles with a depth first search, giving one for each edge back to
+/// a node that is still being visited
+fn cycles(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        Visiting,
+        Done,
+    }
+    let mut state = vec![State::New; edges.len()];
+    let mut cycles = Vec::new();
+    for start in 0..edges.len() {
+        if state[start] != State::New {
+            continue;
+        }
+        // The path from `start` with the next edge to follow from each node
+        let mut path = vec![(start, 0)];
+        state[start] = State::Visiting;
+        while let Some((node, next)) = path.last_mut() {
+            let node = *node;
+            match edges[node].get(*next) {
+                Some(&to) => {
+                    *next += 1;
+                    match state[to] {
+                        State::New => {
+                            state[to] = State::Visiting;
+                            path.push((to, 0));
+                        }
+                        State::Visiting => {
+                            let i = path.iter().position(|&(n, _)| n == to).unwrap();
+                            cycles.push(path[i..].iter().map(|&(n, _)| n).collect());
+                        }
+                        State::Done => {}
+                    }
+                }
+                None => {
+                    state[node] = State::Done;
+                    path.pop();
+                }
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cycles() {
+        let edges = vec![vec![1], vec![2], vec![0, 3], vec![], vec![4]];
+        assert_eq!(cycles(&edges), vec![vec![0, 1, 2], vec![4]]);
+        assert!(cycles(&[vec![1], vec![2], vec![]]).is_empty());
+    }
+}
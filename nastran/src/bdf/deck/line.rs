@@ -0,0 +1,1299 @@
+use std::convert::TryFrom;
+
+use smallvec::{smallvec, SmallVec};
+
+use super::{
+    BulkData, DeckRef, Element, GlobalLocation, HasMaterial, HasProperty, MassMoment, Material,
+    Property, Section, StorageItem,
+};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+use crate::util::{CoordSys, Vec3};
+
+/// How the element y axis of a bar or beam is found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    /// The components X1, X2 and X3 of a vector from end A
+    Vector(f64, f64, f64),
+    /// A grid G0, where the vector points from GA to G0
+    Grid(u32),
+}
+
+/// The coordinate system that a vector on a bar or beam is given in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame {
+    /// The displacement coordinate system of the grid at that end
+    Global,
+    Basic,
+    /// The element coordinate system
+    Element,
+}
+
+pub(super) fn orientation(iter: &mut impl Iterator<Item = Field>) -> Result<Option<Orientation>> {
+    let x1 = iter.next().unwrap_or_default();
+    let x2 = iter.next().float_or(0.0)?;
+    let x3 = iter.next().float_or(0.0)?;
+    match x1 {
+        // The vector would come from a BAROR or BEAMOR card
+        Field::Blank => Ok(None),
+        Field::Int(_) | Field::IntOrId(_) => Ok(Some(Orientation::Grid(x1.id()?))),
+        x1 => Ok(Some(Orientation::Vector(x1.float()?, x2, x3))),
+    }
+}
+
+/// Reads OFFT, which gives the frames of the orientation vector and of the
+/// offsets at ends A and B. The orientation vector can't be in the element
+/// system and the offsets can't be in the basic system
+fn offt(field: Field) -> Result<[Frame; 3]> {
+    let text = match field {
+        Field::Blank => return Ok([Frame::Global; 3]),
+        Field::Text(text) => text,
+        _ => return Err(Error::UnexpectedField("OFFT", field)),
+    };
+    let frame = |c, other, frame| match c {
+        b'G' => Ok(Frame::Global),
+        c if c == other => Ok(frame),
+        _ => Err(Error::UnexpectedField("OFFT", field)),
+    };
+    match text {
+        [v, a, b, b' ', b' ', b' ', b' ', b' '] => Ok([
+            frame(v, b'B', Frame::Basic)?,
+            frame(a, b'O', Frame::Element)?,
+            frame(b, b'O', Frame::Element)?,
+        ]),
+        _ => Err(Error::UnexpectedField("OFFT", field)),
+    }
+}
+
+/// Where a bar or beam lies, which is given the same way by CBAR and CBEAM
+#[derive(Debug, Clone)]
+pub(super) struct Axis {
+    ga: u32,
+    gb: u32,
+    orientation: Option<Orientation>,
+    offt: [Frame; 3],
+    /// The degrees of freedom released at each end
+    pins: [[bool; 6]; 2],
+    /// The offset of each end from its grid
+    w: [Vec3; 2],
+}
+
+impl Axis {
+    /// Reads the fields from PA to W3B
+    fn decode(
+        ga: u32,
+        gb: u32,
+        orientation: Option<Orientation>,
+        offt: [Frame; 3],
+        iter: &mut impl Iterator<Item = Field>,
+    ) -> Result<Self> {
+        let pins = [iter.next().dof()?, iter.next().dof()?];
+        let mut w = [Vec3::default(); 2];
+        for w in w.iter_mut() {
+            let x = iter.next().float_or(0.0)?;
+            let y = iter.next().float_or(0.0)?;
+            let z = iter.next().float_or(0.0)?;
+            *w = Vec3::new(x, y, z);
+        }
+        Ok(Axis {
+            ga,
+            gb,
+            orientation,
+            offt,
+            pins,
+            w,
+        })
+    }
+}
+
+/// Mass per unit length along a line element, as pairs of the distance from
+/// end A as a fraction of the length and the mass there. It varies linearly
+/// between the pairs
+pub(super) type Distribution = SmallVec<[(f64, f64); 2]>;
+
+fn uniform(mass: f64) -> Distribution {
+    smallvec![(0.0, mass), (1.0, mass)]
+}
+
+/// What's needed to find the mass of a line element
+pub(super) trait Line {
+    fn grids(&self) -> [u32; 2];
+
+    /// The orientation and offsets, which only bars and beams have
+    fn axis(&self) -> Option<&Axis> {
+        None
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution>;
+}
+
+#[derive(Debug, Clone)]
+pub struct CBAR {
+    eid: u32,
+    pid: u32,
+    axis: Axis,
+}
+
+impl CBAR {
+    /// The degrees of freedom released at ends A and B, from PA and PB
+    pub fn pins(&self) -> [[bool; 6]; 2] {
+        self.axis.pins
+    }
+}
+
+impl Element for CBAR {
+    type Property = PBAR;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Line for CBAR {
+    fn grids(&self) -> [u32; 2] {
+        [self.axis.ga, self.axis.gb]
+    }
+
+    fn axis(&self) -> Option<&Axis> {
+        Some(&self.axis)
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        bulk.with(self).property()?.mass_per_length(bulk)
+    }
+}
+
+impl StorageItem for CBAR {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CBAR {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CBAR   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CBAR   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CBAR   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Int, Int, IntOrReal, Real, Real, Char, Int, Int, Real, Real, Real, Real,
+                Real, Real,
+            ])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let ga = iter.next().id()?;
+        let gb = iter.next().id()?;
+        let orientation = orientation(&mut iter)?;
+        let offt = offt(iter.next().unwrap_or_default())?;
+        let axis = Axis::decode(ga, gb, orientation, offt, &mut iter)?;
+        Ok(CBAR { eid, pid, axis })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CBEAM {
+    eid: u32,
+    pid: u32,
+    axis: Axis,
+    /// The orientation vector set by a bit number instead of OFFT
+    bit: Option<f64>,
+    /// Scalar or grid points for warping at each end, 0 if blank
+    sa: u32,
+    sb: u32,
+}
+
+impl CBEAM {
+    /// The degrees of freedom released at ends A and B, from PA and PB
+    pub fn pins(&self) -> [[bool; 6]; 2] {
+        self.axis.pins
+    }
+
+    /// The bit number given in place of OFFT
+    pub fn bit(&self) -> Option<f64> {
+        self.bit
+    }
+
+    /// The scalar or grid point for warping at end A
+    pub fn sa(&self) -> Option<u32> {
+        Some(self.sa).filter(|&s| s > 0)
+    }
+
+    /// The scalar or grid point for warping at end B
+    pub fn sb(&self) -> Option<u32> {
+        Some(self.sb).filter(|&s| s > 0)
+    }
+}
+
+impl Element for CBEAM {
+    type Property = PBEAM;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Line for CBEAM {
+    fn grids(&self) -> [u32; 2] {
+        [self.axis.ga, self.axis.gb]
+    }
+
+    fn axis(&self) -> Option<&Axis> {
+        Some(&self.axis)
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        bulk.with(self).property()?.mass_per_length(bulk)
+    }
+}
+
+impl StorageItem for CBEAM {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CBEAM {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CBEAM  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CBEAM  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CBEAM  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Int, Int, IntOrReal, Real, Real, RealOrChar, Int, Int, Real, Real, Real,
+                Real, Real, Real, Int, Int,
+            ])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let ga = iter.next().id()?;
+        let gb = iter.next().id()?;
+        let orientation = orientation(&mut iter)?;
+        let (offt, bit) = match iter.next().unwrap_or_default() {
+            field @ Field::Float(_) | field @ Field::Double(_) => {
+                ([Frame::Global; 3], Some(field.float()?))
+            }
+            field => (offt(field)?, None),
+        };
+        let axis = Axis::decode(ga, gb, orientation, offt, &mut iter)?;
+        let sa = iter.next().id_or(0)?;
+        let sb = iter.next().id_or(0)?;
+        Ok(CBEAM {
+            eid,
+            pid,
+            axis,
+            bit,
+            sa,
+            sb,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CROD {
+    eid: u32,
+    pid: u32,
+    g: [u32; 2],
+}
+
+impl Element for CROD {
+    type Property = PROD;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Line for CROD {
+    fn grids(&self) -> [u32; 2] {
+        self.g
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        bulk.with(self).property()?.mass_per_length(bulk)
+    }
+}
+
+impl StorageItem for CROD {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CROD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CROD   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CROD   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CROD   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let g = [iter.next().id()?, iter.next().id()?];
+        Ok(CROD { eid, pid, g })
+    }
+}
+
+/// A rod with its properties given on the element
+#[derive(Debug, Clone)]
+pub struct CONROD {
+    eid: u32,
+    g: [u32; 2],
+    mid: u32,
+    a: f64,
+    j: f64,
+    c: f64,
+    nsm: f64,
+}
+
+impl CONROD {
+    /// The torsional constant
+    pub fn j(&self) -> f64 {
+        self.j
+    }
+
+    /// The coefficient for torsional stress
+    pub fn c(&self) -> f64 {
+        self.c
+    }
+}
+
+impl Line for CONROD {
+    fn grids(&self) -> [u32; 2] {
+        self.g
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        let rho = bulk.get_material(self.mid).map_or(0.0, |m| m.density());
+        Some(uniform(rho * self.a + self.nsm))
+    }
+}
+
+impl StorageItem for CONROD {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CONROD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CONROD ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CONROD ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CONROD ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Int, Int, Real, Real, Real, Real])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let g = [iter.next().id()?, iter.next().id()?];
+        let mid = iter.next().id()?;
+        let a = iter.next().float_or(0.0)?;
+        let j = iter.next().float_or(0.0)?;
+        let c = iter.next().float_or(0.0)?;
+        let nsm = iter.next().float_or(0.0)?;
+        Ok(CONROD {
+            eid,
+            g,
+            mid,
+            a,
+            j,
+            c,
+            nsm,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CTUBE {
+    eid: u32,
+    pid: u32,
+    g: [u32; 2],
+}
+
+impl Element for CTUBE {
+    type Property = PTUBE;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Line for CTUBE {
+    fn grids(&self) -> [u32; 2] {
+        self.g
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        bulk.with(self).property()?.mass_per_length(bulk)
+    }
+}
+
+impl StorageItem for CTUBE {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CTUBE {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CTUBE  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CTUBE  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CTUBE  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let g = [iter.next().id()?, iter.next().id()?];
+        Ok(CTUBE { eid, pid, g })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PBAR {
+    pid: u32,
+    mid: u32,
+    a: f64,
+    i1: f64,
+    i2: f64,
+    j: f64,
+    nsm: f64,
+    /// The y and z coordinates of the stress recovery points C, D, E and F
+    stress: [f64; 8],
+    /// Shear area factors, which are blank for no shear flexibility
+    k1: Option<f64>,
+    k2: Option<f64>,
+    i12: f64,
+}
+
+impl PBAR {
+    pub fn a(&self) -> f64 {
+        self.a
+    }
+
+    /// The area moment of inertia for bending in plane 1
+    pub fn i1(&self) -> f64 {
+        self.i1
+    }
+
+    /// The area moment of inertia for bending in plane 2
+    pub fn i2(&self) -> f64 {
+        self.i2
+    }
+
+    pub fn i12(&self) -> f64 {
+        self.i12
+    }
+
+    /// The torsional constant
+    pub fn j(&self) -> f64 {
+        self.j
+    }
+
+    pub fn nsm(&self) -> f64 {
+        self.nsm
+    }
+
+    /// The y and z coordinates of the stress recovery points C, D, E and F,
+    /// in the order C1, C2, D1, D2, E1, E2, F1, F2
+    pub fn stress_points(&self) -> [f64; 8] {
+        self.stress
+    }
+
+    /// The shear area factor for plane 1, `None` for no shear flexibility
+    pub fn k1(&self) -> Option<f64> {
+        self.k1
+    }
+
+    /// The shear area factor for plane 2, `None` for no shear flexibility
+    pub fn k2(&self) -> Option<f64> {
+        self.k2
+    }
+
+    /// The PBAR that a PBARL stands for
+    pub(super) fn from_section(pid: u32, mid: u32, section: &Section, nsm: f64) -> Self {
+        PBAR {
+            pid,
+            mid,
+            a: section.a,
+            i1: section.i1,
+            i2: section.i2,
+            j: section.j,
+            nsm,
+            stress: section.stress,
+            k1: Some(section.k1),
+            k2: Some(section.k2),
+            i12: section.i12,
+        }
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        let rho = bulk.with(self).density().unwrap_or(0.0);
+        Some(uniform(rho * self.a + self.nsm))
+    }
+}
+
+impl StorageItem for PBAR {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+impl TryFrom<&BulkCard> for PBAR {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PBAR   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PBAR   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PBAR   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Real, Real, Real, Real, Real, Any, Real, Real, Real, Real, Real, Real,
+                Real, Real, Real, Real, Real,
+            ])?
+            .into_iter();
+        let pid = iter.next().id()?;
+        let mid = iter.next().id()?;
+        let a = iter.next().float_or(0.0)?;
+        let i1 = iter.next().float_or(0.0)?;
+        let i2 = iter.next().float_or(0.0)?;
+        let j = iter.next().float_or(0.0)?;
+        let nsm = iter.next().float_or(0.0)?;
+        iter.next();
+        let mut stress = [0.0; 8];
+        for s in stress.iter_mut() {
+            *s = iter.next().float_or(0.0)?;
+        }
+        let k1 = iter.next().maybe_float()?;
+        let k2 = iter.next().maybe_float()?;
+        let i12 = iter.next().float_or(0.0)?;
+        Ok(PBAR {
+            pid,
+            mid,
+            a,
+            i1,
+            i2,
+            j,
+            nsm,
+            stress,
+            k1,
+            k2,
+            i12,
+        })
+    }
+}
+
+impl Property for PBAR {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.pbar
+            .get(id)
+            .or_else(|| bulk.pbarl.get(id).map(|p| &p.pbar))
+    }
+}
+
+impl<'a> HasMaterial<'a> for DeckRef<'a, PBAR> {
+    fn material(&self) -> Option<Material<'_>> {
+        self.bulk.get_material(self.item.mid)
+    }
+}
+
+/// Whether stresses are recovered at a station of a PBEAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StressOutput {
+    /// At the points given for the station
+    Yes,
+    /// At the points given for end A
+    YesA,
+    No,
+}
+
+/// The section at one station of a PBEAM
+#[derive(Debug, Clone)]
+pub struct Station {
+    /// The distance from end A as a fraction of the length
+    x: f64,
+    a: f64,
+    i1: f64,
+    i2: f64,
+    i12: f64,
+    j: f64,
+    nsm: f64,
+    so: StressOutput,
+    /// The y and z coordinates of the stress recovery points C, D, E and F
+    stress: [f64; 8],
+}
+
+impl Station {
+    /// The distance from end A as a fraction of the length, X/XB
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn a(&self) -> f64 {
+        self.a
+    }
+
+    /// The area moment of inertia for bending in plane 1
+    pub fn i1(&self) -> f64 {
+        self.i1
+    }
+
+    /// The area moment of inertia for bending in plane 2
+    pub fn i2(&self) -> f64 {
+        self.i2
+    }
+
+    pub fn i12(&self) -> f64 {
+        self.i12
+    }
+
+    /// The torsional constant
+    pub fn j(&self) -> f64 {
+        self.j
+    }
+
+    pub fn nsm(&self) -> f64 {
+        self.nsm
+    }
+
+    pub fn stress_output(&self) -> StressOutput {
+        self.so
+    }
+
+    /// The y and z coordinates of the stress recovery points C, D, E and F,
+    /// in the order C1, C2, D1, D2, E1, E2, F1, F2. They're 0 when stresses
+    /// aren't recovered at the station
+    pub fn stress_points(&self) -> [f64; 8] {
+        self.stress
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PBEAM {
+    pid: u32,
+    mid: u32,
+    /// End A, any intermediate stations and end B, in order along the beam
+    stations: Vec<Station>,
+    /// Shear stiffness factors
+    k: [f64; 2],
+    /// Shear relief coefficients
+    s: [f64; 2],
+    /// Nonstructural mass moments of inertia at ends A and B
+    nsi: [f64; 2],
+    /// Warping coefficients at ends A and B
+    cw: [f64; 2],
+    /// The y and z coordinates of the center of gravity of the nonstructural
+    /// mass at ends A and B
+    m: [f64; 4],
+    /// The y and z coordinates of the neutral axis at ends A and B
+    n: [f64; 4],
+}
+
+impl PBEAM {
+    /// End A, any intermediate stations and end B, in order along the beam.
+    /// Blank values are filled in, so every station has its full section
+    pub fn stations(&self) -> &[Station] {
+        &self.stations
+    }
+
+    /// The shear stiffness factors K1 and K2
+    pub fn k(&self) -> [f64; 2] {
+        self.k
+    }
+
+    /// The shear relief coefficients S1 and S2
+    pub fn s(&self) -> [f64; 2] {
+        self.s
+    }
+
+    /// The nonstructural mass moments of inertia at ends A and B
+    pub fn nsi(&self) -> [f64; 2] {
+        self.nsi
+    }
+
+    /// The warping coefficients at ends A and B
+    pub fn cw(&self) -> [f64; 2] {
+        self.cw
+    }
+
+    /// The y and z coordinates of the center of gravity of the nonstructural
+    /// mass, as M1(A), M2(A), M1(B), M2(B)
+    pub fn m(&self) -> [f64; 4] {
+        self.m
+    }
+
+    /// The y and z coordinates of the neutral axis, as N1(A), N2(A), N1(B),
+    /// N2(B)
+    pub fn n(&self) -> [f64; 4] {
+        self.n
+    }
+
+    /// The PBEAM that a PBEAML stands for, from the position, stress output,
+    /// section and nonstructural mass of each station
+    pub(super) fn from_sections(
+        pid: u32,
+        mid: u32,
+        sections: &[(f64, StressOutput, Section, f64)],
+    ) -> Self {
+        let stations = sections
+            .iter()
+            .map(|&(x, so, section, nsm)| Station {
+                x,
+                a: section.a,
+                i1: section.i1,
+                i2: section.i2,
+                i12: section.i12,
+                j: section.j,
+                nsm,
+                so,
+                stress: section.stress,
+            })
+            .collect::<Vec<_>>();
+        let k = sections.first().map_or([1.0; 2], |s| [s.2.k1, s.2.k2]);
+        PBEAM {
+            pid,
+            mid,
+            stations,
+            k,
+            s: [0.0; 2],
+            nsi: [0.0; 2],
+            cw: [0.0; 2],
+            m: [0.0; 4],
+            n: [0.0; 4],
+        }
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        let rho = bulk.with(self).density().unwrap_or(0.0);
+        let mut distribution = self
+            .stations
+            .iter()
+            .map(|s| (s.x, rho * s.a + s.nsm))
+            .collect::<Distribution>();
+        // Without end B the section is the same along the beam
+        if let Some(&(x, m)) = distribution.last() {
+            if x < 1.0 {
+                distribution.push((1.0, m));
+            }
+        }
+        Some(distribution)
+    }
+}
+
+impl StorageItem for PBEAM {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+/// The section values of a station, which can be blank after end A
+type Values = [Option<f64>; 6];
+
+fn values(iter: &mut impl Iterator<Item = Field>) -> Result<Values> {
+    let mut values = [None; 6];
+    for v in values.iter_mut() {
+        *v = iter.next().maybe_float()?;
+    }
+    Ok(values)
+}
+
+fn stress_points(iter: &mut impl Iterator<Item = Field>) -> Result<[f64; 8]> {
+    let mut stress = [0.0; 8];
+    for s in stress.iter_mut() {
+        *s = iter.next().float_or(0.0)?;
+    }
+    Ok(stress)
+}
+
+impl TryFrom<&BulkCard> for PBEAM {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PBEAM  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PBEAM  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PBEAM  ", *b"       ")),
+        }
+        // The number of stations isn't fixed, so only end A has a schema and
+        // the rest is read by whether a line starts with SO
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real,
+                Real, Real,
+            ])?
+            .into_iter()
+            .peekable();
+        let pid = iter.next().id()?;
+        let mid = iter.next().id()?;
+        let end_a = values(&mut iter)?;
+        let stress_a = stress_points(&mut iter)?;
+        let mut stations = vec![(0.0, end_a, StressOutput::Yes, stress_a)];
+        while let Some(Field::Text(text)) = iter.peek().copied() {
+            let field = iter.next().unwrap_or_default();
+            let so = match text.split(|&c| c == b' ').next() {
+                Some(b"YES") => StressOutput::Yes,
+                Some(b"YESA") => StressOutput::YesA,
+                Some(b"NO") => StressOutput::No,
+                _ => return Err(Error::UnexpectedField("SO", field)),
+            };
+            let x = iter.next().float()?;
+            let values = values(&mut iter)?;
+            let stress = match so {
+                StressOutput::Yes => stress_points(&mut iter)?,
+                StressOutput::YesA => stress_a,
+                StressOutput::No => [0.0; 8],
+            };
+            stations.push((x, values, so, stress));
+        }
+        // Blanks at end B are the values at end A, and blanks in between are
+        // interpolated between the ends
+        let end_b = stations
+            .iter()
+            .rev()
+            .find(|s| s.0 == 1.0)
+            .map_or(end_a, |s| s.1);
+        let end_b = [0, 1, 2, 3, 4, 5].map(|i| end_b[i].or(end_a[i]));
+        let stations = stations
+            .into_iter()
+            .map(|(x, values, so, stress)| {
+                let v = [0, 1, 2, 3, 4, 5].map(|i| {
+                    let a = end_a[i].unwrap_or(0.0);
+                    let b = end_b[i].unwrap_or(a);
+                    values[i].unwrap_or(a + x * (b - a))
+                });
+                Station {
+                    x,
+                    a: v[0],
+                    i1: v[1],
+                    i2: v[2],
+                    i12: v[3],
+                    j: v[4],
+                    nsm: v[5],
+                    so,
+                    stress,
+                }
+            })
+            .collect();
+        let k = [iter.next().float_or(1.0)?, iter.next().float_or(1.0)?];
+        let s = [iter.next().float_or(0.0)?, iter.next().float_or(0.0)?];
+        let nsi_a = iter.next().float_or(0.0)?;
+        let nsi = [nsi_a, iter.next().float_or(nsi_a)?];
+        let cw_a = iter.next().float_or(0.0)?;
+        let cw = [cw_a, iter.next().float_or(cw_a)?];
+        let mut m = [0.0; 4];
+        for m in m.iter_mut() {
+            *m = iter.next().float_or(0.0)?;
+        }
+        let mut n = [0.0; 4];
+        for n in n.iter_mut() {
+            *n = iter.next().float_or(0.0)?;
+        }
+        Ok(PBEAM {
+            pid,
+            mid,
+            stations,
+            k,
+            s,
+            nsi,
+            cw,
+            m,
+            n,
+        })
+    }
+}
+
+impl Property for PBEAM {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.pbeam
+            .get(id)
+            .or_else(|| bulk.pbeaml.get(id).map(|p| &p.pbeam))
+    }
+}
+
+impl<'a> HasMaterial<'a> for DeckRef<'a, PBEAM> {
+    fn material(&self) -> Option<Material<'_>> {
+        self.bulk.get_material(self.item.mid)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PROD {
+    pid: u32,
+    mid: u32,
+    a: f64,
+    j: f64,
+    c: f64,
+    nsm: f64,
+}
+
+impl PROD {
+    /// The torsional constant
+    pub fn j(&self) -> f64 {
+        self.j
+    }
+
+    /// The coefficient for torsional stress
+    pub fn c(&self) -> f64 {
+        self.c
+    }
+
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        let rho = bulk.with(self).density().unwrap_or(0.0);
+        Some(uniform(rho * self.a + self.nsm))
+    }
+}
+
+impl StorageItem for PROD {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+impl TryFrom<&BulkCard> for PROD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PROD   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PROD   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PROD   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Real, Real, Real, Real])?
+            .into_iter();
+        let pid = iter.next().id()?;
+        let mid = iter.next().id()?;
+        let a = iter.next().float_or(0.0)?;
+        let j = iter.next().float_or(0.0)?;
+        let c = iter.next().float_or(0.0)?;
+        let nsm = iter.next().float_or(0.0)?;
+        Ok(PROD {
+            pid,
+            mid,
+            a,
+            j,
+            c,
+            nsm,
+        })
+    }
+}
+
+impl Property for PROD {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.prod.get(id)
+    }
+}
+
+impl<'a> HasMaterial<'a> for DeckRef<'a, PROD> {
+    fn material(&self) -> Option<Material<'_>> {
+        self.bulk.get_material(self.item.mid)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PTUBE {
+    pid: u32,
+    mid: u32,
+    /// The outside diameter at end A
+    od: f64,
+    /// The wall thickness, which is blank for a solid rod
+    t: Option<f64>,
+    nsm: f64,
+    /// The outside diameter at end B
+    od2: f64,
+}
+
+impl PTUBE {
+    fn area(&self, od: f64) -> f64 {
+        let t = self.t.unwrap_or(od / 2.);
+        std::f64::consts::PI * t * (od - t)
+    }
+
+    /// The area varies linearly between the ends, which is exact for a
+    /// tapered tube and close for a tapered rod
+    fn mass_per_length(&self, bulk: &BulkData) -> Option<Distribution> {
+        let rho = bulk.with(self).density().unwrap_or(0.0);
+        Some(smallvec![
+            (0.0, rho * self.area(self.od) + self.nsm),
+            (1.0, rho * self.area(self.od2) + self.nsm),
+        ])
+    }
+}
+
+impl StorageItem for PTUBE {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+impl TryFrom<&BulkCard> for PTUBE {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PTUBE  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PTUBE  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PTUBE  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Real, Real, Real, Real])?
+            .into_iter();
+        let pid = iter.next().id()?;
+        let mid = iter.next().id()?;
+        let od = iter.next().float()?;
+        let t = iter.next().maybe_float()?;
+        let nsm = iter.next().float_or(0.0)?;
+        let od2 = iter.next().float_or(od)?;
+        Ok(PTUBE {
+            pid,
+            mid,
+            od,
+            t,
+            nsm,
+            od2,
+        })
+    }
+}
+
+impl Property for PTUBE {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.ptube.get(id)
+    }
+}
+
+impl<'a> HasMaterial<'a> for DeckRef<'a, PTUBE> {
+    fn material(&self) -> Option<Material<'_>> {
+        self.bulk.get_material(self.item.mid)
+    }
+}
+
+/// Turns a vector in the displacement coordinate system of a grid into the
+/// basic system
+fn displacement_to_basic(
+    bulk: &BulkData,
+    location: &GlobalLocation,
+    grid: u32,
+    v: Vec3,
+) -> Option<Vec3> {
+    match bulk.get_grid(grid)?.cd {
+        0 => Some(v),
+        cd => location.get_csys(cd).map(|c| c.rotate(v)),
+    }
+}
+
+impl<T: Line> DeckRef<'_, T> {
+    /// The element axes from end A towards end B, with the y axis in the
+    /// plane of the orientation vector
+    fn element_axes(&self, location: &GlobalLocation, a: Vec3, b: Vec3) -> Option<CoordSys> {
+        let axis = self.axis()?;
+        let v = match axis.orientation? {
+            Orientation::Grid(g0) => location.get_grid(g0)?.to_vec() - a,
+            Orientation::Vector(x1, x2, x3) => {
+                let v = Vec3::new(x1, x2, x3);
+                match axis.offt[0] {
+                    Frame::Global => displacement_to_basic(self.bulk, location, axis.ga, v)?,
+                    _ => v,
+                }
+            }
+        };
+        let x = (b - a).normalize();
+        let z = x.cross(v).normalize();
+        let y = z.cross(x);
+        Some(CoordSys::new(x, y, z, Vec3::default()))
+    }
+
+    /// The ends of the element after any offsets. Offsets in the element
+    /// system use the axes between the grids
+    pub(super) fn ends(&self, location: &GlobalLocation) -> Option<(Vec3, Vec3)> {
+        let [ga, gb] = self.grids();
+        let a = location.get_grid(ga)?.to_vec();
+        let b = location.get_grid(gb)?.to_vec();
+        let axis = match self.axis() {
+            Some(axis) => axis,
+            None => return Some((a, b)),
+        };
+        let mut ends = [a, b];
+        for (i, (grid, end)) in [ga, gb].iter().zip(ends.iter_mut()).enumerate() {
+            let w = axis.w[i];
+            if w.length() == 0.0 {
+                continue;
+            }
+            *end += match axis.offt[i + 1] {
+                Frame::Global => displacement_to_basic(self.bulk, location, *grid, w)?,
+                Frame::Basic => w,
+                Frame::Element => self.element_axes(location, a, b)?.rotate(w),
+            };
+        }
+        Some((ends[0], ends[1]))
+    }
+}
+
+/// The mass of a line element from its mass per unit length between its
+/// ends
+pub(super) fn mass_moment<T: Line>(
+    element: &DeckRef<'_, T>,
+    location: &GlobalLocation,
+) -> Option<MassMoment> {
+    let distribution = element.mass_per_length(element.bulk)?;
+    let (a, b) = element.ends(location)?;
+    let length = (b - a).length();
+    let mut mm = MassMoment::default();
+    for pair in distribution.windows(2) {
+        let ((x0, m0), (x1, m1)) = (pair[0], pair[1]);
+        let mass = length * (x1 - x0) * (m0 + m1) / 2.;
+        if mass == 0.0 {
+            continue;
+        }
+        // The centroid of the trapezoid between the two stations
+        let x = x0 + (x1 - x0) * (m0 + 2. * m1) / (3. * (m0 + m1));
+        mm.mass += mass;
+        mm.moment += mass * (a + x * (b - a));
+    }
+    Some(mm)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::deck::Deck;
+
+    const INPUT: &[u8] = b"GRID,1,,0.,0.,0.
+GRID,2,,2.,0.,0.
+GRID,3,,0.,1.,0.
+CBAR,1,1,1,2,0.,1.,0.,
+,12,456,0.,0.,1.,0.,0.,1.
+PBAR,1,1,0.5,2.,3.,4.,1.
+,0.5,0.5,-0.5,0.5,,,,
+,0.75,,0.25
+CROD,2,2,1,3
+PROD,2,1,1.,5.,6.
+CONROD,3,1,3,1,2.,3.,4.
+CTUBE,4,4,1,2
+PTUBE,4,1,1.,,,2.
+MAT1,1,1.+7,,0.3,2.
+";
+
+    #[test]
+    fn test_line_mass() {
+        let deck = Deck::from_buffer(INPUT).unwrap();
+        let locations = deck.global_locations();
+        let bulk = deck.main();
+        let bar = bulk.with(bulk.get_cbar(1).unwrap());
+        let (a, b) = bar.ends(&locations).unwrap();
+        assert!((a - Vec3::new(0., 0., 1.)).length() < 1e-12);
+        assert!(((b - a).length() - 2.).abs() < 1e-12);
+
+        let mass = |e: Option<MassMoment>| e.unwrap().mass;
+        // A of 0.5 with density 2 and NSM of 1 over a length of 2
+        assert!((mass(mass_moment(&bar, &locations)) - 4.).abs() < 1e-6);
+        let rod = bulk.with(bulk.get_crod(2).unwrap());
+        assert!((mass(mass_moment(&rod, &locations)) - 2.).abs() < 1e-6);
+        let conrod = bulk.with(bulk.get_conrod(3).unwrap());
+        assert!((mass(mass_moment(&conrod, &locations)) - 4.).abs() < 1e-6);
+
+        // The tube tapers from a diameter of 1 to 2, so the mass per length
+        // goes from pi / 2 to 2 pi
+        let tube = bulk.with(bulk.get_ctube(4).unwrap());
+        let mm = mass_moment(&tube, &locations).unwrap();
+        let pi = std::f64::consts::PI;
+        assert!((mm.mass - 2.5 * pi).abs() < 1e-6);
+        let x = 2. * (0.5 + 2. * 2.) / (3. * 2.5);
+        assert!(((mm.moment / mm.mass).x() - x).abs() < 1e-6);
+
+        let pins = bulk.get_cbar(1).unwrap().pins();
+        assert_eq!(pins[0], [true, true, false, false, false, false]);
+        assert_eq!(pins[1], [false, false, false, true, true, true]);
+        let pbar = bulk.get_pbar(1).unwrap();
+        assert_eq!(
+            [pbar.a(), pbar.i1(), pbar.i2(), pbar.j()],
+            [0.5, 2., 3., 4.]
+        );
+        assert_eq!((pbar.nsm(), pbar.i12()), (1., 0.25));
+        assert_eq!(pbar.stress_points()[..4], [0.5, 0.5, -0.5, 0.5]);
+        assert_eq!((pbar.k1(), pbar.k2()), (Some(0.75), None));
+        let prod = bulk.get_prod(2).unwrap();
+        assert_eq!((prod.j(), prod.c()), (5., 6.));
+        let conrod = bulk.get_conrod(3).unwrap();
+        assert_eq!((conrod.j(), conrod.c()), (3., 4.));
+    }
+
+    #[test]
+    fn test_pbeam_stations() {
+        let input = b"PBEAM,1,1,2.,1.,1.,,1.,0.5
+,0.1,0.2
+,YES,0.5,,,,,,
+,1.,1.,1.,1.,1.,1.,1.,1.
+,NO,1.,1.
+,0.9,,0.25,,0.375,,0.5,0.625
+,,,,,0.75
+CBEAM,2,1,1,2,0.,1.,0.,7.
+,1,2
+,10
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let pbeam = deck.main().get_pbeam(1).unwrap();
+        let stations = pbeam.stations();
+        let x = stations.iter().map(Station::x).collect::<Vec<_>>();
+        assert_eq!(x, vec![0.0, 0.5, 1.0]);
+        // The middle station is interpolated and end B takes what's blank
+        // from end A
+        let a = stations.iter().map(Station::a).collect::<Vec<_>>();
+        assert_eq!(a, vec![2.0, 1.5, 1.0]);
+        assert_eq!(stations[1].i1(), 1.0);
+        assert_eq!(stations[2].i2(), 1.0);
+        assert_eq!(stations[2].i12(), 0.0);
+        assert_eq!(stations[1].j(), 1.0);
+        assert_eq!(stations[2].nsm(), 0.5);
+        assert_eq!(stations[1].stress_points()[0], 1.0);
+        assert_eq!(stations[2].stress_output(), StressOutput::No);
+        assert!((pbeam.k()[0] - 0.9).abs() < 1e-6);
+        assert_eq!(pbeam.k()[1], 1.0);
+        assert_eq!(pbeam.s(), [0.25, 0.0]);
+        assert_eq!(pbeam.nsi(), [0.375, 0.375]);
+        assert_eq!(pbeam.cw(), [0.5, 0.625]);
+        assert_eq!(pbeam.m(), [0.0; 4]);
+        assert_eq!(pbeam.n(), [0.75, 0.0, 0.0, 0.0]);
+
+        let cbeam = deck.main().get_cbeam(2).unwrap();
+        assert_eq!(cbeam.pins()[0], [true, false, false, false, false, false]);
+        assert_eq!(cbeam.pins()[1], [false, true, false, false, false, false]);
+        assert_eq!(cbeam.bit(), Some(7.));
+        assert_eq!((cbeam.sa(), cbeam.sb()), (Some(10), None));
+    }
+}
@@ -0,0 +1,1055 @@
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+
+use bstr::ByteSlice;
+use smallvec::SmallVec;
+
+use super::shell::Shell;
+use super::solid::{self, Solid};
+use super::{id_list, BulkData, GlobalLocation, SetItem, StorageItem};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+use crate::util::{Vec3, Xyz};
+
+/// The grids at the corners of a face
+type Face = SmallVec<[u32; 4]>;
+
+/// A force and a moment in the basic system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Resultant {
+    pub force: Vec3,
+    pub moment: Vec3,
+}
+
+impl Resultant {
+    /// A force acting through `xyz`, with its moment about the origin
+    fn at(force: Vec3, xyz: Vec3) -> Self {
+        Self {
+            force,
+            moment: xyz.cross(force),
+        }
+    }
+
+    fn moment(moment: Vec3) -> Self {
+        Self {
+            force: Vec3::default(),
+            moment,
+        }
+    }
+
+    /// The same load with its moment taken about `point` rather than the
+    /// origin
+    pub fn about(self, point: Vec3) -> Self {
+        Self {
+            force: self.force,
+            moment: self.moment - point.cross(self.force),
+        }
+    }
+}
+
+/// A load left out of a resultant because a grid, element or coordinate
+/// system it refers to is missing, or its direction can't be found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkippedLoad {
+    /// The card type, padded like `BulkCard::card_type`
+    pub card: [u8; 7],
+    pub sid: u32,
+    /// The grid a concentrated load or RFORCE acts at, the first grid of a
+    /// PLOAD, the element of a PLOAD2 or PLOAD4, or the coordinate system of
+    /// a GRAV
+    pub id: u32,
+}
+
+impl std::ops::Add<Resultant> for Resultant {
+    type Output = Resultant;
+    fn add(self, rhs: Resultant) -> Resultant {
+        Resultant {
+            force: self.force + rhs.force,
+            moment: self.moment + rhs.moment,
+        }
+    }
+}
+
+impl std::ops::Mul<Resultant> for f64 {
+    type Output = Resultant;
+    fn mul(self, rhs: Resultant) -> Resultant {
+        Resultant {
+            force: self * rhs.force,
+            moment: self * rhs.moment,
+        }
+    }
+}
+
+impl std::iter::Sum for Resultant {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Default::default(), |i, r| i + r)
+    }
+}
+
+/// A vector given in a coordinate system, turned into the basic system
+fn in_basic(location: &GlobalLocation, cid: u32, v: Vec3) -> Option<Vec3> {
+    match cid {
+        0 => Some(v),
+        cid => Some(location.get_csys(cid)?.rotate(v)),
+    }
+}
+
+fn grid(location: &GlobalLocation, g: u32) -> Option<Vec3> {
+    location.get_grid(g).map(Xyz::to_vec)
+}
+
+/// A unit vector, or `None` for a zero vector
+fn unit(v: Vec3) -> Option<Vec3> {
+    let length = v.length();
+    (length > 0.0).then(|| v / length)
+}
+
+/// The load from a pressure varying linearly between the corners of a flat
+/// face. It acts along `direction` or otherwise along the normal given by the
+/// right hand rule on the corners. Quadrilaterals are split into two
+/// triangles
+fn face_load(xyz: &[Vec3], p: &[f64], direction: Option<Vec3>) -> Resultant {
+    let triangles: &[[usize; 3]] = if xyz.len() == 4 {
+        &[[0, 1, 2], [0, 2, 3]]
+    } else {
+        &[[0, 1, 2]]
+    };
+    let mut resultant = Resultant::default();
+    for &[i, j, k] in triangles {
+        let n = (xyz[j] - xyz[i]).cross(xyz[k] - xyz[i]);
+        let area = n.length() / 2.;
+        if area == 0.0 {
+            continue;
+        }
+        let d = direction.unwrap_or(n / (2. * area));
+        let sum_p = p[i] + p[j] + p[k];
+        let sum_xyz = xyz[i] + xyz[j] + xyz[k];
+        let weighted = p[i] * xyz[i] + p[j] * xyz[j] + p[k] * xyz[k];
+        // The integrals of the pressure and of the pressure times position
+        let total = area * sum_p / 3.;
+        let first = area / 12. * (weighted + sum_p * sum_xyz);
+        resultant = resultant
+            + Resultant {
+                force: total * d,
+                moment: first.cross(d),
+            };
+    }
+    resultant
+}
+
+#[derive(Debug, Clone)]
+pub struct FORCE {
+    sid: u32,
+    g: u32,
+    cid: u32,
+    f: f64,
+    n: Vec3,
+}
+
+impl FORCE {
+    fn decode(card: &BulkCard) -> Result<Self> {
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Int, Real, Real, Real, Real])?
+            .into_iter();
+        let sid = iter.next().id()?;
+        let g = iter.next().id()?;
+        let cid = iter.next().id_or(0)?;
+        let f = iter.next().float()?;
+        let n = Vec3::new(
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+        );
+        Ok(FORCE { sid, g, cid, f, n })
+    }
+
+    fn vector(&self, location: &GlobalLocation) -> Option<Vec3> {
+        Some(self.f * in_basic(location, self.cid, self.n)?)
+    }
+
+    fn resultant(&self, location: &GlobalLocation) -> Option<Resultant> {
+        Some(Resultant::at(
+            self.vector(location)?,
+            grid(location, self.g)?,
+        ))
+    }
+}
+
+impl SetItem for FORCE {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for FORCE {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"FORCE  ") => FORCE::decode(card),
+            Some(c) => Err(Error::UnexpectedCardType(*b"FORCE  ", *c)),
+            None => Err(Error::UnexpectedCardType(*b"FORCE  ", *b"       ")),
+        }
+    }
+}
+
+/// A MOMENT has the same fields as a FORCE
+#[derive(Debug, Clone)]
+pub struct MOMENT(FORCE);
+
+impl std::ops::Deref for MOMENT {
+    type Target = FORCE;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl MOMENT {
+    fn resultant(&self, location: &GlobalLocation) -> Option<Resultant> {
+        Some(Resultant::moment(self.vector(location)?))
+    }
+}
+
+impl SetItem for MOMENT {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for MOMENT {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MOMENT ") => FORCE::decode(card).map(MOMENT),
+            Some(c) => Err(Error::UnexpectedCardType(*b"MOMENT ", *c)),
+            None => Err(Error::UnexpectedCardType(*b"MOMENT ", *b"       ")),
+        }
+    }
+}
+
+/// A force along the line from grid G1 to grid G2
+#[derive(Debug, Clone)]
+pub struct FORCE1 {
+    sid: u32,
+    g: u32,
+    f: f64,
+    g1: u32,
+    g2: u32,
+}
+
+impl FORCE1 {
+    fn decode(card: &BulkCard) -> Result<Self> {
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Real, Int, Int])?.into_iter();
+        let sid = iter.next().id()?;
+        let g = iter.next().id()?;
+        let f = iter.next().float()?;
+        let g1 = iter.next().id()?;
+        let g2 = iter.next().id()?;
+        Ok(FORCE1 { sid, g, f, g1, g2 })
+    }
+
+    fn vector(&self, location: &GlobalLocation) -> Option<Vec3> {
+        let d = unit(grid(location, self.g2)? - grid(location, self.g1)?)?;
+        Some(self.f * d)
+    }
+
+    fn resultant(&self, location: &GlobalLocation) -> Option<Resultant> {
+        Some(Resultant::at(
+            self.vector(location)?,
+            grid(location, self.g)?,
+        ))
+    }
+}
+
+impl SetItem for FORCE1 {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for FORCE1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"FORCE1 ") => FORCE1::decode(card),
+            Some(c) => Err(Error::UnexpectedCardType(*b"FORCE1 ", *c)),
+            None => Err(Error::UnexpectedCardType(*b"FORCE1 ", *b"       ")),
+        }
+    }
+}
+
+/// A MOMENT1 has the same fields as a FORCE1
+#[derive(Debug, Clone)]
+pub struct MOMENT1(FORCE1);
+
+impl std::ops::Deref for MOMENT1 {
+    type Target = FORCE1;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl MOMENT1 {
+    fn resultant(&self, location: &GlobalLocation) -> Option<Resultant> {
+        Some(Resultant::moment(self.vector(location)?))
+    }
+}
+
+impl SetItem for MOMENT1 {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for MOMENT1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MOMENT1") => FORCE1::decode(card).map(MOMENT1),
+            Some(c) => Err(Error::UnexpectedCardType(*b"MOMENT1", *c)),
+            None => Err(Error::UnexpectedCardType(*b"MOMENT1", *b"       ")),
+        }
+    }
+}
+
+/// A force along the cross product of the lines from G1 to G2 and from G3 to
+/// G4
+#[derive(Debug, Clone)]
+pub struct FORCE2 {
+    sid: u32,
+    g: u32,
+    f: f64,
+    grids: [u32; 4],
+}
+
+impl FORCE2 {
+    fn decode(card: &BulkCard) -> Result<Self> {
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Real, Int, Int, Int, Int])?
+            .into_iter();
+        let sid = iter.next().id()?;
+        let g = iter.next().id()?;
+        let f = iter.next().float()?;
+        let mut grids = [0; 4];
+        for g in grids.iter_mut() {
+            *g = iter.next().id()?;
+        }
+        Ok(FORCE2 { sid, g, f, grids })
+    }
+
+    fn vector(&self, location: &GlobalLocation) -> Option<Vec3> {
+        let [g1, g2, g3, g4] = self.grids;
+        let a = grid(location, g2)? - grid(location, g1)?;
+        let b = grid(location, g4)? - grid(location, g3)?;
+        Some(self.f * unit(a.cross(b))?)
+    }
+
+    fn resultant(&self, location: &GlobalLocation) -> Option<Resultant> {
+        Some(Resultant::at(
+            self.vector(location)?,
+            grid(location, self.g)?,
+        ))
+    }
+}
+
+impl SetItem for FORCE2 {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for FORCE2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"FORCE2 ") => FORCE2::decode(card),
+            Some(c) => Err(Error::UnexpectedCardType(*b"FORCE2 ", *c)),
+            None => Err(Error::UnexpectedCardType(*b"FORCE2 ", *b"       ")),
+        }
+    }
+}
+
+/// A MOMENT2 has the same fields as a FORCE2
+#[derive(Debug, Clone)]
+pub struct MOMENT2(FORCE2);
+
+impl std::ops::Deref for MOMENT2 {
+    type Target = FORCE2;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl MOMENT2 {
+    fn resultant(&self, location: &GlobalLocation) -> Option<Resultant> {
+        Some(Resultant::moment(self.vector(location)?))
+    }
+}
+
+impl SetItem for MOMENT2 {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for MOMENT2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MOMENT2") => FORCE2::decode(card).map(MOMENT2),
+            Some(c) => Err(Error::UnexpectedCardType(*b"MOMENT2", *c)),
+            None => Err(Error::UnexpectedCardType(*b"MOMENT2", *b"       ")),
+        }
+    }
+}
+
+/// A pressure on the triangle or quadrilateral between three or four grids
+#[derive(Debug, Clone)]
+pub struct PLOAD {
+    sid: u32,
+    p: f64,
+    grids: SmallVec<[u32; 4]>,
+}
+
+impl PLOAD {
+    fn resultant(&self, location: &GlobalLocation) -> Option<Resultant> {
+        let xyz = self
+            .grids
+            .iter()
+            .map(|&g| grid(location, g))
+            .collect::<Option<SmallVec<[Vec3; 4]>>>()?;
+        Some(face_load(&xyz, &[self.p; 4], None))
+    }
+}
+
+impl SetItem for PLOAD {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for PLOAD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PLOAD  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PLOAD  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PLOAD  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Real, Int, Int, Int, Int])?.into_iter();
+        let sid = iter.next().id()?;
+        let p = iter.next().float()?;
+        let mut grids = SmallVec::new();
+        for _ in 0..3 {
+            grids.push(iter.next().id()?);
+        }
+        if let g4 @ Field::IntOrId(_) = iter.next().unwrap_or_default() {
+            grids.push(g4.id()?);
+        }
+        Ok(PLOAD { sid, p, grids })
+    }
+}
+
+/// The corners of a shell element of any type
+fn shell_corners(bulk: &BulkData, eid: u32) -> Option<&[u32]> {
+    None.or_else(|| bulk.cquad4.get(eid).map(Shell::corners))
+        .or_else(|| bulk.ctria3.get(eid).map(Shell::corners))
+        .or_else(|| bulk.cquadr.get(eid).map(Shell::corners))
+        .or_else(|| bulk.ctriar.get(eid).map(Shell::corners))
+        .or_else(|| bulk.cquad8.get(eid).map(Shell::corners))
+        .or_else(|| bulk.ctria6.get(eid).map(Shell::corners))
+}
+
+fn has_solid(bulk: &BulkData, eid: u32) -> bool {
+    bulk.chexa.get(eid).is_some()
+        || bulk.cpenta.get(eid).is_some()
+        || bulk.ctetra.get(eid).is_some()
+        || bulk.cpyram.get(eid).is_some()
+}
+
+/// A uniform pressure on shell elements
+#[derive(Debug, Clone)]
+pub struct PLOAD2 {
+    sid: u32,
+    p: f64,
+    eids: Vec<u32>,
+}
+
+impl PLOAD2 {
+    /// The load on each element, or `None` where it can't be found
+    fn loads<'a>(
+        &'a self,
+        bulk: &'a BulkData,
+        location: &'a GlobalLocation,
+    ) -> impl Iterator<Item = (u32, Option<Resultant>)> + 'a {
+        self.eids.iter().map(move |&eid| {
+            let load = || {
+                let xyz = shell_corners(bulk, eid)?
+                    .iter()
+                    .map(|&g| grid(location, g))
+                    .collect::<Option<SmallVec<[Vec3; 4]>>>()?;
+                Some(face_load(&xyz, &[self.p; 4], None))
+            };
+            (eid, load())
+        })
+    }
+}
+
+impl SetItem for PLOAD2 {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for PLOAD2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PLOAD2 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PLOAD2 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PLOAD2 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Real])?.into_iter();
+        let sid = iter.next().id()?;
+        let p = iter.next().float()?;
+        let eids = id_list(iter, "PLOAD2")?;
+        Ok(PLOAD2 { sid, p, eids })
+    }
+}
+
+/// A pressure on the face of shell or solid elements, varying between the
+/// corners of the face
+#[derive(Debug, Clone)]
+pub struct PLOAD4 {
+    sid: u32,
+    /// A range of shell elements given with THRU, or a single element
+    eids: RangeInclusive<u32>,
+    /// The pressure at each corner, where blanks after the first are the
+    /// same as the first
+    p: [f64; 4],
+    g1: u32,
+    /// The corner of a solid opposite G1, or the one off the face on a CTETRA
+    g34: u32,
+    /// The direction of the load when not normal to the face
+    direction: Option<(u32, Vec3)>,
+}
+
+impl PLOAD4 {
+    /// The load on each element, or `None` where it can't be found. IDs in a
+    /// THRU range without an element are gaps in the range and left out
+    fn loads<'a>(
+        &'a self,
+        bulk: &'a BulkData,
+        location: &'a GlobalLocation,
+    ) -> impl Iterator<Item = (u32, Option<Resultant>)> + 'a {
+        let direction = match self.direction {
+            Some((cid, n)) => in_basic(location, cid, n).and_then(unit).map(Some),
+            None => Some(None),
+        };
+        let thru = self.eids.start() != self.eids.end();
+        self.eids.clone().filter_map(move |eid| {
+            let shell = shell_corners(bulk, eid);
+            if thru && shell.is_none() && !has_solid(bulk, eid) {
+                return None;
+            }
+            let load = || {
+                let corners = match shell {
+                    Some(corners) => corners.iter().copied().collect(),
+                    None => self.solid_face(bulk, location, eid)?,
+                };
+                let xyz = corners
+                    .iter()
+                    .map(|&g| grid(location, g))
+                    .collect::<Option<SmallVec<[Vec3; 4]>>>()?;
+                Some(face_load(&xyz, &self.p, direction?))
+            };
+            Some((eid, load()))
+        })
+    }
+
+    /// The corners of the loaded face of a solid, ordered so that the normal
+    /// points into the element, which is the direction of positive pressure
+    fn solid_face(&self, bulk: &BulkData, location: &GlobalLocation, eid: u32) -> Option<Face> {
+        fn find<'a, T: Solid>(element: &'a T, load: &PLOAD4) -> Option<(Face, &'a [u32])> {
+            let face = solid::face(element, load.g1, load.g34)?;
+            Some((face, &element.grids()[..T::TOPOLOGY.corners()]))
+        }
+        let (mut face, corners) = None
+            .or_else(|| find(bulk.chexa.get(eid)?, self))
+            .or_else(|| find(bulk.cpenta.get(eid)?, self))
+            .or_else(|| find(bulk.ctetra.get(eid)?, self))
+            .or_else(|| find(bulk.cpyram.get(eid)?, self))?;
+        let centroid = |grids: &[u32]| -> Option<Vec3> {
+            let mut sum = Vec3::default();
+            for &g in grids {
+                sum += grid(location, g)?;
+            }
+            Some(sum / grids.len() as f64)
+        };
+        let xyz = face
+            .iter()
+            .map(|&g| grid(location, g))
+            .collect::<Option<SmallVec<[Vec3; 4]>>>()?;
+        let normal = (xyz[1] - xyz[0]).cross(xyz[2] - xyz[0]);
+        if normal.dot(centroid(&face)? - centroid(corners)?) > 0.0 {
+            face[1..].reverse();
+        }
+        Some(face)
+    }
+}
+
+impl SetItem for PLOAD4 {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for PLOAD4 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PLOAD4 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PLOAD4 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PLOAD4 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Real, Real, Real, Real, IntOrChar, Int, Int, Real, Real, Real, Char, Char,
+            ])?
+            .into_iter();
+        let sid = iter.next().id()?;
+        let eid = iter.next().id()?;
+        let p1 = iter.next().float()?;
+        let mut p = [p1; 4];
+        for p in p[1..].iter_mut() {
+            *p = iter.next().float_or(p1)?;
+        }
+        let (eids, g1, g34) = match iter.next().unwrap_or_default() {
+            Field::Text(text) if text.trim_end() == b"THRU" => (eid..=iter.next().id()?, 0, 0),
+            g1 => (eid..=eid, g1.id_or(0)?, iter.next().id_or(0)?),
+        };
+        let cid = iter.next().id_or(0)?;
+        let n = Vec3::new(
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+        );
+        let direction = (n.length() > 0.0).then_some((cid, n));
+        Ok(PLOAD4 {
+            sid,
+            eids,
+            p,
+            g1,
+            g34,
+            direction,
+        })
+    }
+}
+
+/// A uniform acceleration of the whole model, such as gravity
+#[derive(Debug, Clone)]
+pub struct GRAV {
+    sid: u32,
+    cid: u32,
+    a: f64,
+    n: Vec3,
+}
+
+impl GRAV {
+    fn acceleration(&self, location: &GlobalLocation) -> Option<Vec3> {
+        Some(self.a * in_basic(location, self.cid, self.n)?)
+    }
+}
+
+impl SetItem for GRAV {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for GRAV {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"GRAV   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"GRAV   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"GRAV   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Real, Real, Real, Real, Int])?
+            .into_iter();
+        let sid = iter.next().id()?;
+        let cid = iter.next().id_or(0)?;
+        let a = iter.next().float()?;
+        let n = Vec3::new(
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+        );
+        Ok(GRAV { sid, cid, a, n })
+    }
+}
+
+/// A rotation of the whole model about an axis, giving centrifugal loads
+#[derive(Debug, Clone)]
+pub struct RFORCE {
+    sid: u32,
+    /// A grid on the axis, or 0 for the origin
+    g: u32,
+    cid: u32,
+    /// The angular velocity in revolutions per unit time
+    a: f64,
+    r: Vec3,
+    /// The angular acceleration in revolutions per unit time squared
+    racc: f64,
+}
+
+impl RFORCE {
+    /// The point the axis goes through, the unit vector along it and the
+    /// angular velocity and acceleration in radians
+    fn axis(&self, location: &GlobalLocation) -> Option<(Vec3, Vec3, f64, f64)> {
+        let origin = match self.g {
+            0 => Vec3::default(),
+            g => grid(location, g)?,
+        };
+        let axis = unit(in_basic(location, self.cid, self.r)?)?;
+        let turn = 2. * std::f64::consts::PI;
+        Some((origin, axis, turn * self.a, turn * self.racc))
+    }
+}
+
+impl SetItem for RFORCE {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for RFORCE {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"RFORCE ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"RFORCE ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"RFORCE ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Int, Real, Real, Real, Real, Int, Real, Int, Int])?
+            .into_iter();
+        let sid = iter.next().id()?;
+        let g = iter.next().id_or(0)?;
+        let cid = iter.next().id_or(0)?;
+        let a = iter.next().float_or(0.0)?;
+        let r = Vec3::new(
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+        );
+        // METHOD only changes how Nastran lumps the mass
+        iter.next();
+        let racc = iter.next().float_or(0.0)?;
+        Ok(RFORCE {
+            sid,
+            g,
+            cid,
+            a,
+            r,
+            racc,
+        })
+    }
+}
+
+/// A combination of load sets, each scaled and then scaled again by an
+/// overall factor
+#[derive(Debug, Clone)]
+pub struct LOAD {
+    sid: u32,
+    s: f64,
+    loads: Vec<(f64, u32)>,
+}
+
+impl StorageItem for LOAD {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for LOAD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"LOAD   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"LOAD   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"LOAD   ", *b"       ")),
+        }
+        use FieldKind::*;
+        // The pairs of scale factors and sets run on across continuations
+        let schema: Vec<_> = [Int, Real]
+            .iter()
+            .chain([Real, Int].iter().cycle())
+            .copied()
+            .take(card.fields().len().max(2))
+            .collect();
+        let mut iter = card.decode(&schema)?.into_iter();
+        let sid = iter.next().id()?;
+        let s = iter.next().float()?;
+        let mut loads = Vec::new();
+        while let Some(si) = iter.next() {
+            let li = iter.next().unwrap_or_default();
+            if si == Field::Blank && li == Field::Blank {
+                continue;
+            }
+            loads.push((si.float()?, li.id()?));
+        }
+        Ok(LOAD { sid, s, loads })
+    }
+}
+
+/// The resultant of a load set, or of the sets a LOAD card combines, with its
+/// moment about the origin, along with the loads that were left out
+pub(super) fn resultant(
+    bulk: &BulkData,
+    sid: u32,
+    location: &GlobalLocation,
+) -> (Resultant, Vec<SkippedLoad>) {
+    let mut skipped = Vec::new();
+    let resultant = match bulk.load.get(sid) {
+        Some(load) => load
+            .loads
+            .iter()
+            .map(|&(s, l)| load.s * s * set_resultant(bulk, l, location, &mut skipped))
+            .sum(),
+        None => set_resultant(bulk, sid, location, &mut skipped),
+    };
+    (resultant, skipped)
+}
+
+/// Loads are skipped when their grids, elements or coordinate systems are
+/// missing. Enforced displacements from SPCD cards aren't loads and are left
+/// out
+fn set_resultant(
+    bulk: &BulkData,
+    sid: u32,
+    location: &GlobalLocation,
+    skipped: &mut Vec<SkippedLoad>,
+) -> Resultant {
+    let mut resultant = Resultant::default();
+    let mut add = |card: &[u8; 7], id: u32, load: Option<Resultant>| match load {
+        Some(load) => resultant = resultant + load,
+        None => skipped.push(SkippedLoad {
+            card: *card,
+            sid,
+            id,
+        }),
+    };
+    for l in bulk.force.get(sid) {
+        add(b"FORCE  ", l.g, l.resultant(location));
+    }
+    for l in bulk.force1.get(sid) {
+        add(b"FORCE1 ", l.g, l.resultant(location));
+    }
+    for l in bulk.force2.get(sid) {
+        add(b"FORCE2 ", l.g, l.resultant(location));
+    }
+    for l in bulk.moment.get(sid) {
+        add(b"MOMENT ", l.g, l.resultant(location));
+    }
+    for l in bulk.moment1.get(sid) {
+        add(b"MOMENT1", l.g, l.resultant(location));
+    }
+    for l in bulk.moment2.get(sid) {
+        add(b"MOMENT2", l.g, l.resultant(location));
+    }
+    for l in bulk.pload.get(sid) {
+        add(b"PLOAD  ", l.grids[0], l.resultant(location));
+    }
+    for l in bulk.pload2.get(sid) {
+        for (eid, load) in l.loads(bulk, location) {
+            add(b"PLOAD2 ", eid, load);
+        }
+    }
+    for l in bulk.pload4.get(sid) {
+        for (eid, load) in l.loads(bulk, location) {
+            add(b"PLOAD4 ", eid, load);
+        }
+    }
+
+    // Gravity is the mass times the acceleration, so it acts at the center of
+    // gravity
+    let mut mm = None;
+    for g in bulk.grav.get(sid) {
+        let load = g.acceleration(location).map(|a| {
+            let mm = mm.get_or_insert_with(|| bulk.mass_moment(location));
+            Resultant {
+                force: mm.mass * a,
+                moment: mm.moment.cross(a),
+            }
+        });
+        add(b"GRAV   ", g.cid, load);
+    }
+
+    // Centrifugal loads depend on the distance of each mass from the axis, so
+    // the mass of each element is lumped at its center of gravity
+    for rforce in bulk.rforce.get(sid) {
+        let load = rforce.axis(location).map(|(origin, axis, omega, alpha)| {
+            bulk.mass_sum(location, |mm| {
+                if mm.mass == 0.0 {
+                    return Resultant::default();
+                }
+                let cg = mm.moment / mm.mass;
+                let r = cg - origin;
+                let radial = r - r.dot(axis) * axis;
+                let force = mm.mass * (omega * omega * radial - alpha * axis.cross(r));
+                Resultant::at(force, cg)
+            })
+        });
+        add(b"RFORCE ", rforce.g, load);
+    }
+    resultant
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::deck::Deck;
+
+    /// Real fields are read with single precision
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-6 * (1. + b.length())
+    }
+
+    #[test]
+    fn test_concentrated_loads() {
+        // System 1 is turned a quarter turn about z
+        let input = b"GRID,1,,1.,0.,0.
+GRID,2,,0.,1.,0.
+GRID,3,,0.,0.,0.
+CORD2R,1,,0.,0.,0.,0.,0.,1.
+,0.,1.,0.
+FORCE,1,1,,2.,0.,0.,1.
+FORCE,1,2,1,1.,1.,0.,0.
+MOMENT,1,3,,3.,1.,0.,0.
+FORCE,1,9,,1.,1.,0.,0.
+MOMENT,1,1,7,1.,1.,0.,0.
+FORCE1,2,1,4.,3,2
+MOMENT2,2,3,1.,3,1,3,2
+LOAD,3,2.,1.,1,-1.,2
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        let bulk = deck.main();
+
+        // 2 in z at (1, 0, 0) and 1 in basic y at (0, 1, 0)
+        let (r, skipped) = bulk.load_resultant(1, Vec3::default(), &locations);
+        assert!(close(r.force, Vec3::new(0., 1., 2.)));
+        assert!(close(r.moment, Vec3::new(3., -2., 0.)));
+        // Grid 9 and system 7 are missing
+        let skipped: Vec<_> = skipped.iter().map(|s| (&s.card, s.sid, s.id)).collect();
+        assert_eq!(skipped, vec![(b"FORCE  ", 1, 9), (b"MOMENT ", 1, 1)]);
+
+        let (r, _) = bulk.load_resultant(1, Vec3::new(1., 0., 0.), &locations);
+        assert!(close(r.moment, Vec3::new(3., 0., -1.)));
+
+        let (r, _) = bulk.load_resultant(2, Vec3::default(), &locations);
+        assert!(close(r.force, Vec3::new(0., 4., 0.)));
+        assert!(close(r.moment, Vec3::new(0., 0., 5.)));
+
+        let (r, skipped) = bulk.load_resultant(3, Vec3::default(), &locations);
+        assert_eq!(skipped.len(), 2);
+        assert!(close(r.force, Vec3::new(0., -6., 4.)));
+        assert!(close(r.moment, Vec3::new(6., -4., -10.)));
+    }
+
+    #[test]
+    fn test_pressure_loads() {
+        let input = b"GRID,1,,0.,0.,0.
+GRID,2,,2.,0.,0.
+GRID,3,,2.,1.,0.
+GRID,4,,0.,1.,0.
+GRID,5,,0.,0.,1.
+GRID,6,,2.,0.,1.
+GRID,7,,2.,1.,1.
+GRID,8,,0.,1.,1.
+CQUAD4,1,1,1,2,3,4
+CTRIA3,2,1,1,2,3
+CHEXA,3,1,1,2,3,4,5,6,
+,7,8
+PLOAD2,1,3.,1
+PLOAD4,2,1,1.,,3.,
+PLOAD4,3,3,5.,,,,8,6
+PLOAD4,4,3,5.,,,,1,6
+PLOAD4,5,1,2.,,,,THRU,2
+,,1.,0.,0.
+PLOAD,6,1.,1,2,3
+PLOAD2,7,1.,1,9
+PLOAD4,8,1,1.,,,,THRU,9
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        let bulk = deck.main();
+        let r = |sid| bulk.load_resultant(sid, Vec3::default(), &locations).0;
+
+        let r1 = r(1);
+        assert!(close(r1.force, Vec3::new(0., 0., 6.)));
+        assert!(close(r1.moment, Vec3::new(3., -6., 0.)));
+
+        // Pressure varies from 1 to 3 along the diagonal of each triangle, so
+        // the mean is (1 + 1 + 3) / 3 on one and (1 + 3 + 1) / 3 on the other
+        assert!(close(r(2).force, Vec3::new(0., 0., 10. / 3.)));
+
+        // The top face of the hexa is pushed down and the bottom face up
+        assert!(close(r(3).force, Vec3::new(0., 0., -10.)));
+        assert!(close(r(4).force, Vec3::new(0., 10., 0.)));
+
+        // Along basic x over the quad and the triangle
+        assert!(close(r(5).force, Vec3::new(6., 0., 0.)));
+
+        assert!(close(r(6).force, Vec3::new(0., 0., 1.)));
+        assert!(close(r(6).moment, Vec3::new(1. / 3., -4. / 3., 0.)));
+
+        // Element 9 is missing, which is only a gap in a THRU range
+        let (r7, skipped) = bulk.load_resultant(7, Vec3::default(), &locations);
+        assert!(close(r7.force, Vec3::new(0., 0., 2.)));
+        assert_eq!(skipped.iter().map(|s| s.id).collect::<Vec<_>>(), vec![9]);
+        let (r8, skipped) = bulk.load_resultant(8, Vec3::default(), &locations);
+        assert!(close(r8.force, Vec3::new(0., 0., 3.)));
+        assert_eq!(skipped.iter().map(|s| s.id).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn test_body_loads() {
+        let input = b"GRID,1,,1.,0.,0.
+GRID,2,,0.,0.,2.
+CORD2R,1,,0.,0.,0.,0.,0.,1.
+,1.,0.,0.
+CONM2,1,1,,2.
+CONM2,2,2,,1.
+GRAV,1,,9.8,0.,0.,-1.
+RFORCE,2,2,,1.,0.,0.,1.
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        let bulk = deck.main();
+
+        let (r, _) = bulk.load_resultant(1, Vec3::default(), &locations);
+        assert!(close(r.force, Vec3::new(0., 0., -3. * 9.8)));
+        assert!(close(r.moment, Vec3::new(0., 2. * 9.8, 0.)));
+
+        // Only the mass at grid 1 is off the axis, which goes through grid 2
+        let omega = 2. * std::f64::consts::PI;
+        let (r, _) = bulk.load_resultant(2, Vec3::default(), &locations);
+        assert!(close(r.force, Vec3::new(2. * omega * omega, 0., 0.)));
+        assert!(close(r.moment, Vec3::default()));
+    }
+}
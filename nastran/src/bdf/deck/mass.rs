@@ -0,0 +1,218 @@
+use std::convert::TryFrom;
+
+use super::{DeckRef, GlobalLocation, MassMoment, StorageItem};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+use crate::util::Vec3;
+
+/// A concentrated mass given by its 6x6 mass matrix at a grid
+#[derive(Debug, Clone)]
+pub struct CONM1 {
+    eid: u32,
+    g: u32,
+    /// The coordinate system the matrix is in, 0 for basic
+    cid: u32,
+    /// The lower triangle of the mass matrix by rows, M11, M21, M22, M31 and
+    /// so on
+    m: [f64; 21],
+}
+
+impl CONM1 {
+    fn m(&self, i: usize, j: usize) -> f64 {
+        self.m[i * (i - 1) / 2 + j - 1]
+    }
+}
+
+impl DeckRef<'_, CONM1> {
+    /// The mass is the mean of the translational terms and the offset of its
+    /// center of gravity from the grid comes from the coupling terms between
+    /// translation and rotation
+    pub(super) fn mass_moment(&self, location: &GlobalLocation) -> Option<MassMoment> {
+        let mass = (self.m(1, 1) + self.m(2, 2) + self.m(3, 3)) / 3.;
+        let xyz = location.get_grid(self.g)?.to_vec();
+        if mass == 0.0 {
+            return Some(MassMoment::default());
+        }
+        let offset = Vec3::new(
+            self.m(6, 2) - self.m(5, 3),
+            self.m(4, 3) - self.m(6, 1),
+            self.m(5, 1) - self.m(4, 2),
+        ) / (2. * mass);
+        let offset = match self.cid {
+            0 => offset,
+            cid => location.get_csys(cid)?.rotate(offset),
+        };
+        Some(MassMoment {
+            mass,
+            moment: mass * (xyz + offset),
+        })
+    }
+}
+
+impl StorageItem for CONM1 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CONM1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CONM1  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CONM1  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CONM1  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut schema = [Real; 24];
+        schema[..3].copy_from_slice(&[Int, Int, Int]);
+        let mut iter = card.decode(&schema)?.into_iter();
+        let eid = iter.next().id()?;
+        let g = iter.next().id()?;
+        let cid = iter.next().id_or(0)?;
+        let mut m = [0.0; 21];
+        for m in m.iter_mut() {
+            *m = iter.next().float_or(0.0)?;
+        }
+        Ok(CONM1 { eid, g, cid, m })
+    }
+}
+
+/// A concentrated mass at a grid, which can be offset from it
+#[derive(Debug, Clone)]
+pub struct CONM2 {
+    eid: u32,
+    g: u32,
+    /// The coordinate system of the offset and inertia, 0 for basic, or -1
+    /// when the offset is the center of gravity in the basic system
+    cid: i32,
+    m: f64,
+    x: Vec3,
+    i: [f64; 6],
+}
+
+impl CONM2 {
+    /// The inertia about the center of gravity in the CID system, I11, I21,
+    /// I22, I31, I32 and I33
+    pub fn inertia(&self) -> [f64; 6] {
+        self.i
+    }
+}
+
+impl DeckRef<'_, CONM2> {
+    pub(super) fn mass_moment(&self, location: &GlobalLocation) -> Option<MassMoment> {
+        let xyz = match self.cid {
+            -1 => self.x,
+            0 => location.get_grid(self.g)?.to_vec() + self.x,
+            cid => {
+                let csys = location.get_csys(cid as u32)?;
+                location.get_grid(self.g)?.to_vec() + csys.rotate(self.x)
+            }
+        };
+        Some(MassMoment {
+            mass: self.m,
+            moment: self.m * xyz,
+        })
+    }
+}
+
+impl StorageItem for CONM2 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CONM2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CONM2  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CONM2  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CONM2  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Int, Real, Real, Real, Real, Any, Real, Real, Real, Real, Real, Real,
+            ])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let g = iter.next().id()?;
+        let cid = iter.next().int_or(0)?;
+        if cid < -1 {
+            return Err(Error::UnexpectedField("CID", Field::Int(cid)));
+        }
+        let m = iter.next().float_or(0.0)?;
+        let x = Vec3::new(
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+            iter.next().float_or(0.0)?,
+        );
+        iter.next();
+        let mut i = [0.0; 6];
+        for i in i.iter_mut() {
+            *i = iter.next().float_or(0.0)?;
+        }
+        Ok(CONM2 {
+            eid,
+            g,
+            cid,
+            m,
+            x,
+            i,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::deck::Deck;
+
+    #[test]
+    fn test_concentrated_mass() {
+        // System 1 is turned a quarter turn about z, so its x is basic y
+        let input = b"GRID,1,,1.,0.,0.
+GRID,2,,0.,0.,0.
+CORD2R,1,,0.,0.,0.,0.,0.,1.
+,0.,1.,0.
+CONM2,1,1,,2.,0.,0.,1.
+CONM2,2,1,1,1.,1.,0.,0.
+CONM2,3,1,-1,1.,5.,5.,5.
+,1.,-.1,2.,,-.2,3.
+CONM1,4,2,,4.,,4.,,
+,4.,,-2.,,,2.,,
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        let bulk = deck.main();
+        let cg = |eid| {
+            let mm = bulk.with(bulk.get_conm2(eid).unwrap());
+            let mm = mm.mass_moment(&locations).unwrap();
+            mm.moment / mm.mass
+        };
+        assert!((cg(1) - Vec3::new(1., 0., 1.)).length() < 1e-12);
+        assert!((cg(2) - Vec3::new(1., 1., 0.)).length() < 1e-12);
+        assert!((cg(3) - Vec3::new(5., 5., 5.)).length() < 1e-12);
+        let inertia = bulk.get_conm2(3).unwrap().inertia();
+        let expected = [1., -0.1, 2., 0., -0.2, 3.];
+        assert!(inertia
+            .iter()
+            .zip(expected)
+            .all(|(i, e)| (i - e).abs() < 1e-6));
+        assert_eq!(bulk.get_conm2(1).unwrap().inertia(), [0.; 6]);
+
+        // M42 of -m * z and M51 of m * z put the mass 0.5 above the grid
+        let conm1 = bulk.with(bulk.get_conm1(4).unwrap());
+        let mm = conm1.mass_moment(&locations).unwrap();
+        assert_eq!(mm.mass, 4.);
+        assert!((mm.moment / mm.mass - Vec3::new(0., 0., 0.5)).length() < 1e-12);
+
+        let (mass, _) = deck.mass_cg(&locations);
+        assert_eq!(mass, 8.);
+    }
+}
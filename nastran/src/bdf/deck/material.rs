@@ -0,0 +1,670 @@
+use std::convert::TryFrom;
+
+use super::{BulkData, StorageItem, MAT1};
+use crate::bdf::parser::{BulkCard, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+/// A symmetric matrix from its upper triangle, given row by row
+fn symmetric<const N: usize>(upper: &[f64]) -> [[f64; N]; N] {
+    let mut matrix = [[0.0; N]; N];
+    let pairs = (0..N).flat_map(|i| (i..N).map(move |j| (i, j)));
+    for ((i, j), &g) in pairs.zip(upper) {
+        matrix[i][j] = g;
+        matrix[j][i] = g;
+    }
+    matrix
+}
+
+impl MAT1 {
+    /// The plane stress stiffness relating the membrane stresses to the
+    /// strains, in the order xx, yy and xy
+    pub fn plane_stress(&self) -> [[f64; 3]; 3] {
+        let d = self.e / (1. - self.nu * self.nu);
+        [[d, self.nu * d, 0.], [self.nu * d, d, 0.], [0., 0., self.g]]
+    }
+
+    /// The stiffness of a solid, in the order xx, yy, zz, xy, yz and zx used
+    /// by MAT9
+    pub fn solid(&self) -> [[f64; 6]; 6] {
+        let d = self.e / ((1. + self.nu) * (1. - 2. * self.nu));
+        let c11 = (1. - self.nu) * d;
+        let c12 = self.nu * d;
+        let mut matrix = [[0.0; 6]; 6];
+        for i in 0..3 {
+            matrix[i][..3].fill(c12);
+            matrix[i][i] = c11;
+            matrix[i + 3][i + 3] = self.g;
+        }
+        matrix
+    }
+}
+
+/// An anisotropic material for shells, given by its plane stress stiffness
+#[derive(Debug, Clone)]
+pub struct MAT2 {
+    mid: u32,
+    g: [[f64; 3]; 3],
+    rho: f64,
+    #[allow(dead_code)]
+    a: [f64; 3],
+    #[allow(dead_code)]
+    tref: f64,
+    #[allow(dead_code)]
+    ge: f64,
+}
+
+impl MAT2 {
+    /// The stiffness relating the membrane stresses to the strains, in the
+    /// order xx, yy and xy
+    pub fn plane_stress(&self) -> [[f64; 3]; 3] {
+        self.g
+    }
+}
+
+impl StorageItem for MAT2 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.mid
+    }
+}
+
+impl TryFrom<&BulkCard> for MAT2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MAT2   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"MAT2   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"MAT2   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real,
+            ])?
+            .into_iter();
+        let mid = iter.next().id()?;
+        let mut upper = [0.0; 6];
+        for g in upper.iter_mut() {
+            *g = iter.next().float_or(0.0)?;
+        }
+        let rho = iter.next().float_or(0.0)?;
+        let mut a = [0.0; 3];
+        for a in a.iter_mut() {
+            *a = iter.next().float_or(0.0)?;
+        }
+        let tref = iter.next().float_or(0.0)?;
+        let ge = iter.next().float_or(0.0)?;
+        Ok(MAT2 {
+            mid,
+            g: symmetric(&upper),
+            rho,
+            a,
+            tref,
+            ge,
+        })
+    }
+}
+
+/// An orthotropic material for shells, such as a ply of a laminate
+#[derive(Debug, Clone)]
+pub struct MAT8 {
+    mid: u32,
+    e1: f64,
+    e2: f64,
+    nu12: f64,
+    g12: f64,
+    g1z: f64,
+    g2z: f64,
+    rho: f64,
+    #[allow(dead_code)]
+    a: [f64; 2],
+    #[allow(dead_code)]
+    tref: f64,
+    #[allow(dead_code)]
+    ge: f64,
+}
+
+impl MAT8 {
+    /// The stiffness relating the in plane stresses to the strains in the
+    /// material directions, in the order 11, 22 and 12
+    pub fn plane_stress(&self) -> [[f64; 3]; 3] {
+        let nu21 = self.nu12 * self.e2 / self.e1;
+        let d = 1. - self.nu12 * nu21;
+        [
+            [self.e1 / d, self.nu12 * self.e2 / d, 0.],
+            [self.nu12 * self.e2 / d, self.e2 / d, 0.],
+            [0., 0., self.g12],
+        ]
+    }
+
+    /// The stiffness for the transverse shear strains 1z and 2z
+    pub fn transverse_shear(&self) -> [[f64; 2]; 2] {
+        [[self.g1z, 0.], [0., self.g2z]]
+    }
+}
+
+impl StorageItem for MAT8 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.mid
+    }
+}
+
+impl TryFrom<&BulkCard> for MAT8 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MAT8   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"MAT8   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"MAT8   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real, Real,
+                Real, Real, Real,
+            ])?
+            .into_iter();
+        let mid = iter.next().id()?;
+        let e1 = iter.next().float()?;
+        let e2 = iter.next().float()?;
+        let nu12 = iter.next().float()?;
+        let g12 = iter.next().float_or(0.0)?;
+        // Transverse shear is flexible when the moduli are left blank
+        let g1z = iter.next().float_or(0.0)?;
+        let g2z = iter.next().float_or(0.0)?;
+        let rho = iter.next().float_or(0.0)?;
+        let a = [iter.next().float_or(0.0)?, iter.next().float_or(0.0)?];
+        let tref = iter.next().float_or(0.0)?;
+        // Skips the allowable stresses
+        let mut iter = iter.skip(5);
+        let ge = iter.next().float_or(0.0)?;
+        Ok(MAT8 {
+            mid,
+            e1,
+            e2,
+            nu12,
+            g12,
+            g1z,
+            g2z,
+            rho,
+            a,
+            tref,
+            ge,
+        })
+    }
+}
+
+/// An anisotropic material for solids, given by its stiffness
+#[derive(Debug, Clone)]
+pub struct MAT9 {
+    mid: u32,
+    g: [[f64; 6]; 6],
+    rho: f64,
+    #[allow(dead_code)]
+    a: [f64; 6],
+    #[allow(dead_code)]
+    tref: f64,
+    #[allow(dead_code)]
+    ge: f64,
+}
+
+impl MAT9 {
+    /// The stiffness in the order xx, yy, zz, xy, yz and zx
+    pub fn solid(&self) -> [[f64; 6]; 6] {
+        self.g
+    }
+}
+
+impl StorageItem for MAT9 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.mid
+    }
+}
+
+impl TryFrom<&BulkCard> for MAT9 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MAT9   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"MAT9   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"MAT9   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut schema = vec![Int];
+        schema.extend([Real; 30].iter());
+        let mut iter = card.decode(&schema)?.into_iter();
+        let mid = iter.next().id()?;
+        let mut upper = [0.0; 21];
+        for g in upper.iter_mut() {
+            *g = iter.next().float_or(0.0)?;
+        }
+        let rho = iter.next().float_or(0.0)?;
+        let mut a = [0.0; 6];
+        for a in a.iter_mut() {
+            *a = iter.next().float_or(0.0)?;
+        }
+        let tref = iter.next().float_or(0.0)?;
+        let ge = iter.next().float_or(0.0)?;
+        Ok(MAT9 {
+            mid,
+            g: symmetric(&upper),
+            rho,
+            a,
+            tref,
+            ge,
+        })
+    }
+}
+
+/// A fluid for acoustic elements
+#[derive(Debug, Clone)]
+pub struct MAT10 {
+    mid: u32,
+    bulk: f64,
+    rho: f64,
+    c: f64,
+    #[allow(dead_code)]
+    ge: f64,
+}
+
+impl MAT10 {
+    pub fn bulk_modulus(&self) -> f64 {
+        self.bulk
+    }
+
+    /// The speed of sound
+    pub fn speed_of_sound(&self) -> f64 {
+        self.c
+    }
+}
+
+impl StorageItem for MAT10 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.mid
+    }
+}
+
+impl TryFrom<&BulkCard> for MAT10 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MAT10  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"MAT10  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"MAT10  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Real, Real, Real, Real])?.into_iter();
+        let mid = iter.next().id()?;
+        let field_bulk = iter.next().unwrap_or_default();
+        let field_rho = iter.next().unwrap_or_default();
+        let field_c = iter.next().unwrap_or_default();
+        // Any two of them give the third, from bulk = rho * c^2
+        let (bulk, rho, c) = match (
+            field_bulk.maybe_float()?,
+            field_rho.maybe_float()?,
+            field_c.maybe_float()?,
+        ) {
+            (Some(bulk), Some(rho), Some(c)) => (bulk, rho, c),
+            (Some(bulk), Some(rho), None) => (bulk, rho, (bulk / rho).sqrt()),
+            (Some(bulk), None, Some(c)) => (bulk, bulk / (c * c), c),
+            (None, Some(rho), Some(c)) => (rho * c * c, rho, c),
+            _ => return Err(Error::InvalidFluidMaterial(field_bulk, field_rho, field_c)),
+        };
+        let ge = iter.next().float_or(0.0)?;
+        Ok(MAT10 {
+            mid,
+            bulk,
+            rho,
+            c,
+            ge,
+        })
+    }
+}
+
+/// A property at a temperature from the table `tid`, where `z` is its value
+/// on the material card. A table id of 0 leaves the property as it is
+fn at(bulk: &BulkData, tid: u32, z: f64, temperature: f64) -> Option<f64> {
+    match tid {
+        0 => Some(z),
+        tid => Some(bulk.get_material_table(tid)?.value(temperature, z)),
+    }
+}
+
+/// The tables for the properties of the MAT1 with the same id that depend
+/// on temperature
+#[derive(Debug, Clone)]
+pub struct MATT1 {
+    mid: u32,
+    e: u32,
+    g: u32,
+    nu: u32,
+    rho: u32,
+    a: u32,
+    ge: u32,
+}
+
+impl MATT1 {
+    /// The MAT1 at a temperature. E, G and NU are found from the tables for
+    /// those given on the MAT1 and then the others from them, as at any
+    /// temperature
+    pub(super) fn apply(&self, bulk: &BulkData, mat: &MAT1, temperature: f64) -> Option<MAT1> {
+        let at = |tid, z| at(bulk, tid, z, temperature);
+        let mut given = mat.given;
+        for (value, &tid) in given.iter_mut().zip(&[self.e, self.g, self.nu]) {
+            if let Some(z) = value {
+                *z = at(tid, *z)?;
+            }
+        }
+        let (e, g, nu) = MAT1::elastic(given)?;
+        Some(MAT1 {
+            e,
+            g,
+            nu,
+            given,
+            rho: at(self.rho, mat.rho)?,
+            a: at(self.a, mat.a)?,
+            ge: at(self.ge, mat.ge)?,
+            ..mat.clone()
+        })
+    }
+}
+
+impl StorageItem for MATT1 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.mid
+    }
+}
+
+impl TryFrom<&BulkCard> for MATT1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MATT1  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"MATT1  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"MATT1  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int; 11])?.into_iter();
+        let mid = iter.next().id()?;
+        let e = iter.next().id_or(0)?;
+        let g = iter.next().id_or(0)?;
+        let nu = iter.next().id_or(0)?;
+        let rho = iter.next().id_or(0)?;
+        let a = iter.next().id_or(0)?;
+        iter.next();
+        let ge = iter.next().id_or(0)?;
+        Ok(MATT1 {
+            mid,
+            e,
+            g,
+            nu,
+            rho,
+            a,
+            ge,
+        })
+    }
+}
+
+/// The tables for the properties of the MAT8 with the same id that depend
+/// on temperature
+#[derive(Debug, Clone)]
+pub struct MATT8 {
+    mid: u32,
+    e1: u32,
+    e2: u32,
+    nu12: u32,
+    g12: u32,
+    g1z: u32,
+    g2z: u32,
+    rho: u32,
+    a: [u32; 2],
+    ge: u32,
+}
+
+impl MATT8 {
+    /// The MAT8 at a temperature
+    pub(super) fn apply(&self, bulk: &BulkData, mat: &MAT8, temperature: f64) -> Option<MAT8> {
+        let at = |tid, z| at(bulk, tid, z, temperature);
+        Some(MAT8 {
+            e1: at(self.e1, mat.e1)?,
+            e2: at(self.e2, mat.e2)?,
+            nu12: at(self.nu12, mat.nu12)?,
+            g12: at(self.g12, mat.g12)?,
+            g1z: at(self.g1z, mat.g1z)?,
+            g2z: at(self.g2z, mat.g2z)?,
+            rho: at(self.rho, mat.rho)?,
+            a: [at(self.a[0], mat.a[0])?, at(self.a[1], mat.a[1])?],
+            ge: at(self.ge, mat.ge)?,
+            ..mat.clone()
+        })
+    }
+}
+
+impl StorageItem for MATT8 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.mid
+    }
+}
+
+impl TryFrom<&BulkCard> for MATT8 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MATT8  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"MATT8  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"MATT8  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int; 18])?.into_iter();
+        let mid = iter.next().id()?;
+        let e1 = iter.next().id_or(0)?;
+        let e2 = iter.next().id_or(0)?;
+        let nu12 = iter.next().id_or(0)?;
+        let g12 = iter.next().id_or(0)?;
+        let g1z = iter.next().id_or(0)?;
+        let g2z = iter.next().id_or(0)?;
+        let rho = iter.next().id_or(0)?;
+        let a = [iter.next().id_or(0)?, iter.next().id_or(0)?];
+        // Skips the allowable stresses
+        let mut iter = iter.skip(6);
+        let ge = iter.next().id_or(0)?;
+        Ok(MATT8 {
+            mid,
+            e1,
+            e2,
+            nu12,
+            g12,
+            g1z,
+            g2z,
+            rho,
+            a,
+            ge,
+        })
+    }
+}
+
+/// A material of any type. They all share the same ids, so a property can
+/// refer to any of them
+#[derive(Debug, Clone, Copy)]
+pub enum Material<'a> {
+    Mat1(&'a MAT1),
+    Mat2(&'a MAT2),
+    Mat8(&'a MAT8),
+    Mat9(&'a MAT9),
+    Mat10(&'a MAT10),
+}
+
+impl Material<'_> {
+    pub fn density(&self) -> f64 {
+        match self {
+            Material::Mat1(m) => m.density(),
+            Material::Mat2(m) => m.rho,
+            Material::Mat8(m) => m.rho,
+            Material::Mat9(m) => m.rho,
+            Material::Mat10(m) => m.rho,
+        }
+    }
+
+    /// The stiffness for membrane stresses of shells, when the material
+    /// can be used for them
+    pub fn plane_stress(&self) -> Option<[[f64; 3]; 3]> {
+        match self {
+            Material::Mat1(m) => Some(m.plane_stress()),
+            Material::Mat2(m) => Some(m.plane_stress()),
+            Material::Mat8(m) => Some(m.plane_stress()),
+            Material::Mat9(_) | Material::Mat10(_) => None,
+        }
+    }
+
+    /// The transverse shear stiffness of shells for the strains 1z and 2z,
+    /// when the material has one
+    pub fn transverse_shear(&self) -> Option<[[f64; 2]; 2]> {
+        match self {
+            Material::Mat1(m) => Some([[m.g, 0.], [0., m.g]]),
+            Material::Mat8(m) => Some(m.transverse_shear()),
+            Material::Mat2(_) | Material::Mat9(_) | Material::Mat10(_) => None,
+        }
+    }
+
+    /// The stiffness of solids, when the material can be used for them
+    pub fn solid(&self) -> Option<[[f64; 6]; 6]> {
+        match self {
+            Material::Mat1(m) => Some(m.solid()),
+            Material::Mat9(m) => Some(m.solid()),
+            Material::Mat2(_) | Material::Mat8(_) | Material::Mat10(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdf::deck::Deck;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6 * b.abs().max(1.)
+    }
+
+    #[test]
+    fn test_stiffness() {
+        let input = b"MAT1,1,100.,,0.25,1.
+MAT2,2,10.,2.,,20.,,5.,2.
+MAT8,3,100.,10.,0.3,5.,4.,3.,3.
+MAT9,4,1.,2.,3.,,,,4.
+,5.,,,,6.
+,,,,,,7.,4.
+MAT10,5,4.,,2.
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let material = |mid| bulk.get_material(mid).unwrap();
+
+        let q = material(1).plane_stress().unwrap();
+        assert!(close(q[0][0], 100. / 0.9375));
+        assert!(close(q[0][1], 25. / 0.9375));
+        assert!(close(q[2][2], 40.));
+        let c = material(1).solid().unwrap();
+        assert!(close(c[0][0], 120.));
+        assert!(close(c[1][2], 40.));
+        assert!(close(c[5][5], 40.));
+
+        let q = material(2).plane_stress().unwrap();
+        assert_eq!(q, [[10., 2., 0.], [2., 20., 0.], [0., 0., 5.]]);
+        assert!(material(2).solid().is_none());
+
+        // nu21 is 0.03, so 1 - nu12 * nu21 is 0.991
+        let q = material(3).plane_stress().unwrap();
+        assert!(close(q[0][0], 100. / 0.991));
+        assert!(close(q[0][1], 3. / 0.991));
+        assert!(close(q[1][1], 10. / 0.991));
+        assert!(close(q[2][2], 5.));
+        assert_eq!(
+            bulk.get_mat8(3).unwrap().transverse_shear(),
+            [[4., 0.], [0., 3.]]
+        );
+
+        let c = material(4).solid().unwrap();
+        assert_eq!(c[0][..3], [1., 2., 3.]);
+        assert_eq!(c[2][0], 3.);
+        assert_eq!(c[1][1], 4.);
+        assert_eq!(c[1][2], 5.);
+        assert_eq!(c[2][2], 6.);
+        assert_eq!(c[5][5], 7.);
+        assert!(material(4).plane_stress().is_none());
+
+        // The density and speed of sound give the bulk modulus
+        assert_eq!(bulk.get_mat10(5).unwrap().speed_of_sound(), 2.);
+        assert_eq!(material(5).density(), 1.);
+        assert_eq!(bulk.get_mat10(5).unwrap().bulk_modulus(), 4.);
+        assert!(Deck::from_buffer(b"MAT10,1,4.").is_err());
+    }
+
+    #[test]
+    fn test_temperature() {
+        let input = b"MAT1,1,100.,,0.25,2.
+MATT1,1,10,,,11
+MAT1,2,100.,40.
+MATT1,2,,12
+MAT8,3,100.,10.,0.3,5.
+MATT8,3,11,,,11
+TABLEM1,10
+,0.,100.,100.,50.,ENDT
+TABLEM2,11,0.
+,0.,1.,100.,2.,ENDT
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+
+        // G is found from E at the temperature
+        let mat = bulk.mat1_at(1, 50.).unwrap();
+        assert!(close(mat.e, 75.));
+        assert!(close(mat.g, 30.));
+        assert!(close(mat.density(), 3.));
+        assert!(close(bulk.mat1_at(1, 200.).unwrap().e, 0.));
+
+        // The table for G is missing
+        assert!(bulk.mat1_at(2, 50.).is_none());
+        assert!(bulk.mat1_at(4, 50.).is_none());
+
+        let mat = bulk.mat8_at(3, 50.).unwrap();
+        assert!(close(mat.e1, 150.));
+        assert!(close(mat.g12, 7.5));
+        assert!(close(mat.e2, 10.));
+        assert!(close(bulk.mat8_at(3, 0.).unwrap().e1, 100.));
+    }
+
+    #[test]
+    fn test_density() {
+        let input = b"GRID,1,,0.,0.,0.
+GRID,2,,1.,0.,0.
+GRID,3,,1.,1.,0.
+GRID,4,,0.,1.,0.
+GRID,5,,0.,0.,1.
+GRID,6,,1.,0.,1.
+GRID,7,,1.,1.,1.
+GRID,8,,0.,1.,1.
+CQUAD4,1,1,1,2,3,4
+PSHELL,1,8,0.5
+MAT8,8,100.,10.,0.3,,,,2.
+CHEXA,2,2,1,2,3,4,5,6,
+,7,8
+PSOLID,2,9
+MAT9,9,1.,,,,,,1.
+,,,,,1.
+,1.,,,1.,,1.,3.
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        assert!(close(deck.main().mass(&locations), 1. + 3.));
+    }
+}
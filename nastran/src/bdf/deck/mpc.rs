@@ -0,0 +1,200 @@
+use std::convert::TryFrom;
+
+use super::dof::{component, Constraint, Dof, Source};
+use super::{SetItem, StorageItem};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+/// A linear equation between DOFs, where the first DOF is dependent
+#[derive(Debug, Clone)]
+pub struct MPC {
+    sid: u32,
+    terms: Vec<(Dof, f64)>,
+}
+
+impl MPC {
+    pub fn terms(&self) -> &[(Dof, f64)] {
+        &self.terms
+    }
+}
+
+impl SetItem for MPC {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl Constraint for MPC {
+    fn source(&self) -> Source {
+        Source::Mpc(self.sid)
+    }
+
+    fn dofs(&self) -> (Vec<Dof>, Vec<Dof>) {
+        let mut dofs = self.terms.iter().map(|(d, _)| *d);
+        (dofs.next().into_iter().collect(), dofs.collect())
+    }
+}
+
+impl TryFrom<&BulkCard> for MPC {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MPC    ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"MPC    ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"MPC    ", *b"       ")),
+        }
+        use FieldKind::*;
+        // Every line has two terms after the set id or a blank field
+        let schema: Vec<_> = [Int, Int, Int, Real, Int, Int, Real, Any]
+            .iter()
+            .copied()
+            .cycle()
+            .take(card.fields().len())
+            .collect();
+        let fields = card.decode(&schema)?;
+        let sid = fields.first().copied().unwrap_or_default().id()?;
+        let mut terms = Vec::new();
+        for line in fields.chunks(8) {
+            for term in line[1..].chunks(3).take(2) {
+                let mut iter = term.iter().copied();
+                let g = iter.next().unwrap_or_default();
+                if g == Field::Blank {
+                    continue;
+                }
+                let dof = Dof::new(g.id()?, component(iter.next().unwrap_or_default())?);
+                terms.push((dof, iter.next().float()?));
+            }
+        }
+        if terms.is_empty() {
+            return Err(Error::UnexpectedField("G1", Field::Blank));
+        }
+        Ok(MPC { sid, terms })
+    }
+}
+
+/// Combines MPC sets into a new set
+#[derive(Debug, Clone)]
+pub struct MPCADD {
+    sid: u32,
+    pub(super) sets: Vec<u32>,
+}
+
+impl StorageItem for MPCADD {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for MPCADD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"MPCADD ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"MPCADD ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"MPCADD ", *b"       ")),
+        }
+        use FieldKind::*;
+        let fields = card.decode(&vec![Int; card.fields().len()])?;
+        let mut iter = fields.into_iter();
+        let sid = iter.next().id()?;
+        let sets = iter
+            .filter(|f| *f != Field::Blank)
+            .map(|f| f.id())
+            .collect::<Result<_>>()?;
+        Ok(MPCADD { sid, sets })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::case_control::Subcase;
+    use crate::bdf::deck::{Deck, DofConflict, SpcSource};
+
+    #[test]
+    fn test_mpc() {
+        let input = b"MPC,1,10,1,1.,11,1,-1.
+,,12,3,0.5
+MPC,1,13,2,1.
+MPC,2,11,1,1.,20,0,2.
+MPCADD,3,1,2
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let set: Vec<_> = bulk.get_mpc(1).collect();
+        assert_eq!(set.len(), 2);
+        assert_eq!(set[0].terms().len(), 3);
+        assert_eq!(set[0].terms()[2], (Dof::new(12, 3), 0.5));
+        assert_eq!(
+            bulk.get_mpc(2).next().unwrap().terms()[1].0,
+            Dof::new(20, 0)
+        );
+        assert_eq!(bulk.get_mpcadd(3).unwrap().sets, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_dof_conflicts() {
+        let input = b"GRID,1,,0.,0.,0.,,3
+RBE2,1,1,123456,2,3
+RBE2,2,3,123,4
+RBE2,3,4,123,3
+MPC,10,2,1,1.,5,1,1.
+MPC,20,6,1,1.,5,1,1.
+MPC,20,5,1,1.,6,1,1.
+MPCADD,30,20
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let subcase = |mpc| Subcase {
+            mpc,
+            ..Default::default()
+        };
+        let cycle = DofConflict::Cycle(vec![Source::Rbe2(2), Source::Rbe2(3)]);
+        assert_eq!(
+            bulk.dof_conflicts(&subcase(None)),
+            vec![
+                DofConflict::DependentTwice {
+                    dof: Dof::new(3, 1),
+                    first: Source::Rbe2(1),
+                    second: Source::Rbe2(3),
+                },
+                DofConflict::DependentTwice {
+                    dof: Dof::new(3, 2),
+                    first: Source::Rbe2(1),
+                    second: Source::Rbe2(3),
+                },
+                DofConflict::DependentTwice {
+                    dof: Dof::new(3, 3),
+                    first: Source::Rbe2(1),
+                    second: Source::Rbe2(3),
+                },
+                cycle.clone(),
+            ]
+        );
+
+        // The MPC makes a DOF dependent that the first RBE2 already has
+        let conflicts = bulk.dof_conflicts(&subcase(Some(10)));
+        assert!(conflicts.contains(&DofConflict::DependentTwice {
+            dof: Dof::new(2, 1),
+            first: Source::Rbe2(1),
+            second: Source::Mpc(10),
+        }));
+
+        let conflicts = bulk.dof_conflicts(&subcase(Some(30)));
+        assert!(conflicts.contains(&DofConflict::Cycle(vec![Source::Mpc(20), Source::Mpc(20)])));
+        assert!(conflicts.contains(&cycle));
+
+        let input = b"GRID,1,,0.,0.,0.,,3\nMPC,1,1,3,1.,2,3,1.";
+        let deck = Deck::from_buffer(input).unwrap();
+        assert_eq!(
+            deck.main().dof_conflicts(&subcase(Some(1))),
+            vec![DofConflict::DependentSpc {
+                dof: Dof::new(1, 3),
+                dependent: Source::Mpc(1),
+                spc: SpcSource::Grid,
+            }]
+        );
+    }
+}
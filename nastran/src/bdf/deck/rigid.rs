@@ -0,0 +1,439 @@
+use std::convert::TryFrom;
+
+use bstr::ByteSlice;
+
+use super::dof::{dofs, Constraint, Dof, Source};
+use super::StorageItem;
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+const ALL: [bool; 6] = [true; 6];
+const TRANSLATIONS: [bool; 6] = [true, true, true, false, false, false];
+
+/// Reads the thermal expansion coefficient that can end the lists of grids on
+/// rigid elements, either as a bare real or after an ALPHA keyword
+fn alpha(field: Field, iter: &mut impl Iterator<Item = Field>) -> Result<f64> {
+    match field {
+        Field::Text(text) if text.trim_end() == b"ALPHA" => iter.next().float_or(0.0),
+        field => field.float(),
+    }
+}
+
+/// A rigid body connecting dependent components of any number of grids to all
+/// six components of an independent grid
+#[derive(Debug, Clone)]
+pub struct RBE2 {
+    eid: u32,
+    gn: u32,
+    cm: [bool; 6],
+    gm: Vec<u32>,
+    #[allow(dead_code)]
+    alpha: f64,
+}
+
+impl StorageItem for RBE2 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl Constraint for RBE2 {
+    fn source(&self) -> Source {
+        Source::Rbe2(self.eid)
+    }
+
+    fn dofs(&self) -> (Vec<Dof>, Vec<Dof>) {
+        let m = self.gm.iter().flat_map(|&g| dofs(g, self.cm)).collect();
+        (m, dofs(self.gn, ALL).collect())
+    }
+}
+
+impl TryFrom<&BulkCard> for RBE2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"RBE2   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"RBE2   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"RBE2   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let gn = iter.next().id()?;
+        let cm = iter.next().dof()?;
+        let mut gm = Vec::new();
+        let mut alpha = 0.0;
+        while let Some(field) = iter.next() {
+            match field {
+                Field::Blank => {}
+                Field::IntOrId(g) => gm.push(g),
+                field => {
+                    alpha = self::alpha(field, &mut iter)?;
+                    break;
+                }
+            }
+        }
+        Ok(RBE2 {
+            eid,
+            gn,
+            cm,
+            gm,
+            alpha,
+        })
+    }
+}
+
+/// A group of grids sharing a weight and components on an RBE3
+#[derive(Debug, Clone)]
+pub struct Weight {
+    pub weight: f64,
+    pub components: [bool; 6],
+    pub grids: Vec<u32>,
+}
+
+/// An interpolation element, where the motion of a reference grid is the
+/// weighted average of the motion of other grids
+#[derive(Debug, Clone)]
+pub struct RBE3 {
+    eid: u32,
+    refgrid: u32,
+    refc: [bool; 6],
+    weights: Vec<Weight>,
+    /// Components made dependent instead of those of the reference grid
+    um: Vec<(u32, [bool; 6])>,
+    #[allow(dead_code)]
+    alpha: f64,
+}
+
+impl StorageItem for RBE3 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl Constraint for RBE3 {
+    fn source(&self) -> Source {
+        Source::Rbe3(self.eid)
+    }
+
+    fn dofs(&self) -> (Vec<Dof>, Vec<Dof>) {
+        let reference = dofs(self.refgrid, self.refc);
+        let weighted = self
+            .weights
+            .iter()
+            .flat_map(|w| w.grids.iter().flat_map(move |&g| dofs(g, w.components)));
+        if self.um.is_empty() {
+            return (reference.collect(), weighted.collect());
+        }
+        // The reference grid becomes independent in place of the UM DOFs
+        let m: Vec<_> = self.um.iter().flat_map(|&(g, c)| dofs(g, c)).collect();
+        let n = reference
+            .chain(weighted)
+            .filter(|d| !m.contains(d))
+            .collect();
+        (m, n)
+    }
+}
+
+impl TryFrom<&BulkCard> for RBE3 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"RBE3   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"RBE3   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"RBE3   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Any, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        iter.next();
+        let refgrid = iter.next().id()?;
+        let refc = iter.next().dof()?;
+        let mut weights = Vec::<Weight>::new();
+        let mut um = Vec::new();
+        let mut alpha = 0.0;
+        let mut in_um = false;
+        while let Some(field) = iter.next() {
+            match field {
+                Field::Blank => {}
+                Field::Text(text) if text.trim_end() == b"UM" => in_um = true,
+                Field::Text(text) if text.trim_end() == b"ALPHA" => {
+                    alpha = iter.next().float_or(0.0)?;
+                }
+                Field::IntOrId(g) if in_um => um.push((g, iter.next().dof()?)),
+                Field::IntOrId(g) => match weights.last_mut() {
+                    Some(weight) => weight.grids.push(g),
+                    None => return Err(Error::UnexpectedField("WT1", field)),
+                },
+                Field::Float(_) | Field::Double(_) if !in_um => weights.push(Weight {
+                    weight: field.float()?,
+                    components: iter.next().dof()?,
+                    grids: Vec::new(),
+                }),
+                field => return Err(Error::UnexpectedField("RBE3", field)),
+            }
+        }
+        Ok(RBE3 {
+            eid,
+            refgrid,
+            refc,
+            weights,
+            um,
+            alpha,
+        })
+    }
+}
+
+/// A rigid bar between two grids
+#[derive(Debug, Clone)]
+pub struct RBAR {
+    eid: u32,
+    g: [u32; 2],
+    cn: [[bool; 6]; 2],
+    cm: [[bool; 6]; 2],
+    #[allow(dead_code)]
+    alpha: f64,
+}
+
+impl StorageItem for RBAR {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl Constraint for RBAR {
+    fn source(&self) -> Source {
+        Source::Rbar(self.eid)
+    }
+
+    fn dofs(&self) -> (Vec<Dof>, Vec<Dof>) {
+        let pairs = |c: [[bool; 6]; 2]| {
+            (0..2)
+                .flat_map(|i| dofs(self.g[i], c[i]))
+                .collect::<Vec<_>>()
+        };
+        // With no dependent components given, every component that isn't
+        // independent is dependent
+        let cm = if self.cm == [[false; 6]; 2] {
+            self.cn.map(|c| c.map(|c| !c))
+        } else {
+            self.cm
+        };
+        (pairs(cm), pairs(self.cn))
+    }
+}
+
+impl TryFrom<&BulkCard> for RBAR {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"RBAR   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"RBAR   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"RBAR   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Int, Int, Int, Int, Int, Real])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let g = [iter.next().id()?, iter.next().id()?];
+        let cn = [iter.next().dof()?, iter.next().dof()?];
+        let cm = [iter.next().dof()?, iter.next().dof()?];
+        let alpha = iter.next().float_or(0.0)?;
+        Ok(RBAR {
+            eid,
+            g,
+            cn,
+            cm,
+            alpha,
+        })
+    }
+}
+
+/// A rigid body with independent components on up to six grids
+#[derive(Debug, Clone)]
+pub struct RBE1 {
+    eid: u32,
+    n: Vec<(u32, [bool; 6])>,
+    m: Vec<(u32, [bool; 6])>,
+    #[allow(dead_code)]
+    alpha: f64,
+}
+
+impl StorageItem for RBE1 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl Constraint for RBE1 {
+    fn source(&self) -> Source {
+        Source::Rbe1(self.eid)
+    }
+
+    fn dofs(&self) -> (Vec<Dof>, Vec<Dof>) {
+        let all = |g: &[(u32, [bool; 6])]| g.iter().flat_map(|&(g, c)| dofs(g, c)).collect();
+        (all(&self.m), all(&self.n))
+    }
+}
+
+impl TryFrom<&BulkCard> for RBE1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"RBE1   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"RBE1   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"RBE1   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let mut n = Vec::new();
+        let mut m = Vec::new();
+        let mut alpha = 0.0;
+        let mut in_um = false;
+        while let Some(field) = iter.next() {
+            match field {
+                Field::Blank => {}
+                Field::Text(text) if text.trim_end() == b"UM" => in_um = true,
+                Field::IntOrId(g) => {
+                    let c = iter.next().dof()?;
+                    if in_um {
+                        m.push((g, c));
+                    } else {
+                        n.push((g, c));
+                    }
+                }
+                field => {
+                    alpha = self::alpha(field, &mut iter)?;
+                    break;
+                }
+            }
+        }
+        Ok(RBE1 { eid, n, m, alpha })
+    }
+}
+
+/// A rod that is rigid in extension, with one translational component at one
+/// end dependent on the others
+#[derive(Debug, Clone)]
+pub struct RROD {
+    eid: u32,
+    g: [u32; 2],
+    cm: [[bool; 6]; 2],
+    #[allow(dead_code)]
+    alpha: f64,
+}
+
+impl StorageItem for RROD {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl Constraint for RROD {
+    fn source(&self) -> Source {
+        Source::Rrod(self.eid)
+    }
+
+    fn dofs(&self) -> (Vec<Dof>, Vec<Dof>) {
+        let m: Vec<_> = (0..2).flat_map(|i| dofs(self.g[i], self.cm[i])).collect();
+        let n = (0..2)
+            .flat_map(|i| dofs(self.g[i], TRANSLATIONS))
+            .filter(|d| !m.contains(d))
+            .collect();
+        (m, n)
+    }
+}
+
+impl TryFrom<&BulkCard> for RROD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"RROD   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"RROD   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"RROD   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int, Int, Real])?.into_iter();
+        let eid = iter.next().id()?;
+        let g = [iter.next().id()?, iter.next().id()?];
+        let fields = [
+            iter.next().unwrap_or_default(),
+            iter.next().unwrap_or_default(),
+        ];
+        let cm = [fields[0].dof()?, fields[1].dof()?];
+        // Exactly one translational component is dependent
+        let count = |c: [bool; 6]| c.iter().filter(|&&c| c).count();
+        for (field, c) in fields.iter().zip(&cm) {
+            if count(*c) > 1 || c[3..].contains(&true) {
+                return Err(Error::UnexpectedDOF(*field));
+            }
+        }
+        if count(cm[0]) + count(cm[1]) != 1 {
+            return Err(Error::UnexpectedDOF(fields[1]));
+        }
+        let alpha = iter.next().float_or(0.0)?;
+        Ok(RROD { eid, g, cm, alpha })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::deck::Deck;
+
+    #[test]
+    fn test_rigid() {
+        let input = b"RBE2,1,10,123,11,12,
+,13,1.-5
+RBE3,2,,20,123456,1.,123,21,22
+,0.5,123,23,UM,21,123
+RBAR,3,30,31,123456
+RBE1,4,40,123,41,123,42,3
+,43,3,,,,,
+,UM,44,123456,ALPHA,2.-5
+RROD,5,50,51,,3
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let rbe2 = bulk.get_rbe2(1).unwrap();
+        assert_eq!(rbe2.gm, vec![11, 12, 13]);
+        assert_eq!(rbe2.alpha, 1e-5f32 as f64);
+        assert_eq!(rbe2.dofs().0.len(), 9);
+
+        let rbe3 = bulk.get_rbe3(2).unwrap();
+        assert_eq!(rbe3.weights.len(), 2);
+        assert_eq!(rbe3.weights[1].grids, vec![23]);
+        let (m, n) = rbe3.dofs();
+        assert_eq!(m, (1..=3).map(|c| Dof::new(21, c)).collect::<Vec<_>>());
+        assert!(n.contains(&Dof::new(20, 6)));
+        assert!(!n.contains(&Dof::new(21, 1)));
+
+        let (m, n) = bulk.get_rbar(3).unwrap().dofs();
+        assert_eq!(m, (1..=6).map(|c| Dof::new(31, c)).collect::<Vec<_>>());
+        assert_eq!(n.len(), 6);
+
+        let rbe1 = bulk.get_rbe1(4).unwrap();
+        assert_eq!(rbe1.n.len(), 4);
+        assert_eq!(rbe1.m, vec![(44, ALL)]);
+        assert_eq!(rbe1.alpha, 2e-5f32 as f64);
+
+        let (m, n) = bulk.get_rrod(5).unwrap().dofs();
+        assert_eq!(m, vec![Dof::new(51, 3)]);
+        assert_eq!(n.len(), 5);
+
+        assert!(Deck::from_buffer(b"RROD,1,1,2,1,2").is_err());
+        assert!(Deck::from_buffer(b"RROD,1,1,2,4").is_err());
+    }
+}
@@ -0,0 +1,829 @@
+use std::convert::TryFrom;
+
+use super::dof::component;
+use super::{BulkData, Element, Property, StorageItem};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+/// A grid and one of its components, or a scalar point with component 0.
+/// Grid 0 is ground
+type Connection = (u32, u8);
+
+fn connection(iter: &mut impl Iterator<Item = Field>) -> Result<Connection> {
+    let grid = iter.next().id_or(0)?;
+    Ok((grid, component(iter.next().unwrap_or_default())?))
+}
+
+fn scalar_points(iter: &mut impl Iterator<Item = Field>) -> Result<[u32; 2]> {
+    Ok([iter.next().id_or(0)?, iter.next().id_or(0)?])
+}
+
+#[derive(Debug, Clone)]
+pub struct CELAS1 {
+    eid: u32,
+    pid: u32,
+    g: [Connection; 2],
+}
+
+impl CELAS1 {
+    /// The grid and component at each end, where grid 0 is ground and
+    /// component 0 is a scalar point
+    pub fn g(&self) -> [(u32, u8); 2] {
+        self.g
+    }
+}
+
+impl Element for CELAS1 {
+    type Property = PELAS;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl StorageItem for CELAS1 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CELAS1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CELAS1 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CELAS1 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CELAS1 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let g = [connection(&mut iter)?, connection(&mut iter)?];
+        Ok(CELAS1 { eid, pid, g })
+    }
+}
+
+/// A spring with its properties given on the element
+#[derive(Debug, Clone)]
+pub struct CELAS2 {
+    eid: u32,
+    k: f64,
+    g: [Connection; 2],
+    ge: f64,
+    /// The stress coefficient
+    s: f64,
+}
+
+impl CELAS2 {
+    /// The stiffness
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// The grid and component at each end, where grid 0 is ground and
+    /// component 0 is a scalar point
+    pub fn g(&self) -> [(u32, u8); 2] {
+        self.g
+    }
+
+    /// The damping coefficient
+    pub fn ge(&self) -> f64 {
+        self.ge
+    }
+
+    /// The stress coefficient
+    pub fn s(&self) -> f64 {
+        self.s
+    }
+}
+
+impl StorageItem for CELAS2 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CELAS2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CELAS2 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CELAS2 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CELAS2 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Real, Int, Int, Int, Int, Real, Real])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let k = iter.next().float()?;
+        let g = [connection(&mut iter)?, connection(&mut iter)?];
+        let ge = iter.next().float_or(0.0)?;
+        let s = iter.next().float_or(0.0)?;
+        Ok(CELAS2 { eid, k, g, ge, s })
+    }
+}
+
+/// A spring between scalar points
+#[derive(Debug, Clone)]
+pub struct CELAS3 {
+    eid: u32,
+    pid: u32,
+    s: [u32; 2],
+}
+
+impl CELAS3 {
+    /// The scalar points at each end, where 0 is ground
+    pub fn s(&self) -> [u32; 2] {
+        self.s
+    }
+}
+
+impl Element for CELAS3 {
+    type Property = PELAS;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl StorageItem for CELAS3 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CELAS3 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CELAS3 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CELAS3 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CELAS3 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let s = scalar_points(&mut iter)?;
+        Ok(CELAS3 { eid, pid, s })
+    }
+}
+
+/// A spring between scalar points with its stiffness given on the element
+#[derive(Debug, Clone)]
+pub struct CELAS4 {
+    eid: u32,
+    k: f64,
+    s: [u32; 2],
+}
+
+impl CELAS4 {
+    /// The stiffness
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// The scalar points at each end, where 0 is ground
+    pub fn s(&self) -> [u32; 2] {
+        self.s
+    }
+}
+
+impl StorageItem for CELAS4 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CELAS4 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CELAS4 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CELAS4 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CELAS4 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Real, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let k = iter.next().float()?;
+        let s = scalar_points(&mut iter)?;
+        Ok(CELAS4 { eid, k, s })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PELAS {
+    pid: u32,
+    k: f64,
+    ge: f64,
+    /// The stress coefficient
+    s: f64,
+}
+
+impl PELAS {
+    /// The stiffness
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// The damping coefficient
+    pub fn ge(&self) -> f64 {
+        self.ge
+    }
+
+    /// The stress coefficient
+    pub fn s(&self) -> f64 {
+        self.s
+    }
+}
+
+impl StorageItem for PELAS {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+/// A PELAS card holds up to two properties
+impl TryFrom<&BulkCard> for Vec<PELAS> {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PELAS  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PELAS  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PELAS  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Real, Real, Real, Int, Real, Real, Real])?
+            .into_iter();
+        let mut properties = Vec::with_capacity(2);
+        for i in 0..2 {
+            let pid = iter.next().unwrap_or_default();
+            if i > 0 && pid == Field::Blank {
+                break;
+            }
+            properties.push(PELAS {
+                pid: pid.id()?,
+                k: iter.next().float()?,
+                ge: iter.next().float_or(0.0)?,
+                s: iter.next().float_or(0.0)?,
+            });
+        }
+        Ok(properties)
+    }
+}
+
+impl Property for PELAS {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.pelas.get(id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CDAMP1 {
+    eid: u32,
+    pid: u32,
+    g: [Connection; 2],
+}
+
+impl CDAMP1 {
+    /// The grid and component at each end, where grid 0 is ground and
+    /// component 0 is a scalar point
+    pub fn g(&self) -> [(u32, u8); 2] {
+        self.g
+    }
+}
+
+impl Element for CDAMP1 {
+    type Property = PDAMP;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl StorageItem for CDAMP1 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CDAMP1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CDAMP1 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CDAMP1 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CDAMP1 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let g = [connection(&mut iter)?, connection(&mut iter)?];
+        Ok(CDAMP1 { eid, pid, g })
+    }
+}
+
+/// A damper with its damping given on the element
+#[derive(Debug, Clone)]
+pub struct CDAMP2 {
+    eid: u32,
+    b: f64,
+    g: [Connection; 2],
+}
+
+impl CDAMP2 {
+    /// The damping
+    pub fn b(&self) -> f64 {
+        self.b
+    }
+
+    /// The grid and component at each end, where grid 0 is ground and
+    /// component 0 is a scalar point
+    pub fn g(&self) -> [(u32, u8); 2] {
+        self.g
+    }
+}
+
+impl StorageItem for CDAMP2 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CDAMP2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CDAMP2 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CDAMP2 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CDAMP2 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Real, Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let b = iter.next().float()?;
+        let g = [connection(&mut iter)?, connection(&mut iter)?];
+        Ok(CDAMP2 { eid, b, g })
+    }
+}
+
+/// A damper between scalar points
+#[derive(Debug, Clone)]
+pub struct CDAMP3 {
+    eid: u32,
+    pid: u32,
+    s: [u32; 2],
+}
+
+impl CDAMP3 {
+    /// The scalar points at each end, where 0 is ground
+    pub fn s(&self) -> [u32; 2] {
+        self.s
+    }
+}
+
+impl Element for CDAMP3 {
+    type Property = PDAMP;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl StorageItem for CDAMP3 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CDAMP3 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CDAMP3 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CDAMP3 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CDAMP3 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let s = scalar_points(&mut iter)?;
+        Ok(CDAMP3 { eid, pid, s })
+    }
+}
+
+/// A damper between scalar points with its damping given on the element
+#[derive(Debug, Clone)]
+pub struct CDAMP4 {
+    eid: u32,
+    b: f64,
+    s: [u32; 2],
+}
+
+impl CDAMP4 {
+    /// The damping
+    pub fn b(&self) -> f64 {
+        self.b
+    }
+
+    /// The scalar points at each end, where 0 is ground
+    pub fn s(&self) -> [u32; 2] {
+        self.s
+    }
+}
+
+impl StorageItem for CDAMP4 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CDAMP4 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CDAMP4 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CDAMP4 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CDAMP4 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Real, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let b = iter.next().float()?;
+        let s = scalar_points(&mut iter)?;
+        Ok(CDAMP4 { eid, b, s })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PDAMP {
+    pid: u32,
+    b: f64,
+}
+
+impl PDAMP {
+    /// The damping
+    pub fn b(&self) -> f64 {
+        self.b
+    }
+}
+
+impl StorageItem for PDAMP {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+/// A PDAMP card holds up to four properties
+impl TryFrom<&BulkCard> for Vec<PDAMP> {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PDAMP  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PDAMP  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PDAMP  ", *b"       ")),
+        }
+        pairs(card)?
+            .into_iter()
+            .map(|(pid, b)| Ok(PDAMP { pid, b }))
+            .collect()
+    }
+}
+
+impl Property for PDAMP {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.pdamp.get(id)
+    }
+}
+
+/// Reads the pairs of IDs and values of PDAMP and PMASS, stopping at the
+/// first blank ID after the first
+fn pairs(card: &BulkCard) -> Result<Vec<(u32, f64)>> {
+    use FieldKind::*;
+    let mut iter = card
+        .decode(&[Int, Real, Int, Real, Int, Real, Int, Real])?
+        .into_iter();
+    let mut pairs = Vec::with_capacity(4);
+    for i in 0..4 {
+        let id = iter.next().unwrap_or_default();
+        if i > 0 && id == Field::Blank {
+            break;
+        }
+        pairs.push((id.id()?, iter.next().float()?));
+    }
+    Ok(pairs)
+}
+
+#[derive(Debug, Clone)]
+pub struct CMASS1 {
+    eid: u32,
+    pid: u32,
+    g: [Connection; 2],
+}
+
+impl CMASS1 {
+    /// The grid and component at each end, where grid 0 is ground and
+    /// component 0 is a scalar point
+    pub fn g(&self) -> [(u32, u8); 2] {
+        self.g
+    }
+}
+
+impl Element for CMASS1 {
+    type Property = PMASS;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl StorageItem for CMASS1 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CMASS1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CMASS1 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CMASS1 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CMASS1 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let g = [connection(&mut iter)?, connection(&mut iter)?];
+        Ok(CMASS1 { eid, pid, g })
+    }
+}
+
+/// A scalar mass with its value given on the element
+#[derive(Debug, Clone)]
+pub struct CMASS2 {
+    eid: u32,
+    m: f64,
+    g: [Connection; 2],
+}
+
+impl CMASS2 {
+    /// The mass
+    pub fn m(&self) -> f64 {
+        self.m
+    }
+
+    /// The grid and component at each end, where grid 0 is ground and
+    /// component 0 is a scalar point
+    pub fn g(&self) -> [(u32, u8); 2] {
+        self.g
+    }
+}
+
+impl StorageItem for CMASS2 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CMASS2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CMASS2 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CMASS2 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CMASS2 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Real, Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let m = iter.next().float()?;
+        let g = [connection(&mut iter)?, connection(&mut iter)?];
+        Ok(CMASS2 { eid, m, g })
+    }
+}
+
+/// A scalar mass between scalar points
+#[derive(Debug, Clone)]
+pub struct CMASS3 {
+    eid: u32,
+    pid: u32,
+    s: [u32; 2],
+}
+
+impl CMASS3 {
+    /// The scalar points at each end, where 0 is ground
+    pub fn s(&self) -> [u32; 2] {
+        self.s
+    }
+}
+
+impl Element for CMASS3 {
+    type Property = PMASS;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl StorageItem for CMASS3 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CMASS3 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CMASS3 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CMASS3 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CMASS3 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let s = scalar_points(&mut iter)?;
+        Ok(CMASS3 { eid, pid, s })
+    }
+}
+
+/// A scalar mass between scalar points with its value given on the element
+#[derive(Debug, Clone)]
+pub struct CMASS4 {
+    eid: u32,
+    m: f64,
+    s: [u32; 2],
+}
+
+impl CMASS4 {
+    /// The mass
+    pub fn m(&self) -> f64 {
+        self.m
+    }
+
+    /// The scalar points at each end, where 0 is ground
+    pub fn s(&self) -> [u32; 2] {
+        self.s
+    }
+}
+
+impl StorageItem for CMASS4 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CMASS4 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CMASS4 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CMASS4 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CMASS4 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Real, Int, Int])?.into_iter();
+        let eid = iter.next().id()?;
+        let m = iter.next().float()?;
+        let s = scalar_points(&mut iter)?;
+        Ok(CMASS4 { eid, m, s })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PMASS {
+    pid: u32,
+    m: f64,
+}
+
+impl PMASS {
+    /// The mass
+    pub fn m(&self) -> f64 {
+        self.m
+    }
+}
+
+impl StorageItem for PMASS {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+/// A PMASS card holds up to four properties
+impl TryFrom<&BulkCard> for Vec<PMASS> {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PMASS  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PMASS  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PMASS  ", *b"       ")),
+        }
+        pairs(card)?
+            .into_iter()
+            .map(|(pid, m)| Ok(PMASS { pid, m }))
+            .collect()
+    }
+}
+
+impl Property for PMASS {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.pmass.get(id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdf::deck::Deck;
+
+    #[test]
+    fn test_scalar() {
+        let input = b"CELAS1,1,2,10,1,11,3
+CELAS2,2,100.,10,4,,,0.5,2.
+CELAS3,3,2,20,21
+PELAS,2,50.,0.25,4.,3,60.
+PDAMP,5,1.,6,2.,7,3.
+PMASS,8,0.5
+CMASS4,9,2.,20
+CDAMP1,10,5,10,3
+CELAS4,11,30.,22,23
+CDAMP2,12,4.,10,2
+CDAMP3,13,5,24,25
+CDAMP4,14,6.,26
+CMASS1,15,8,10,6
+CMASS2,16,7.,10,5,11,5
+CMASS3,17,8,27,28
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let celas1 = bulk.get_celas1(1).unwrap();
+        assert_eq!(celas1.g(), [(10, 1), (11, 3)]);
+        let celas2 = bulk.get_celas2(2).unwrap();
+        assert_eq!(celas2.g(), [(10, 4), (0, 0)]);
+        assert_eq!((celas2.k(), celas2.ge(), celas2.s()), (100., 0.5, 2.));
+        assert_eq!(bulk.get_celas3(3).unwrap().s(), [20, 21]);
+        let celas4 = bulk.get_celas4(11).unwrap();
+        assert_eq!((celas4.k(), celas4.s()), (30., [22, 23]));
+        let pelas = bulk.get_pelas(2).unwrap();
+        assert_eq!((pelas.k(), pelas.ge(), pelas.s()), (50., 0.25, 4.));
+        assert_eq!(bulk.get_pelas(3).unwrap().k(), 60.);
+
+        assert_eq!(bulk.get_pdamp(7).unwrap().b(), 3.);
+        assert!(bulk.get_pdamp(8).is_none());
+        assert_eq!(bulk.get_cdamp1(10).unwrap().g()[1], (0, 0));
+        let cdamp2 = bulk.get_cdamp2(12).unwrap();
+        assert_eq!((cdamp2.b(), cdamp2.g()), (4., [(10, 2), (0, 0)]));
+        assert_eq!(bulk.get_cdamp3(13).unwrap().s(), [24, 25]);
+        let cdamp4 = bulk.get_cdamp4(14).unwrap();
+        assert_eq!((cdamp4.b(), cdamp4.s()), (6., [26, 0]));
+
+        assert_eq!(bulk.get_pmass(8).unwrap().m(), 0.5);
+        assert_eq!(bulk.get_cmass1(15).unwrap().g(), [(10, 6), (0, 0)]);
+        let cmass2 = bulk.get_cmass2(16).unwrap();
+        assert_eq!((cmass2.m(), cmass2.g()), (7., [(10, 5), (11, 5)]));
+        assert_eq!(bulk.get_cmass3(17).unwrap().s(), [27, 28]);
+        let cmass4 = bulk.get_cmass4(9).unwrap();
+        assert_eq!((cmass4.m(), cmass4.s()), (2., [20, 0]));
+
+        assert!(Deck::from_buffer(b"CELAS1,1,2,10,7").is_err());
+        assert!(Deck::from_buffer(b"PMASS,1,1.\nPMASS,1,2.").is_err());
+    }
+}
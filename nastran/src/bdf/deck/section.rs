@@ -0,0 +1,608 @@
+//! Cross-sections of bars and beams given by shape and dimensions. The
+//! element y axis runs up the page in NASTRAN's section drawings and the z
+//! axis across it, so I1 is about the horizontal axis and the "height"
+//! dimensions are along y
+
+use std::convert::TryFrom;
+use std::f64::consts::PI;
+
+use bstr::ByteSlice;
+use smallvec::SmallVec;
+
+use super::{StorageItem, StressOutput, PBAR, PBEAM};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+/// A standard cross-section shape of a PBARL or PBEAML
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Radius
+    Rod,
+    /// Outer and inner radius
+    Tube,
+    /// Width and height
+    Bar,
+    /// Width, height, thickness of the top and bottom and thickness of the
+    /// sides
+    Box,
+    /// Height, bottom flange width, top flange width, web thickness, bottom
+    /// flange thickness and top flange thickness
+    I,
+    /// Flange width, height, flange thickness and web thickness, with the
+    /// flange at the top
+    T,
+    /// Width, height, thickness of the horizontal leg and thickness of the
+    /// vertical leg
+    L,
+    /// Width, height, web thickness and flange thickness
+    Chan,
+    /// Height, thickness, width of the top and width of each brim
+    Hat,
+    /// Flange width beyond the web, web thickness, flange thickness and
+    /// height
+    Z,
+}
+
+/// Properties of a cross-section, in the element y and z axes about its
+/// centroid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Section {
+    pub a: f64,
+    /// The area moment of inertia for bending in plane 1, about z
+    pub i1: f64,
+    /// The area moment of inertia for bending in plane 2, about y
+    pub i2: f64,
+    pub i12: f64,
+    /// The torsional constant
+    pub j: f64,
+    /// The shear area factors for plane 1 and plane 2
+    pub k1: f64,
+    pub k2: f64,
+    /// The y and z coordinates of the stress recovery points C, D, E and F
+    pub stress: [f64; 8],
+}
+
+/// Part of a section between `y` and `z` limits
+struct Rect {
+    y: (f64, f64),
+    z: (f64, f64),
+}
+
+impl Rect {
+    fn new(y0: f64, y1: f64, z0: f64, z1: f64) -> Self {
+        Rect {
+            y: (y0, y1),
+            z: (z0, z1),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        (self.y.1 - self.y.0) * (self.z.1 - self.z.0)
+    }
+
+    /// The torsional constant of a solid rectangle, from Roark
+    fn torsion(&self) -> f64 {
+        let dy = self.y.1 - self.y.0;
+        let dz = self.z.1 - self.z.0;
+        let (a, b) = if dy > dz { (dy, dz) } else { (dz, dy) };
+        a * b.powi(3) * (1. / 3. - 0.21 * b / a * (1. - b.powi(4) / (12. * a.powi(4))))
+    }
+}
+
+/// The y and z coordinates of the stress recovery points C, D, E and F of a
+/// shape, in the same axes as its rectangles
+type Points = [(f64, f64); 4];
+
+/// The corners of a bar `h` high and `b` wide, starting from the top at the
+/// largest z
+fn corners(h: f64, b: f64) -> Points {
+    [(h, b), (0.0, b), (0.0, 0.0), (h, 0.0)]
+}
+
+/// The properties of a section made of rectangles that don't overlap.
+/// Shear is carried by the rectangles that are longer in that direction
+fn rectangles(rects: &[Rect], j: f64, points: Points) -> Section {
+    let a = rects.iter().map(Rect::area).sum::<f64>();
+    let yc = rects
+        .iter()
+        .map(|r| r.area() * (r.y.0 + r.y.1) / 2.)
+        .sum::<f64>()
+        / a;
+    let zc = rects
+        .iter()
+        .map(|r| r.area() * (r.z.0 + r.z.1) / 2.)
+        .sum::<f64>()
+        / a;
+    let mut section = Section {
+        a,
+        i1: 0.0,
+        i2: 0.0,
+        i12: 0.0,
+        j,
+        k1: 0.0,
+        k2: 0.0,
+        stress: [0.0; 8],
+    };
+    for r in rects {
+        let (y0, y1) = (r.y.0 - yc, r.y.1 - yc);
+        let (z0, z1) = (r.z.0 - zc, r.z.1 - zc);
+        section.i1 += (z1 - z0) * (y1.powi(3) - y0.powi(3)) / 3.;
+        section.i2 += (y1 - y0) * (z1.powi(3) - z0.powi(3)) / 3.;
+        section.i12 += (y1 * y1 - y0 * y0) * (z1 * z1 - z0 * z0) / 4.;
+        if y1 - y0 > z1 - z0 {
+            section.k1 += r.area() / a;
+        } else if z1 - z0 > y1 - y0 {
+            section.k2 += r.area() / a;
+        }
+    }
+    for (stress, (y, z)) in section.stress.chunks_mut(2).zip(points) {
+        stress.copy_from_slice(&[y - yc, z - zc]);
+    }
+    section
+}
+
+fn circle(r: f64, area: f64, i: f64, k: f64) -> Section {
+    Section {
+        a: area,
+        i1: i,
+        i2: i,
+        i12: 0.0,
+        j: 2. * i,
+        k1: k,
+        k2: k,
+        stress: [r, 0.0, 0.0, r, -r, 0.0, 0.0, -r],
+    }
+}
+
+impl Shape {
+    pub fn from_name(name: &str) -> Option<Shape> {
+        Some(match name {
+            "ROD" => Shape::Rod,
+            "TUBE" => Shape::Tube,
+            "BAR" => Shape::Bar,
+            "BOX" => Shape::Box,
+            "I" => Shape::I,
+            "T" => Shape::T,
+            "L" => Shape::L,
+            "CHAN" => Shape::Chan,
+            "HAT" => Shape::Hat,
+            "Z" => Shape::Z,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Rod => "ROD",
+            Shape::Tube => "TUBE",
+            Shape::Bar => "BAR",
+            Shape::Box => "BOX",
+            Shape::I => "I",
+            Shape::T => "T",
+            Shape::L => "L",
+            Shape::Chan => "CHAN",
+            Shape::Hat => "HAT",
+            Shape::Z => "Z",
+        }
+    }
+
+    /// The number of dimensions the shape takes
+    pub fn dimensions(self) -> usize {
+        match self {
+            Shape::Rod => 1,
+            Shape::Tube | Shape::Bar => 2,
+            Shape::I => 6,
+            _ => 4,
+        }
+    }
+
+    /// Works out the properties of the section from its dimensions, which
+    /// are in the order of the DIM fields. The stress recovery points are
+    /// where NASTRAN puts them for the shape, which are the outer corners of
+    /// a bar or box and otherwise the ends of the parts furthest from the
+    /// centroid, such as the flange tips of an I
+    pub fn section(self, dim: &[f64]) -> Result<Section> {
+        let invalid = || Error::InvalidSection(self.name());
+        if dim.len() != self.dimensions() || !dim.iter().all(|&d| d > 0.0) {
+            return Err(invalid());
+        }
+        let check = |valid: bool| if valid { Ok(()) } else { Err(invalid()) };
+        let section = match *dim {
+            [r] => circle(r, PI * r * r, PI * r.powi(4) / 4., 0.9),
+            [r1, r2] if self == Shape::Tube => {
+                check(r2 < r1)?;
+                let area = PI * (r1 * r1 - r2 * r2);
+                circle(r1, area, PI * (r1.powi(4) - r2.powi(4)) / 4., 0.5)
+            }
+            [b, h] => {
+                let rect = Rect::new(0.0, h, 0.0, b);
+                let j = rect.torsion();
+                Section {
+                    k1: 5. / 6.,
+                    k2: 5. / 6.,
+                    ..rectangles(&[rect], j, corners(h, b))
+                }
+            }
+            [b, h, t1, t2] if self == Shape::Box => {
+                check(2. * t1 < h && 2. * t2 < b)?;
+                // Bredt's formula for a closed thin-walled section
+                let (bm, hm) = (b - t2, h - t1);
+                let j = 4. * (bm * hm).powi(2) / (2. * bm / t1 + 2. * hm / t2);
+                rectangles(
+                    &[
+                        Rect::new(0.0, t1, 0.0, b),
+                        Rect::new(h - t1, h, 0.0, b),
+                        Rect::new(t1, h - t1, 0.0, t2),
+                        Rect::new(t1, h - t1, b - t2, b),
+                    ],
+                    j,
+                    corners(h, b),
+                )
+            }
+            [d, b1, b2, tw, tf1, tf2] => {
+                check(tf1 + tf2 < d && tw < b1.min(b2))?;
+                open(
+                    &[
+                        Rect::new(0.0, tf1, -b1 / 2., b1 / 2.),
+                        Rect::new(d - tf2, d, -b2 / 2., b2 / 2.),
+                        Rect::new(tf1, d - tf2, -tw / 2., tw / 2.),
+                    ],
+                    [(d, b2 / 2.), (0.0, b1 / 2.), (0.0, -b1 / 2.), (d, -b2 / 2.)],
+                )
+            }
+            [b, h, tf, tw] if self == Shape::T => {
+                check(tf < h && tw < b)?;
+                open(
+                    &[
+                        Rect::new(h - tf, h, -b / 2., b / 2.),
+                        Rect::new(0.0, h - tf, -tw / 2., tw / 2.),
+                    ],
+                    [(h, b / 2.), (0.0, tw / 2.), (0.0, -tw / 2.), (h, -b / 2.)],
+                )
+            }
+            [b, h, t1, t2] if self == Shape::L => {
+                check(t1 < h && t2 < b)?;
+                open(
+                    &[Rect::new(0.0, h, 0.0, t2), Rect::new(0.0, t1, t2, b)],
+                    [(h, t2), (0.0, b), (0.0, 0.0), (h, 0.0)],
+                )
+            }
+            [b, h, tw, tf] if self == Shape::Chan => {
+                check(2. * tf < h && tw < b)?;
+                open(
+                    &[
+                        Rect::new(0.0, h, 0.0, tw),
+                        Rect::new(0.0, tf, tw, b),
+                        Rect::new(h - tf, h, tw, b),
+                    ],
+                    corners(h, b),
+                )
+            }
+            [h, t, w, b] if self == Shape::Hat => {
+                check(t < h && 2. * t < w)?;
+                let w = w / 2.;
+                open(
+                    &[
+                        Rect::new(h - t, h, -w, w),
+                        Rect::new(0.0, h - t, -w, t - w),
+                        Rect::new(0.0, h - t, w - t, w),
+                        Rect::new(0.0, t, -w - b, -w),
+                        Rect::new(0.0, t, w, w + b),
+                    ],
+                    [(h, w), (0.0, w + b), (0.0, -w - b), (h, -w)],
+                )
+            }
+            [b, tw, tf, h] => {
+                check(2. * tf < h)?;
+                open(
+                    &[
+                        Rect::new(0.0, h, 0.0, tw),
+                        Rect::new(h - tf, h, tw, tw + b),
+                        Rect::new(0.0, tf, -b, 0.0),
+                    ],
+                    [(h, tw + b), (0.0, tw), (0.0, -b), (h, 0.0)],
+                )
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(section)
+    }
+}
+
+/// An open section, where the torsional constant is the sum of its parts
+fn open(rects: &[Rect], points: Points) -> Section {
+    let j = rects.iter().map(Rect::torsion).sum();
+    rectangles(rects, j, points)
+}
+
+/// Reads the TYPE field
+fn shape(field: Option<Field>) -> Result<Shape> {
+    let field = field.unwrap_or_default();
+    let name = match field {
+        Field::Text(text) => text,
+        _ => return Err(Error::UnexpectedField("TYPE", field)),
+    };
+    let name = name.trim_end();
+    name.to_str()
+        .ok()
+        .and_then(Shape::from_name)
+        .ok_or_else(|| Error::UnknownSection(name.to_vec()))
+}
+
+#[derive(Debug, Clone)]
+pub struct PBARL {
+    group: Field,
+    shape: Shape,
+    dim: SmallVec<[f64; 6]>,
+    section: Section,
+    /// The PBAR with the properties of the section
+    pub(super) pbar: PBAR,
+}
+
+impl PBARL {
+    /// The GROUP field, which is blank for the standard shapes
+    pub fn group(&self) -> Field {
+        self.group
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// The dimensions in the order of the DIM fields
+    pub fn dimensions(&self) -> &[f64] {
+        &self.dim
+    }
+
+    /// The properties worked out from the shape and dimensions
+    pub fn section(&self) -> Section {
+        self.section
+    }
+}
+
+impl StorageItem for PBARL {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pbar.id()
+    }
+}
+
+impl TryFrom<&BulkCard> for PBARL {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PBARL  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PBARL  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PBARL  ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Char, Char, Any, Any, Any, Any, Real, Real, Real])?
+            .into_iter();
+        let pid = iter.next().id()?;
+        let mid = iter.next().id()?;
+        let group = iter.next().unwrap_or_default();
+        let shape = shape(iter.next())?;
+        let mut iter = iter.skip(4);
+        let dim = (0..shape.dimensions())
+            .map(|_| iter.next().float())
+            .collect::<Result<SmallVec<_>>>()?;
+        let nsm = iter.next().float_or(0.0)?;
+        let section = shape.section(&dim)?;
+        let pbar = PBAR::from_section(pid, mid, &section, nsm);
+        Ok(PBARL {
+            group,
+            shape,
+            dim,
+            section,
+            pbar,
+        })
+    }
+}
+
+/// Reads the `n` dimensions and the nonstructural mass of a PBEAML station
+fn station(iter: &mut impl Iterator<Item = Field>, n: usize) -> Result<SmallVec<[Option<f64>; 7]>> {
+    (0..=n).map(|_| iter.next().maybe_float()).collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct PBEAML {
+    group: Field,
+    shape: Shape,
+    /// The dimensions at each station, starting from end A
+    dim: Vec<SmallVec<[f64; 6]>>,
+    sections: Vec<Section>,
+    /// The PBEAM with the properties of the section at each station
+    pub(super) pbeam: PBEAM,
+}
+
+impl PBEAML {
+    /// The GROUP field, which is blank for the standard shapes
+    pub fn group(&self) -> Field {
+        self.group
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// The dimensions at each station, starting from end A, with blanks
+    /// filled in
+    pub fn dimensions(&self) -> impl Iterator<Item = &[f64]> + '_ {
+        self.dim.iter().map(|dim| dim.as_slice())
+    }
+
+    /// The properties of the section at each station, starting from end A
+    pub fn sections(&self) -> impl Iterator<Item = Section> + '_ {
+        self.sections.iter().copied()
+    }
+}
+
+impl StorageItem for PBEAML {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pbeam.id()
+    }
+}
+
+impl TryFrom<&BulkCard> for PBEAML {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PBEAML ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PBEAML ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PBEAML ", *b"       ")),
+        }
+        // The stations run on from one another without starting new lines,
+        // so only the first line has a schema
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Char, Char])?.into_iter();
+        let pid = iter.next().id()?;
+        let mid = iter.next().id()?;
+        let group = iter.next().unwrap_or_default();
+        let shape = shape(iter.next())?;
+        let mut iter = iter.skip(4);
+        let n = shape.dimensions();
+        let end_a = station(&mut iter, n)?;
+        let mut stations = vec![(0.0, StressOutput::Yes, end_a.clone())];
+        while let Some(field) = iter.next() {
+            let x = iter.next().unwrap_or_default();
+            let so = match field {
+                Field::Blank if x == Field::Blank => break,
+                Field::Blank => StressOutput::Yes,
+                Field::Text(text) => match text.trim_end() {
+                    b"YES" => StressOutput::Yes,
+                    b"NO" => StressOutput::No,
+                    _ => return Err(Error::UnexpectedField("SO", field)),
+                },
+                _ => return Err(Error::UnexpectedField("SO", field)),
+            };
+            stations.push((x.float()?, so, station(&mut iter, n)?));
+        }
+        // Blanks at end B are the values at end A, and blanks in between are
+        // interpolated between the ends
+        let end_b = stations
+            .iter()
+            .rev()
+            .find(|s| s.0 == 1.0)
+            .map_or_else(|| end_a.clone(), |s| s.2.clone());
+        let mut dim = Vec::with_capacity(stations.len());
+        let mut sections = Vec::with_capacity(stations.len());
+        for (x, so, values) in stations {
+            let values = values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let a = end_a[i].unwrap_or(0.0);
+                    let b = end_b[i].unwrap_or(a);
+                    v.unwrap_or(a + x * (b - a))
+                })
+                .collect::<SmallVec<[f64; 7]>>();
+            let section = shape.section(&values[..n])?;
+            sections.push((x, so, section, values[n]));
+            dim.push(values[..n].iter().copied().collect());
+        }
+        let pbeam = PBEAM::from_sections(pid, mid, &sections);
+        let sections = sections.into_iter().map(|s| s.2).collect();
+        Ok(PBEAML {
+            group,
+            shape,
+            dim,
+            sections,
+            pbeam,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn test_sections() {
+        let bar = Shape::Bar.section(&[2., 3.]).unwrap();
+        assert!(close(bar.a, 6.));
+        assert!(close(bar.i1, 2. * 27. / 12.));
+        assert!(close(bar.i2, 3. * 8. / 12.));
+        assert!(close(bar.i12, 0.));
+        assert_eq!(bar.stress[..2], [1.5, 1.0]);
+
+        // A box is a bar with a smaller bar taken out of the middle
+        let boxed = Shape::Box.section(&[4., 6., 1., 0.5]).unwrap();
+        assert!(close(boxed.a, 24. - 3. * 4.));
+        assert!(close(boxed.i1, (4. * 216. - 3. * 64.) / 12.));
+        assert!(close(boxed.i2, (6. * 64. - 4. * 27.) / 12.));
+
+        // An I with equal flanges is symmetric about both axes
+        let i = Shape::I.section(&[10., 4., 4., 1., 1., 1.]).unwrap();
+        assert!(close(i.a, 16.));
+        assert!(close(i.i1, (4. * 1000. - 3. * 512.) / 12.));
+        assert!(close(i.i2, (2. * 64. + 8.) / 12.));
+        assert!(close(i.i12, 0.));
+        assert!(close(i.k1, 0.5));
+
+        let tube = Shape::Tube.section(&[2., 1.]).unwrap();
+        assert!(close(tube.a, 3. * PI));
+        assert!(close(tube.j, PI * 15. / 2.));
+
+        // An L isn't symmetric, so it has a product of inertia
+        let l = Shape::L.section(&[2., 2., 1., 1.]).unwrap();
+        assert!(close(l.a, 3.));
+        assert!(close(l.i12, -1. / 3.));
+
+        // The points of an I with unequal flanges are at the flange tips
+        let i = Shape::I.section(&[10., 4., 2., 1., 1., 1.]).unwrap();
+        let yc = (4. * 0.5 + 8. * 5. + 2. * 9.5) / 14.;
+        let points = [10. - yc, 1., -yc, 2., -yc, -2., 10. - yc, -1.];
+        assert!(i.stress.iter().zip(points).all(|(&s, p)| close(s, p)));
+
+        // and those of a T are at the flange tips and the bottom of the web
+        let t = Shape::T.section(&[4., 5., 1., 1.]).unwrap();
+        let yc = (4. * 4.5 + 4. * 2.) / 8.;
+        let points = [5. - yc, 2., -yc, 0.5, -yc, -0.5, 5. - yc, -2.];
+        assert!(t.stress.iter().zip(points).all(|(&s, p)| close(s, p)));
+
+        assert!(Shape::Box.section(&[4., 6., 3., 0.5]).is_err());
+        assert!(Shape::Rod.section(&[1., 2.]).is_err());
+        assert_eq!(Shape::from_name("CHAN"), Some(Shape::Chan));
+    }
+
+    #[test]
+    fn test_pbeaml() {
+        let input = b"CBEAM,1,1,1,2,0.,1.,0.
+GRID,1,,0.,0.,0.
+GRID,2,,2.,0.,0.
+PBEAML,1,1,,BAR
+,1.,1.,,YES,0.5,,,
+,NO,1.,1.,3.,2.
+MAT1,1,1.+7,,0.3,1.
+CBAR,2,2,1,2,0.,1.,0.
+PBARL,2,1,,ROD
+,1.
+";
+        let deck = crate::bdf::deck::Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let pbeaml = bulk.get_pbeaml(1).unwrap();
+        assert_eq!(pbeaml.shape(), Shape::Bar);
+        assert_eq!(pbeaml.group(), Field::Blank);
+        let dim: Vec<_> = pbeaml.dimensions().collect();
+        assert_eq!(dim, vec![&[1.0, 1.0][..], &[1.0, 2.0], &[1.0, 3.0]]);
+        let areas: Vec<_> = pbeaml.sections().map(|s| s.a).collect();
+        assert_eq!(areas, vec![1.0, 2.0, 3.0]);
+        let pbarl = bulk.get_pbarl(2).unwrap();
+        assert_eq!(pbarl.shape(), Shape::Rod);
+        assert_eq!(pbarl.dimensions(), &[1.0]);
+        assert!(close(pbarl.section().a, PI));
+        assert_eq!(pbarl.section(), Shape::Rod.section(&[1.0]).unwrap());
+        // The beam's area goes from 1 to 3 over a length of 2, and there's
+        // nonstructural mass of 2 at end B only. The bar is a rod of radius 1
+        let (mass, _) = deck.mass_cg(&deck.global_locations());
+        assert!(close(mass, 4. + 2. + 2. * PI), "{}", mass);
+    }
+}
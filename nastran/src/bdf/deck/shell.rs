@@ -0,0 +1,753 @@
+use std::convert::TryFrom;
+
+use smallvec::SmallVec;
+
+use super::{
+    BulkData, DeckRef, Element, GlobalLocation, HasMaterial, HasProperty, MassMoment, Material,
+    Property, StorageItem,
+};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+use crate::util::{Vec3, Xyz};
+
+/// How the material axes of a shell element are oriented
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaterialOrientation {
+    /// An angle in degrees from the G1-G2 edge
+    Theta(f64),
+    /// The x axis of a coordinate system projected onto the element
+    Mcid(u32),
+}
+
+impl Default for MaterialOrientation {
+    fn default() -> Self {
+        MaterialOrientation::Theta(0.0)
+    }
+}
+
+fn orientation(field: Option<Field>) -> Result<MaterialOrientation> {
+    match field.unwrap_or_default() {
+        Field::Blank => Ok(MaterialOrientation::default()),
+        Field::Int(_) | Field::IntOrId(_) => Ok(MaterialOrientation::Mcid(field.id()?)),
+        field => Ok(MaterialOrientation::Theta(field.float()?)),
+    }
+}
+
+/// What's needed to find the mass of a shell element
+pub(super) trait Shell: Element<Property = PSHELL> {
+    /// The corner grids, which define the surface of the element
+    fn corners(&self) -> &[u32];
+    /// The thickness at each corner, which is a multiple of the property
+    /// thickness when TFLAG is 1. Blank thicknesses are the property thickness
+    fn corner_thickness(&self) -> (i32, &[Option<f64>]);
+    fn zoffs(&self) -> f64;
+}
+
+#[derive(Debug, Clone)]
+pub struct CQUAD4 {
+    eid: u32,
+    pid: u32,
+    g: [u32; 4],
+    orientation: MaterialOrientation,
+    zoffs: f64,
+    tflag: i32,
+    t: [Option<f64>; 4],
+}
+
+impl CQUAD4 {
+    /// The THETA or MCID field, which orients the material axes
+    pub fn orientation(&self) -> MaterialOrientation {
+        self.orientation
+    }
+
+    /// Reads the fields, which are the same for CQUADR
+    fn decode(card: &BulkCard) -> Result<Self> {
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Int, Int, Int, Int, IntOrReal, Real, Any, Int, Real, Real, Real, Real,
+            ])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let g = [
+            iter.next().id()?,
+            iter.next().id()?,
+            iter.next().id()?,
+            iter.next().id()?,
+        ];
+        let orientation = orientation(iter.next())?;
+        let zoffs = iter.next().float_or(0.0)?;
+        iter.next();
+        let tflag = iter.next().int_or(0)?;
+        let t = [
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+        ];
+        Ok(CQUAD4 {
+            eid,
+            pid,
+            g,
+            orientation,
+            zoffs,
+            tflag,
+            t,
+        })
+    }
+}
+
+impl Element for CQUAD4 {
+    type Property = PSHELL;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Shell for CQUAD4 {
+    fn corners(&self) -> &[u32] {
+        &self.g
+    }
+
+    fn corner_thickness(&self) -> (i32, &[Option<f64>]) {
+        (self.tflag, &self.t)
+    }
+
+    fn zoffs(&self) -> f64 {
+        self.zoffs
+    }
+}
+
+impl StorageItem for CQUAD4 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CQUAD4 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CQUAD4 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CQUAD4 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CQUAD4 ", *b"       ")),
+        }
+        CQUAD4::decode(card)
+    }
+}
+
+/// A CQUAD4 with in-plane rotational stiffness. It has the same fields
+#[derive(Debug, Clone)]
+pub struct CQUADR(CQUAD4);
+
+impl std::ops::Deref for CQUADR {
+    type Target = CQUAD4;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Element for CQUADR {
+    type Property = PSHELL;
+
+    fn pid(&self) -> u32 {
+        self.0.pid()
+    }
+}
+
+impl Shell for CQUADR {
+    fn corners(&self) -> &[u32] {
+        self.0.corners()
+    }
+
+    fn corner_thickness(&self) -> (i32, &[Option<f64>]) {
+        self.0.corner_thickness()
+    }
+
+    fn zoffs(&self) -> f64 {
+        self.0.zoffs()
+    }
+}
+
+impl StorageItem for CQUADR {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.0.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CQUADR {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CQUADR ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CQUADR ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CQUADR ", *b"       ")),
+        }
+        Ok(CQUADR(CQUAD4::decode(card)?))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CTRIA3 {
+    eid: u32,
+    pid: u32,
+    g: [u32; 3],
+    orientation: MaterialOrientation,
+    zoffs: f64,
+    tflag: i32,
+    t: [Option<f64>; 3],
+}
+
+impl CTRIA3 {
+    /// The THETA or MCID field, which orients the material axes
+    pub fn orientation(&self) -> MaterialOrientation {
+        self.orientation
+    }
+
+    /// Reads the fields, which are the same for CTRIAR
+    fn decode(card: &BulkCard) -> Result<Self> {
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Int, Int, Int, IntOrReal, Real, Any, Any, Int, Real, Real, Real,
+            ])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let g = [iter.next().id()?, iter.next().id()?, iter.next().id()?];
+        let orientation = orientation(iter.next())?;
+        let zoffs = iter.next().float_or(0.0)?;
+        iter.next();
+        iter.next();
+        let tflag = iter.next().int_or(0)?;
+        let t = [
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+        ];
+        Ok(CTRIA3 {
+            eid,
+            pid,
+            g,
+            orientation,
+            zoffs,
+            tflag,
+            t,
+        })
+    }
+}
+
+impl Element for CTRIA3 {
+    type Property = PSHELL;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Shell for CTRIA3 {
+    fn corners(&self) -> &[u32] {
+        &self.g
+    }
+
+    fn corner_thickness(&self) -> (i32, &[Option<f64>]) {
+        (self.tflag, &self.t)
+    }
+
+    fn zoffs(&self) -> f64 {
+        self.zoffs
+    }
+}
+
+impl StorageItem for CTRIA3 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CTRIA3 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CTRIA3 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CTRIA3 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CTRIA3 ", *b"       ")),
+        }
+        CTRIA3::decode(card)
+    }
+}
+
+/// A CTRIA3 with in-plane rotational stiffness. It has the same fields
+#[derive(Debug, Clone)]
+pub struct CTRIAR(CTRIA3);
+
+impl std::ops::Deref for CTRIAR {
+    type Target = CTRIA3;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Element for CTRIAR {
+    type Property = PSHELL;
+
+    fn pid(&self) -> u32 {
+        self.0.pid()
+    }
+}
+
+impl Shell for CTRIAR {
+    fn corners(&self) -> &[u32] {
+        self.0.corners()
+    }
+
+    fn corner_thickness(&self) -> (i32, &[Option<f64>]) {
+        self.0.corner_thickness()
+    }
+
+    fn zoffs(&self) -> f64 {
+        self.0.zoffs()
+    }
+}
+
+impl StorageItem for CTRIAR {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.0.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CTRIAR {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CTRIAR ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CTRIAR ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CTRIAR ", *b"       ")),
+        }
+        Ok(CTRIAR(CTRIA3::decode(card)?))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CQUAD8 {
+    eid: u32,
+    pid: u32,
+    /// The corner grids followed by the midside grids, which are 0 when
+    /// they're left out
+    g: [u32; 8],
+    orientation: MaterialOrientation,
+    zoffs: f64,
+    tflag: i32,
+    t: [Option<f64>; 4],
+}
+
+impl CQUAD8 {
+    /// The THETA or MCID field, which orients the material axes
+    pub fn orientation(&self) -> MaterialOrientation {
+        self.orientation
+    }
+}
+
+impl Element for CQUAD8 {
+    type Property = PSHELL;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Shell for CQUAD8 {
+    fn corners(&self) -> &[u32] {
+        &self.g[..4]
+    }
+
+    fn corner_thickness(&self) -> (i32, &[Option<f64>]) {
+        (self.tflag, &self.t)
+    }
+
+    fn zoffs(&self) -> f64 {
+        self.zoffs
+    }
+}
+
+impl StorageItem for CQUAD8 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CQUAD8 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CQUAD8 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CQUAD8 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CQUAD8 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Int, Int, Int, Int, Int, Int, Int, Int, Real, Real, Real, Real,
+                IntOrReal, Real, Int,
+            ])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let mut g = [0; 8];
+        for (i, g) in g.iter_mut().enumerate() {
+            *g = if i < 4 {
+                iter.next().id()?
+            } else {
+                iter.next().id_or(0)?
+            };
+        }
+        let t = [
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+        ];
+        let orientation = orientation(iter.next())?;
+        let zoffs = iter.next().float_or(0.0)?;
+        let tflag = iter.next().int_or(0)?;
+        Ok(CQUAD8 {
+            eid,
+            pid,
+            g,
+            orientation,
+            zoffs,
+            tflag,
+            t,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CTRIA6 {
+    eid: u32,
+    pid: u32,
+    /// The corner grids followed by the midside grids, which are 0 when
+    /// they're left out
+    g: [u32; 6],
+    orientation: MaterialOrientation,
+    zoffs: f64,
+    tflag: i32,
+    t: [Option<f64>; 3],
+}
+
+impl CTRIA6 {
+    /// The THETA or MCID field, which orients the material axes
+    pub fn orientation(&self) -> MaterialOrientation {
+        self.orientation
+    }
+}
+
+impl Element for CTRIA6 {
+    type Property = PSHELL;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Shell for CTRIA6 {
+    fn corners(&self) -> &[u32] {
+        &self.g[..3]
+    }
+
+    fn corner_thickness(&self) -> (i32, &[Option<f64>]) {
+        (self.tflag, &self.t)
+    }
+
+    fn zoffs(&self) -> f64 {
+        self.zoffs
+    }
+}
+
+impl StorageItem for CTRIA6 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CTRIA6 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CTRIA6 ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CTRIA6 ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CTRIA6 ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[
+                Int, Int, Int, Int, Int, Int, Int, Int, IntOrReal, Real, Real, Real, Real, Int,
+            ])?
+            .into_iter();
+        let eid = iter.next().id()?;
+        let pid = iter.next().id_or(eid)?;
+        let mut g = [0; 6];
+        for (i, g) in g.iter_mut().enumerate() {
+            *g = if i < 3 {
+                iter.next().id()?
+            } else {
+                iter.next().id_or(0)?
+            };
+        }
+        let orientation = orientation(iter.next())?;
+        let zoffs = iter.next().float_or(0.0)?;
+        let t = [
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+            iter.next().maybe_float()?,
+        ];
+        let tflag = iter.next().int_or(0)?;
+        Ok(CTRIA6 {
+            eid,
+            pid,
+            g,
+            orientation,
+            zoffs,
+            tflag,
+            t,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PSHELL {
+    pid: u32,
+    /// Membrane material, 0 if blank
+    mid1: u32,
+    t: Option<f64>,
+    /// Bending material, 0 if blank or -1 for plane strain
+    mid2: i32,
+    /// The bending moment of inertia ratio 12I/T³
+    bending: f64,
+    /// Transverse shear material, 0 if blank
+    mid3: u32,
+    /// The transverse shear thickness ratio TS/T
+    shear: f64,
+    nsm: f64,
+    z1: Option<f64>,
+    z2: Option<f64>,
+    /// Membrane-bending coupling material, 0 if blank
+    mid4: u32,
+}
+
+impl PSHELL {
+    /// The bending moment of inertia ratio 12I/T³, 1.0 if blank
+    pub fn bending(&self) -> f64 {
+        self.bending
+    }
+
+    /// The transverse shear material
+    pub fn mid3(&self) -> Option<u32> {
+        Some(self.mid3).filter(|&mid| mid > 0)
+    }
+
+    /// The transverse shear thickness ratio TS/T, 0.833333 if blank
+    pub fn shear(&self) -> f64 {
+        self.shear
+    }
+
+    /// The fiber distance for stresses at the bottom surface, -T/2 if blank
+    pub fn z1(&self) -> Option<f64> {
+        self.z1.or_else(|| self.t.map(|t| -t / 2.))
+    }
+
+    /// The fiber distance for stresses at the top surface, T/2 if blank
+    pub fn z2(&self) -> Option<f64> {
+        self.z2.or_else(|| self.t.map(|t| t / 2.))
+    }
+
+    /// The membrane-bending coupling material
+    pub fn mid4(&self) -> Option<u32> {
+        Some(self.mid4).filter(|&mid| mid > 0)
+    }
+}
+
+impl StorageItem for PSHELL {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.pid
+    }
+}
+
+impl TryFrom<&BulkCard> for PSHELL {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"PSHELL ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"PSHELL ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"PSHELL ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Int, Int, Real, Int, Real, Int, Real, Real, Real, Real, Int])?
+            .into_iter();
+        let pid = iter.next().id()?;
+        let mid1 = iter.next().id_or(0)?;
+        let t = iter.next().maybe_float()?;
+        let mid2 = iter.next().int_or(0)?;
+        let bending = iter.next().float_or(1.0)?;
+        let mid3 = iter.next().id_or(0)?;
+        let shear = iter.next().float_or(0.833333)?;
+        let nsm = iter.next().float_or(0.0)?;
+        let z1 = iter.next().maybe_float()?;
+        let z2 = iter.next().maybe_float()?;
+        let mid4 = iter.next().id_or(0)?;
+        Ok(PSHELL {
+            pid,
+            mid1,
+            t,
+            mid2,
+            bending,
+            mid3,
+            shear,
+            nsm,
+            z1,
+            z2,
+            mid4,
+        })
+    }
+}
+
+impl Property for PSHELL {
+    fn get(bulk: &BulkData, id: u32) -> Option<&Self> {
+        bulk.pshell.get(id)
+    }
+}
+
+impl<'a> HasMaterial<'a> for DeckRef<'a, PSHELL> {
+    /// The membrane material, or the bending material if there isn't one,
+    /// which is the material NASTRAN takes the density from
+    fn material(&self) -> Option<Material<'_>> {
+        if self.item.mid1 > 0 {
+            self.bulk.get_material(self.item.mid1)
+        } else if self.item.mid2 > 0 {
+            self.bulk.get_material(self.item.mid2 as u32)
+        } else {
+            None
+        }
+    }
+}
+
+/// The mass of a shell element from the density and thickness along with the
+/// non-structural mass, or from the plies of a PCOMP or PCOMPG. Midside grids
+/// are ignored, so edges are taken to be straight, and quadrilaterals are
+/// split into two triangles
+pub(super) fn mass_moment<T: Shell>(
+    element: &DeckRef<'_, T>,
+    location: &GlobalLocation,
+) -> Option<MassMoment> {
+    // A laminate has the same mass per area everywhere, whatever the
+    // thickness given on the element
+    let (rho, base, nsm) = match element.property() {
+        Some(property) => (
+            property.density().unwrap_or(0.0),
+            property.t.unwrap_or(0.0),
+            property.nsm,
+        ),
+        None => (0.0, 0.0, element.bulk.areal_mass(element.pid())?),
+    };
+    let xyz = element
+        .corners()
+        .iter()
+        .map(|&g| location.get_grid(g).map(Xyz::to_vec))
+        .collect::<Option<SmallVec<[Vec3; 4]>>>()?;
+    let (tflag, corner_thickness) = element.corner_thickness();
+    let t = corner_thickness
+        .iter()
+        .map(|t| match (tflag, t) {
+            (1, Some(t)) => t * base,
+            (_, Some(t)) => *t,
+            (_, None) => base,
+        })
+        .collect::<SmallVec<[f64; 4]>>();
+    let triangles: &[[usize; 3]] = if xyz.len() == 4 {
+        &[[0, 1, 2], [0, 2, 3]]
+    } else {
+        &[[0, 1, 2]]
+    };
+    let mut mm = MassMoment::default();
+    let mut normal = Vec3::default();
+    for &[i, j, k] in triangles {
+        let n = (xyz[j] - xyz[i]).cross(xyz[k] - xyz[i]);
+        let area = n.length() / 2.;
+        normal += n;
+        // The thickness varies linearly over each triangle
+        let sum_t = t[i] + t[j] + t[k];
+        let sum_xyz = xyz[i] + xyz[j] + xyz[k];
+        let weighted = t[i] * xyz[i] + t[j] * xyz[j] + t[k] * xyz[k];
+        mm.mass += area * (rho * sum_t / 3. + nsm);
+        mm.moment += area * (rho / 12. * (weighted + sum_t * sum_xyz) + nsm / 3. * sum_xyz);
+    }
+    let offset = element.zoffs();
+    if offset != 0.0 && normal.length() > 0.0 {
+        mm.moment += mm.mass * offset * normal.normalize();
+    }
+    Some(mm)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::deck::Deck;
+
+    #[test]
+    fn test_shell_mass() {
+        let input = b"GRID,1,,0.,0.,0.
+GRID,2,,2.,0.,0.
+GRID,3,,2.,1.,0.
+GRID,4,,0.,1.,0.
+GRID,5,,4.,0.,0.
+CQUAD4,1,1,1,2,3,4,30.,0.5
+CTRIA3,2,2,2,5,3,9
+,,1,2.,1.,1.
+PSHELL,1,1,0.1,1,,,,0.5
+PSHELL,2,1,0.1,1,0.5,1,0.75,,,-0.02
+MAT1,1,1.+7,,0.3,10.
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        let (mass, cg) = deck.mass_cg(&locations);
+        // The quad has an area of 2 and the triangle an area of 1 with its
+        // thickness doubled at G2. Fields are single precision
+        let quad = 2. * (10. * 0.1 + 0.5);
+        let tria = 10. * 0.1 * 4. / 3.;
+        assert!((mass - (quad + tria)).abs() < 1e-6);
+        let tria_moment = 10. / 12. * (0.2 * 2. + 0.1 * 4. + 0.1 * 2. + 0.4 * 8.);
+        assert!((cg.x() - (quad * 1. + tria_moment) / mass).abs() < 1e-6);
+        assert!((cg.z() - quad * 0.5 / mass).abs() < 1e-6);
+
+        let bulk = deck.main();
+        let quad = bulk.get_cquad4(1).unwrap();
+        assert_eq!(quad.orientation(), MaterialOrientation::Theta(30.));
+        let tria = bulk.get_ctria3(2).unwrap();
+        assert_eq!(tria.orientation(), MaterialOrientation::Mcid(9));
+        let pshell = bulk.get_pshell(2).unwrap();
+        assert_eq!((pshell.bending(), pshell.shear()), (0.5, 0.75));
+        assert_eq!((pshell.mid3(), pshell.mid4()), (Some(1), None));
+        assert!((pshell.z1().unwrap() + 0.02).abs() < 1e-6);
+        assert!((pshell.z2().unwrap() - 0.05).abs() < 1e-6);
+        assert_eq!(bulk.get_pshell(1).unwrap().mid3(), None);
+    }
+}
@@ -0,0 +1,701 @@
+use std::convert::TryFrom;
+
+use smallvec::SmallVec;
+
+use super::{
+    DeckRef, Element, GlobalLocation, HasMaterial, HasProperty, MassMoment, StorageItem, PSOLID,
+};
+use crate::bdf::parser::{BulkCard, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+use crate::util::{Vec3, Xyz};
+
+/// The shape of a solid element, which sets the order of its grids
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Topology {
+    Tetra,
+    Penta,
+    Pyram,
+    Hexa,
+}
+
+impl Topology {
+    /// The corner grids of each edge in the order of the midside grids
+    fn edges(self) -> &'static [(usize, usize)] {
+        match self {
+            Topology::Tetra => &[(0, 1), (1, 2), (2, 0), (0, 3), (1, 3), (2, 3)],
+            Topology::Penta => &[
+                (0, 1),
+                (1, 2),
+                (2, 0),
+                (0, 3),
+                (1, 4),
+                (2, 5),
+                (3, 4),
+                (4, 5),
+                (5, 3),
+            ],
+            Topology::Pyram => &[
+                (0, 1),
+                (1, 2),
+                (2, 3),
+                (3, 0),
+                (0, 4),
+                (1, 4),
+                (2, 4),
+                (3, 4),
+            ],
+            Topology::Hexa => &[
+                (0, 1),
+                (1, 2),
+                (2, 3),
+                (3, 0),
+                (0, 4),
+                (1, 5),
+                (2, 6),
+                (3, 7),
+                (4, 5),
+                (5, 6),
+                (6, 7),
+                (7, 4),
+            ],
+        }
+    }
+
+    pub(super) fn corners(self) -> usize {
+        match self {
+            Topology::Tetra => 4,
+            Topology::Penta => 6,
+            Topology::Pyram => 5,
+            Topology::Hexa => 8,
+        }
+    }
+
+    /// The corners of each face in order around it
+    fn faces(self) -> &'static [&'static [usize]] {
+        match self {
+            Topology::Tetra => &[&[0, 1, 2], &[0, 1, 3], &[1, 2, 3], &[0, 2, 3]],
+            Topology::Penta => &[
+                &[0, 1, 2],
+                &[3, 4, 5],
+                &[0, 1, 4, 3],
+                &[1, 2, 5, 4],
+                &[2, 0, 3, 5],
+            ],
+            Topology::Pyram => &[
+                &[0, 1, 2, 3],
+                &[0, 1, 4],
+                &[1, 2, 4],
+                &[2, 3, 4],
+                &[3, 0, 4],
+            ],
+            Topology::Hexa => &[
+                &[0, 1, 2, 3],
+                &[4, 5, 6, 7],
+                &[0, 1, 5, 4],
+                &[1, 2, 6, 5],
+                &[2, 3, 7, 6],
+                &[3, 0, 4, 7],
+            ],
+        }
+    }
+}
+
+/// What's needed to find the mass of a solid element
+pub(super) trait Solid: Element<Property = PSOLID> {
+    const TOPOLOGY: Topology;
+    /// The corner grids followed by the midside grids, which are 0 when
+    /// they're left out
+    fn grids(&self) -> &[u32];
+}
+
+/// The corner grids of a face picked the way PLOAD4 does, in order around
+/// the face starting from `g1`. On a quadrilateral face `g34` is the corner
+/// diagonally opposite `g1`, on a CTETRA it is the corner that isn't on the
+/// face, and it is 0 to pick a triangular face of a CPENTA
+pub(super) fn face<T: Solid>(element: &T, g1: u32, g34: u32) -> Option<SmallVec<[u32; 4]>> {
+    let grids = &element.grids()[..T::TOPOLOGY.corners()];
+    let position = |g| grids.iter().position(|&x| x == g);
+    let i1 = position(g1)?;
+    let i34 = match g34 {
+        0 => None,
+        g => Some(position(g)?),
+    };
+    T::TOPOLOGY.faces().iter().find_map(|face| {
+        let n = face.len();
+        let k = face.iter().position(|&c| c == i1)?;
+        let matches = match (T::TOPOLOGY, i34) {
+            (Topology::Tetra, Some(j)) => !face.contains(&j),
+            (_, Some(j)) if n == 4 => face[(k + 2) % 4] == j,
+            (_, Some(j)) => face.contains(&j),
+            (_, None) => n == 3,
+        };
+        matches.then(|| (0..n).map(|m| grids[face[(k + m) % n]]).collect())
+    })
+}
+
+/// Reads a solid element with `n` grids, of which the first `corners` are
+/// required
+fn decode_solid<const N: usize>(card: &BulkCard, corners: usize) -> Result<(u32, u32, [u32; N])> {
+    let schema = [FieldKind::Int; 22];
+    let mut iter = card.decode(&schema[..N + 2])?.into_iter();
+    let eid = iter.next().id()?;
+    let pid = iter.next().id()?;
+    let mut g = [0; N];
+    for (i, g) in g.iter_mut().enumerate() {
+        *g = if i < corners {
+            iter.next().id()?
+        } else {
+            iter.next().id_or(0)?
+        };
+    }
+    Ok((eid, pid, g))
+}
+
+#[derive(Debug, Clone)]
+pub struct CTETRA {
+    eid: u32,
+    pid: u32,
+    g: [u32; 10],
+}
+
+impl Element for CTETRA {
+    type Property = PSOLID;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Solid for CTETRA {
+    const TOPOLOGY: Topology = Topology::Tetra;
+
+    fn grids(&self) -> &[u32] {
+        &self.g
+    }
+}
+
+impl StorageItem for CTETRA {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CTETRA {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CTETRA ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CTETRA ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CTETRA ", *b"       ")),
+        }
+        let (eid, pid, g) = decode_solid(card, 4)?;
+        Ok(CTETRA { eid, pid, g })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CPENTA {
+    eid: u32,
+    pid: u32,
+    g: [u32; 15],
+}
+
+impl Element for CPENTA {
+    type Property = PSOLID;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Solid for CPENTA {
+    const TOPOLOGY: Topology = Topology::Penta;
+
+    fn grids(&self) -> &[u32] {
+        &self.g
+    }
+}
+
+impl StorageItem for CPENTA {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CPENTA {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CPENTA ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CPENTA ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CPENTA ", *b"       ")),
+        }
+        let (eid, pid, g) = decode_solid(card, 6)?;
+        Ok(CPENTA { eid, pid, g })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CPYRAM {
+    eid: u32,
+    pid: u32,
+    g: [u32; 13],
+}
+
+impl Element for CPYRAM {
+    type Property = PSOLID;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Solid for CPYRAM {
+    const TOPOLOGY: Topology = Topology::Pyram;
+
+    fn grids(&self) -> &[u32] {
+        &self.g
+    }
+}
+
+impl StorageItem for CPYRAM {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CPYRAM {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CPYRAM ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CPYRAM ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CPYRAM ", *b"       ")),
+        }
+        let (eid, pid, g) = decode_solid(card, 5)?;
+        Ok(CPYRAM { eid, pid, g })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CHEXA {
+    eid: u32,
+    pid: u32,
+    g: [u32; 20],
+}
+
+impl Element for CHEXA {
+    type Property = PSOLID;
+
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+impl Solid for CHEXA {
+    const TOPOLOGY: Topology = Topology::Hexa;
+
+    fn grids(&self) -> &[u32] {
+        &self.g
+    }
+}
+
+impl StorageItem for CHEXA {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.eid
+    }
+}
+
+impl TryFrom<&BulkCard> for CHEXA {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"CHEXA  ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"CHEXA  ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"CHEXA  ", *b"       ")),
+        }
+        let (eid, pid, g) = decode_solid(card, 8)?;
+        Ok(CHEXA { eid, pid, g })
+    }
+}
+
+impl<T: Solid> DeckRef<'_, T> {
+    /// The volume and centroid. Elements are linear unless at least one
+    /// midside grid is given, in which case the others are placed at the
+    /// middle of their edges
+    pub(super) fn volume_cg(&self, location: &GlobalLocation) -> Option<(f64, Vec3)> {
+        let topology = T::TOPOLOGY;
+        let grids = self.grids();
+        let corners = topology.corners();
+        let grid = |id| location.get_grid(id).map(Xyz::to_vec);
+        let mut xyz = grids[..corners]
+            .iter()
+            .map(|&id| grid(id))
+            .collect::<Option<SmallVec<[Vec3; 20]>>>()?;
+        let quadratic = grids[corners..].iter().any(|&id| id != 0);
+        if quadratic {
+            for (&id, &(a, b)) in grids[corners..].iter().zip(topology.edges()) {
+                let midside = match id {
+                    0 => (xyz[a] + xyz[b]) / 2.,
+                    id => grid(id)?,
+                };
+                xyz.push(midside);
+            }
+        }
+        Some(integrate(topology, &xyz))
+    }
+}
+
+/// The mass of a solid element from its density and volume
+pub(super) fn mass_moment<T: Solid>(
+    element: &DeckRef<'_, T>,
+    location: &GlobalLocation,
+) -> Option<MassMoment> {
+    let density = element.property()?.density()?;
+    let (volume, cg) = element.volume_cg(location)?;
+    let mass = density * volume;
+    Some(MassMoment {
+        mass,
+        moment: mass * cg,
+    })
+}
+
+/// Gauss-Legendre points and weights on [-1, 1]
+const GAUSS_2: [(f64, f64); 2] = [(-0.5773502691896257, 1.0), (0.5773502691896257, 1.0)];
+const GAUSS_4: [(f64, f64); 4] = [
+    (-0.8611363115940526, 0.3478548451374538),
+    (-0.3399810435848563, 0.6521451548625461),
+    (0.3399810435848563, 0.6521451548625461),
+    (0.8611363115940526, 0.3478548451374538),
+];
+
+/// The hexahedron grids that a pyramid's grids are collapsed onto, for
+/// the linear and quadratic pyramids
+const PYRAM_AS_HEXA: [usize; 20] = [
+    0, 1, 2, 3, 4, 4, 4, 4, 5, 6, 7, 8, 9, 10, 11, 12, 4, 4, 4, 4,
+];
+
+/// Calls `f` with each integration point in the natural coordinates of the
+/// element and its weight. Triangles and tetrahedra are integrated by
+/// collapsing a square or cube onto them
+fn for_each_point(topology: Topology, quadratic: bool, mut f: impl FnMut([f64; 3], f64)) {
+    let rule: &[(f64, f64)] = if quadratic { &GAUSS_4 } else { &GAUSS_2 };
+    // The same points on [0, 1]
+    let unit = |&(x, w): &(f64, f64)| ((1. + x) / 2., w / 2.);
+    for a in rule {
+        for b in rule {
+            for c in rule {
+                match topology {
+                    Topology::Hexa | Topology::Pyram => f([a.0, b.0, c.0], a.1 * b.1 * c.1),
+                    Topology::Penta => {
+                        let ((u, wu), (v, wv)) = (unit(a), unit(b));
+                        let point = [u, v * (1. - u), c.0];
+                        f(point, wu * wv * c.1 * (1. - u))
+                    }
+                    Topology::Tetra => {
+                        let ((u, wu), (v, wv), (w, ww)) = (unit(a), unit(b), unit(c));
+                        let point = [u, v * (1. - u), w * (1. - u) * (1. - v)];
+                        let jacobian = (1. - u) * (1. - u) * (1. - v);
+                        f(point, wu * wv * ww * jacobian)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The volume and centroid from integrating over the element
+fn integrate(topology: Topology, xyz: &[Vec3]) -> (f64, Vec3) {
+    let quadratic = xyz.len() > topology.corners();
+    let mut n = [0.0; 20];
+    let mut dn = [[0.0; 3]; 20];
+    let mut volume = 0.0;
+    let mut moment = Vec3::default();
+    for_each_point(topology, quadratic, |point, weight| {
+        let count = shape(topology, quadratic, point, &mut n, &mut dn);
+        let mut x = Vec3::default();
+        let mut jacobian = [Vec3::default(); 3];
+        for i in 0..count {
+            x += n[i] * xyz[i];
+            for (j, d) in jacobian.iter_mut().enumerate() {
+                *d += dn[i][j] * xyz[i];
+            }
+        }
+        let det = jacobian[0].cross(jacobian[1]).dot(jacobian[2]) * weight;
+        volume += det;
+        moment += det * x;
+    });
+    // Grids numbered the other way around give a negative volume
+    if volume < 0.0 {
+        volume = -volume;
+        moment = -1.0 * moment;
+    }
+    (volume, moment / volume)
+}
+
+/// Evaluates the shape functions and their derivatives with respect to the
+/// natural coordinates, returning how many there are
+fn shape(
+    topology: Topology,
+    quadratic: bool,
+    [r, s, t]: [f64; 3],
+    n: &mut [f64; 20],
+    dn: &mut [[f64; 3]; 20],
+) -> usize {
+    match topology {
+        Topology::Tetra => {
+            let l = [1. - r - s - t, r, s, t];
+            let dl = [[-1., -1., -1.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+            if !quadratic {
+                n[..4].copy_from_slice(&l);
+                dn[..4].copy_from_slice(&dl);
+                return 4;
+            }
+            for i in 0..4 {
+                n[i] = l[i] * (2. * l[i] - 1.);
+                dn[i] = dl[i].map(|d| (4. * l[i] - 1.) * d);
+            }
+            for (i, &(a, b)) in Topology::Tetra.edges().iter().enumerate() {
+                n[4 + i] = 4. * l[a] * l[b];
+                for j in 0..3 {
+                    dn[4 + i][j] = 4. * (l[a] * dl[b][j] + l[b] * dl[a][j]);
+                }
+            }
+            10
+        }
+        Topology::Penta => {
+            let l = [1. - r - s, r, s];
+            let dl = [[-1., -1.], [1., 0.], [0., 1.]];
+            for (i, z) in [(0, -1.0), (3, 1.0)] {
+                for k in 0..3 {
+                    // Linear in the triangle times linear through the thickness
+                    let h = (1. + z * t) / 2.;
+                    let (n, dn) = (&mut n[i + k], &mut dn[i + k]);
+                    if quadratic {
+                        *n = l[k] * ((2. * l[k] - 1.) * (1. + z * t) - (1. - t * t)) / 2.;
+                        let dl_term = ((4. * l[k] - 1.) * (1. + z * t) - (1. - t * t)) / 2.;
+                        *dn = [
+                            dl_term * dl[k][0],
+                            dl_term * dl[k][1],
+                            l[k] * ((2. * l[k] - 1.) * z + 2. * t) / 2.,
+                        ];
+                    } else {
+                        *n = l[k] * h;
+                        *dn = [dl[k][0] * h, dl[k][1] * h, l[k] * z / 2.];
+                    }
+                }
+            }
+            if !quadratic {
+                return 6;
+            }
+            for (i, &(a, b)) in Topology::Penta.edges().iter().enumerate() {
+                let (a, b) = (a % 3, b % 3);
+                let (value, derivative) = if a == b {
+                    // Through the thickness at the middle
+                    let value = l[a] * (1. - t * t);
+                    let d = [
+                        dl[a][0] * (1. - t * t),
+                        dl[a][1] * (1. - t * t),
+                        -2. * l[a] * t,
+                    ];
+                    (value, d)
+                } else {
+                    let z = if i < 3 { -1.0 } else { 1.0 };
+                    let h = 1. + z * t;
+                    let value = 2. * l[a] * l[b] * h;
+                    let d = [
+                        2. * (dl[a][0] * l[b] + l[a] * dl[b][0]) * h,
+                        2. * (dl[a][1] * l[b] + l[a] * dl[b][1]) * h,
+                        2. * l[a] * l[b] * z,
+                    ];
+                    (value, d)
+                };
+                n[6 + i] = value;
+                dn[6 + i] = derivative;
+            }
+            15
+        }
+        Topology::Hexa => hexa_shape(quadratic, [r, s, t], n, dn),
+        Topology::Pyram => {
+            // A hexahedron with its top face collapsed onto the apex
+            let mut hn = [0.0; 20];
+            let mut hdn = [[0.0; 3]; 20];
+            let count = hexa_shape(quadratic, [r, s, t], &mut hn, &mut hdn);
+            let grids = if quadratic { 13 } else { 5 };
+            n[..grids].iter_mut().for_each(|n| *n = 0.0);
+            dn[..grids].iter_mut().for_each(|d| *d = [0.0; 3]);
+            for i in 0..count {
+                let p = if quadratic {
+                    PYRAM_AS_HEXA[i]
+                } else {
+                    PYRAM_AS_HEXA[i].min(4)
+                };
+                n[p] += hn[i];
+                for j in 0..3 {
+                    dn[p][j] += hdn[i][j];
+                }
+            }
+            grids
+        }
+    }
+}
+
+/// The natural coordinates of the corners of a hexahedron
+const HEXA_CORNERS: [[f64; 3]; 8] = [
+    [-1., -1., -1.],
+    [1., -1., -1.],
+    [1., 1., -1.],
+    [-1., 1., -1.],
+    [-1., -1., 1.],
+    [1., -1., 1.],
+    [1., 1., 1.],
+    [-1., 1., 1.],
+];
+
+fn hexa_shape(quadratic: bool, x: [f64; 3], n: &mut [f64; 20], dn: &mut [[f64; 3]; 20]) -> usize {
+    for (i, c) in HEXA_CORNERS.iter().enumerate() {
+        let f = [0, 1, 2].map(|j| 1. + c[j] * x[j]);
+        let product = f[0] * f[1] * f[2];
+        if quadratic {
+            let sum = c[0] * x[0] + c[1] * x[1] + c[2] * x[2] - 2.;
+            n[i] = product * sum / 8.;
+            for j in 0..3 {
+                let others = product / f[j];
+                dn[i][j] = c[j] * others * (sum + f[j]) / 8.;
+            }
+        } else {
+            n[i] = product / 8.;
+            for j in 0..3 {
+                dn[i][j] = c[j] * product / f[j] / 8.;
+            }
+        }
+    }
+    if !quadratic {
+        return 8;
+    }
+    for (i, &(a, b)) in Topology::Hexa.edges().iter().enumerate() {
+        // The midside grid has a zero coordinate along its edge
+        let c = [0, 1, 2].map(|j| (HEXA_CORNERS[a][j] + HEXA_CORNERS[b][j]) / 2.);
+        let along = (0..3).find(|&j| c[j] == 0.0).unwrap_or(0);
+        let f = [0, 1, 2].map(|j| {
+            if j == along {
+                1. - x[j] * x[j]
+            } else {
+                1. + c[j] * x[j]
+            }
+        });
+        n[8 + i] = f[0] * f[1] * f[2] / 4.;
+        for j in 0..3 {
+            let others: f64 = (0..3).filter(|&k| k != j).map(|k| f[k]).product();
+            let d = if j == along { -2. * x[j] } else { c[j] };
+            dn[8 + i][j] = d * others / 4.;
+        }
+    }
+    20
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unit_cube() -> Vec<Vec3> {
+        HEXA_CORNERS
+            .iter()
+            .map(|c| Vec3::new((c[0] + 1.) / 2., (c[1] + 1.) / 2., (c[2] + 1.) / 2.))
+            .collect()
+    }
+
+    fn with_midsides(topology: Topology, corners: &[Vec3]) -> Vec<Vec3> {
+        let mut xyz = corners.to_vec();
+        for &(a, b) in topology.edges() {
+            xyz.push((corners[a] + corners[b]) / 2.);
+        }
+        xyz
+    }
+
+    fn check(topology: Topology, corners: &[Vec3], volume: f64, cg: Vec3) {
+        for xyz in [corners.to_vec(), with_midsides(topology, corners)] {
+            let (v, c) = integrate(topology, &xyz);
+            assert!(
+                (v - volume).abs() < 1e-12,
+                "{:?} {} {}",
+                topology,
+                v,
+                volume
+            );
+            assert!((c - cg).length() < 1e-12, "{:?} {:?} {:?}", topology, c, cg);
+        }
+    }
+
+    #[test]
+    fn test_straight_sided() {
+        let cube = unit_cube();
+        check(Topology::Hexa, &cube, 1.0, Vec3::new(0.5, 0.5, 0.5));
+        let tetra = [cube[0], cube[1], cube[3], cube[4]];
+        check(
+            Topology::Tetra,
+            &tetra,
+            1. / 6.,
+            Vec3::new(0.25, 0.25, 0.25),
+        );
+        let penta = [cube[0], cube[1], cube[3], cube[4], cube[5], cube[7]];
+        check(
+            Topology::Penta,
+            &penta,
+            0.5,
+            Vec3::new(1. / 3., 1. / 3., 0.5),
+        );
+        let apex = Vec3::new(0.5, 0.5, 1.0);
+        let pyram = [cube[0], cube[1], cube[2], cube[3], apex];
+        check(Topology::Pyram, &pyram, 1. / 3., Vec3::new(0.5, 0.5, 0.25));
+    }
+
+    #[test]
+    fn test_curved() {
+        // A tetrahedron with the midside grid of one edge pushed outwards
+        let cube = unit_cube();
+        let tetra = [cube[0], cube[1], cube[3], cube[4]];
+        let mut xyz = with_midsides(Topology::Tetra, &tetra);
+        let (flat, _) = integrate(Topology::Tetra, &xyz);
+        xyz[5] += Vec3::new(0.1, 0.1, 0.0);
+        let (curved, _) = integrate(Topology::Tetra, &xyz);
+        assert!(curved > flat);
+        let (linear, _) = integrate(Topology::Tetra, &tetra);
+        assert!((flat - linear).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_solid_mass() {
+        let input = b"GRID,1,,0.,0.,0.
+GRID,2,,1.,0.,0.
+GRID,3,,1.,1.,0.
+GRID,4,,0.,1.,0.
+GRID,5,,0.,0.,1.
+GRID,6,,1.,0.,1.
+GRID,7,,1.,1.,1.
+GRID,8,,0.,1.,1.
+GRID,9,,0.5,0.5,2.
+CHEXA,1,1,1,2,3,4,5,6,
+,7,8
+CPYRAM,2,2,5,6,7,8,9
+PSOLID,1,1
+PSOLID,2,2
+MAT1,1,1.+7,,0.3,2.
+MAT1,2,1.+7,,0.3,3.
+";
+        let deck = crate::bdf::deck::Deck::from_buffer(input).unwrap();
+        let locations = deck.global_locations();
+        let (mass, cg) = deck.mass_cg(&locations);
+        // A cube of mass 2 at z = 0.5 and a pyramid of mass 1 at z = 1.25
+        assert!((mass - 3.0).abs() < 1e-9, "{}", mass);
+        assert!((cg - Vec3::new(0.5, 0.5, 0.75)).length() < 1e-9, "{:?}", cg);
+    }
+}
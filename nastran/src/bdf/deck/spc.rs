@@ -0,0 +1,278 @@
+use std::convert::TryFrom;
+
+use super::{id_list, SetItem, StorageItem};
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+/// A grid, some of its components and a value for them
+type GridValue = (u32, [bool; 6], f64);
+
+/// Defaults for the fields of GRID cards left blank. Only the permanent
+/// constraints are applied so far
+#[derive(Debug, Clone)]
+pub struct GRDSET {
+    #[allow(dead_code)]
+    cp: u32,
+    #[allow(dead_code)]
+    cd: u32,
+    pub(super) ps: [bool; 6],
+    #[allow(dead_code)]
+    seid: u32,
+}
+
+/// There can only be one GRDSET in a deck
+impl StorageItem for GRDSET {
+    type Id = ();
+
+    fn id(&self) -> Self::Id {}
+}
+
+impl TryFrom<&BulkCard> for GRDSET {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"GRDSET ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"GRDSET ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"GRDSET ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card
+            .decode(&[Any, Int, Any, Any, Any, Int, Int, Int])?
+            .into_iter();
+        iter.next();
+        let cp = iter.next().id_or(0)?;
+        let mut iter = iter.skip(3);
+        let cd = iter.next().id_or(0)?;
+        let ps = iter.next().dof()?;
+        let seid = iter.next().id_or(0)?;
+        Ok(GRDSET { cp, cd, ps, seid })
+    }
+}
+
+/// Up to two constraints on the components of a grid, with the value of an
+/// enforced displacement
+#[derive(Debug, Clone)]
+pub struct SPC {
+    sid: u32,
+    constraints: Vec<GridValue>,
+}
+
+impl SPC {
+    /// The grid, its constrained components and the enforced displacement
+    pub fn constraints(&self) -> &[GridValue] {
+        &self.constraints
+    }
+}
+
+impl SetItem for SPC {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for SPC {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"SPC    ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"SPC    ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"SPC    ", *b"       ")),
+        }
+        let (sid, constraints) = grid_values(card)?;
+        Ok(SPC { sid, constraints })
+    }
+}
+
+/// Reads the set id and the triples of grid, components and value of SPC
+/// and SPCD
+fn grid_values(card: &BulkCard) -> Result<(u32, Vec<GridValue>)> {
+    use FieldKind::*;
+    let mut iter = card
+        .decode(&[Int, Int, Int, Real, Int, Int, Real])?
+        .into_iter();
+    let sid = iter.next().id()?;
+    let mut values = Vec::with_capacity(2);
+    for i in 0..2 {
+        let g = iter.next().unwrap_or_default();
+        if i > 0 && g == Field::Blank {
+            break;
+        }
+        values.push((g.id()?, iter.next().dof()?, iter.next().float_or(0.0)?));
+    }
+    Ok((sid, values))
+}
+
+/// The same components constrained on a list of grids
+#[derive(Debug, Clone)]
+pub struct SPC1 {
+    sid: u32,
+    c: [bool; 6],
+    grids: Vec<u32>,
+}
+
+impl SPC1 {
+    pub fn components(&self) -> [bool; 6] {
+        self.c
+    }
+
+    pub fn grids(&self) -> &[u32] {
+        &self.grids
+    }
+}
+
+impl SetItem for SPC1 {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for SPC1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"SPC1   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"SPC1   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"SPC1   ", *b"       ")),
+        }
+        use FieldKind::*;
+        let mut iter = card.decode(&[Int, Int, Int, IntOrChar])?.into_iter();
+        let sid = iter.next().id()?;
+        let c = iter.next().dof()?;
+        let grids = id_list(iter, "SPC1")?;
+        Ok(SPC1 { sid, c, grids })
+    }
+}
+
+/// Combines SPC sets into a new set
+#[derive(Debug, Clone)]
+pub struct SPCADD {
+    sid: u32,
+    pub(super) sets: Vec<u32>,
+}
+
+impl StorageItem for SPCADD {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for SPCADD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"SPCADD ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"SPCADD ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"SPCADD ", *b"       ")),
+        }
+        use FieldKind::*;
+        let fields = card.decode(&vec![Int; card.fields().len()])?;
+        let mut iter = fields.into_iter();
+        let sid = iter.next().id()?;
+        let sets = iter
+            .filter(|f| *f != Field::Blank)
+            .map(|f| f.id())
+            .collect::<Result<_>>()?;
+        Ok(SPCADD { sid, sets })
+    }
+}
+
+/// Enforced displacements, selected as a load set, for components that are
+/// also constrained by an SPC or SPC1
+#[derive(Debug, Clone)]
+pub struct SPCD {
+    sid: u32,
+    values: Vec<GridValue>,
+}
+
+impl SPCD {
+    /// The grid, its components and the enforced displacement
+    pub fn values(&self) -> &[GridValue] {
+        &self.values
+    }
+}
+
+impl SetItem for SPCD {
+    fn sid(&self) -> u32 {
+        self.sid
+    }
+}
+
+impl TryFrom<&BulkCard> for SPCD {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"SPCD   ") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"SPCD   ", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"SPCD   ", *b"       ")),
+        }
+        let (sid, values) = grid_values(card)?;
+        Ok(SPCD { sid, values })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use crate::bdf::case_control::Subcase;
+    use crate::bdf::deck::{Deck, Dof, DofConflict, Source, SpcSource};
+
+    const T: bool = true;
+    const F: bool = false;
+
+    #[test]
+    fn test_constrained_dofs() {
+        let input = b"GRDSET,,,,,,,6
+GRID,1,,0.,0.,0.
+GRID,2,,1.,0.,0.,,45
+GRID,3,,2.,0.,0.
+SPC,1,1,123,,2,1,0.5
+SPC1,2,3,3,THRU,5
+SPC1,2,12,7,9,
+,10
+SPCADD,3,1,2
+SPCD,4,2,1,0.5
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        assert_eq!(bulk.get_spc(1).next().unwrap().constraints()[1].2, 0.5);
+        assert_eq!(bulk.get_spc1(2).next().unwrap().grids(), &[3, 4, 5]);
+        assert_eq!(bulk.get_spcd(4).next().unwrap().values()[0].2, 0.5);
+
+        let mut expected = BTreeMap::new();
+        expected.insert(1, [T, T, T, F, F, T]);
+        expected.insert(2, [T, F, F, T, T, F]);
+        expected.insert(3, [F, F, F, F, F, T]);
+        assert_eq!(bulk.constrained_dofs(1), expected);
+
+        let dofs = bulk.constrained_dofs(3);
+        assert_eq!(dofs[&3], [F, F, T, F, F, T]);
+        assert_eq!(dofs[&4], [F, F, T, F, F, F]);
+        assert_eq!(dofs[&10], [T, T, F, F, F, F]);
+        assert_eq!(dofs.len(), 8);
+
+        assert!(Deck::from_buffer(b"SPC1,1,1,5,THRU,2").is_err());
+        assert!(Deck::from_buffer(b"GRDSET,,,,,,,6\nGRDSET,,,,,,,5").is_err());
+    }
+
+    #[test]
+    fn test_dependent_spc() {
+        let input = b"SPC1,1,1,2\nSPCADD,2,1\nRBE2,1,1,123,2";
+        let deck = Deck::from_buffer(input).unwrap();
+        let subcase = Subcase {
+            spc: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            deck.main().dof_conflicts(&subcase),
+            vec![DofConflict::DependentSpc {
+                dof: Dof::new(2, 1),
+                dependent: Source::Rbe2(1),
+                spc: SpcSource::Set(1),
+            }]
+        );
+        assert!(deck.main().dof_conflicts(&Subcase::default()).is_empty());
+    }
+}
@@ -0,0 +1,400 @@
+use std::convert::TryFrom;
+
+use bstr::ByteSlice;
+
+use super::StorageItem;
+use crate::bdf::parser::{BulkCard, Field, FieldConv, FieldKind};
+use crate::bdf::{Error, Result};
+
+/// How a table interpolates along one of its axes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Linear,
+    Log,
+}
+
+impl Axis {
+    fn decode(field: Field) -> Result<Self> {
+        match field {
+            Field::Blank => Ok(Axis::Linear),
+            Field::Text(text) if text.trim_end() == b"LINEAR" => Ok(Axis::Linear),
+            Field::Text(text) if text.trim_end() == b"LOG" => Ok(Axis::Log),
+            field => Err(Error::UnexpectedField("LINEAR or LOG", field)),
+        }
+    }
+
+    fn to(self, v: f64) -> f64 {
+        match self {
+            Axis::Linear => v,
+            Axis::Log => v.ln(),
+        }
+    }
+
+    fn from(self, v: f64) -> f64 {
+        match self {
+            Axis::Linear => v,
+            Axis::Log => v.exp(),
+        }
+    }
+}
+
+/// Decodes a card with `header` fields on its first line, followed by
+/// numbers from the second line on
+fn decode(card: &BulkCard, header: &[FieldKind]) -> Result<(Vec<Field>, Vec<Field>)> {
+    let mut schema = header.to_vec();
+    schema.resize(8, FieldKind::Any);
+    schema.resize(card.fields().len().max(8), FieldKind::RealOrChar);
+    let mut fields = card.decode(&schema)?;
+    let values = fields.split_off(8.min(fields.len()));
+    Ok((fields, values))
+}
+
+/// The numbers up to ENDT, leaving out SKIP
+fn numbers(fields: Vec<Field>, card: &'static str) -> Result<Vec<Option<f64>>> {
+    let mut values = Vec::new();
+    for field in fields {
+        match field {
+            Field::Text(text) if text.trim_end() == b"ENDT" => return Ok(values),
+            Field::Text(text) if text.trim_end() == b"SKIP" => values.push(None),
+            Field::Blank => {}
+            field => values.push(Some(field.float()?)),
+        }
+    }
+    Err(Error::UnexpectedField(card, Field::Blank))
+}
+
+/// The points of a table, where a pair with SKIP in either field is left out
+fn points(fields: Vec<Field>, card: &'static str) -> Result<Vec<(f64, f64)>> {
+    let values = numbers(fields, card)?;
+    if values.len() % 2 != 0 {
+        return Err(Error::UnexpectedField(card, Field::Blank));
+    }
+    let mut points: Vec<_> = values
+        .chunks(2)
+        .filter_map(|pair| Some((pair[0]?, pair[1]?)))
+        .collect();
+    if points.is_empty() {
+        return Err(Error::UnexpectedField(card, Field::Blank));
+    }
+    // The x values can be descending as well as ascending
+    if points.len() > 1 && points[0].0 > points[points.len() - 1].0 {
+        points.reverse();
+    }
+    Ok(points)
+}
+
+/// Interpolates linearly within the table and extrapolates linearly from
+/// the first two or the last two points outside it. At a discontinuity,
+/// where two points have the same x, the average of their y values is used
+fn interpolate(points: &[(f64, f64)], x: f64, xaxis: Axis, yaxis: Axis) -> f64 {
+    let exact: Vec<_> = points.iter().filter(|p| p.0 == x).map(|p| p.1).collect();
+    if !exact.is_empty() {
+        return exact.iter().sum::<f64>() / exact.len() as f64;
+    }
+    let n = points.len();
+    if n == 1 {
+        return points[0].1;
+    }
+    let i = match points.iter().position(|p| p.0 > x) {
+        Some(0) => 0,
+        Some(i) => i - 1,
+        None => n - 2,
+    };
+    let (x1, y1) = points[i];
+    let (x2, y2) = points[i + 1];
+    if x1 == x2 {
+        return y1;
+    }
+    let (u, u1, u2) = (xaxis.to(x), xaxis.to(x1), xaxis.to(x2));
+    let (v1, v2) = (yaxis.to(y1), yaxis.to(y2));
+    yaxis.from(v1 + (u - u1) / (u2 - u1) * (v2 - v1))
+}
+
+/// A material property as a function of temperature, given directly
+#[derive(Debug, Clone)]
+pub struct TABLEM1 {
+    tid: u32,
+    xaxis: Axis,
+    yaxis: Axis,
+    points: Vec<(f64, f64)>,
+}
+
+impl TABLEM1 {
+    pub fn value(&self, x: f64) -> f64 {
+        interpolate(&self.points, x, self.xaxis, self.yaxis)
+    }
+}
+
+impl StorageItem for TABLEM1 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.tid
+    }
+}
+
+impl TryFrom<&BulkCard> for TABLEM1 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"TABLEM1") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"TABLEM1", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"TABLEM1", *b"       ")),
+        }
+        use FieldKind::*;
+        let (header, values) = decode(card, &[Int, Char, Char])?;
+        let mut iter = header.into_iter();
+        let tid = iter.next().id()?;
+        let xaxis = Axis::decode(iter.next().unwrap_or_default())?;
+        let yaxis = Axis::decode(iter.next().unwrap_or_default())?;
+        let points = points(values, "TABLEM1")?;
+        Ok(TABLEM1 {
+            tid,
+            xaxis,
+            yaxis,
+            points,
+        })
+    }
+}
+
+/// A factor on a material property, as a function of the temperature less
+/// X1
+#[derive(Debug, Clone)]
+pub struct TABLEM2 {
+    tid: u32,
+    x1: f64,
+    points: Vec<(f64, f64)>,
+}
+
+impl TABLEM2 {
+    /// The property at `x`, where `z` is its value on the material card
+    pub fn value(&self, x: f64, z: f64) -> f64 {
+        z * interpolate(&self.points, x - self.x1, Axis::Linear, Axis::Linear)
+    }
+}
+
+impl StorageItem for TABLEM2 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.tid
+    }
+}
+
+impl TryFrom<&BulkCard> for TABLEM2 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"TABLEM2") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"TABLEM2", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"TABLEM2", *b"       ")),
+        }
+        use FieldKind::*;
+        let (header, values) = decode(card, &[Int, Real])?;
+        let mut iter = header.into_iter();
+        let tid = iter.next().id()?;
+        let x1 = iter.next().float_or(0.0)?;
+        let points = points(values, "TABLEM2")?;
+        Ok(TABLEM2 { tid, x1, points })
+    }
+}
+
+/// A factor on a material property, as a function of the temperature less
+/// X1 over X2
+#[derive(Debug, Clone)]
+pub struct TABLEM3 {
+    tid: u32,
+    x1: f64,
+    x2: f64,
+    points: Vec<(f64, f64)>,
+}
+
+impl TABLEM3 {
+    /// The property at `x`, where `z` is its value on the material card
+    pub fn value(&self, x: f64, z: f64) -> f64 {
+        let x = (x - self.x1) / self.x2;
+        z * interpolate(&self.points, x, Axis::Linear, Axis::Linear)
+    }
+}
+
+impl StorageItem for TABLEM3 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.tid
+    }
+}
+
+impl TryFrom<&BulkCard> for TABLEM3 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"TABLEM3") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"TABLEM3", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"TABLEM3", *b"       ")),
+        }
+        use FieldKind::*;
+        let (header, values) = decode(card, &[Int, Real, Real])?;
+        let mut iter = header.into_iter();
+        let tid = iter.next().id()?;
+        let x1 = iter.next().float_or(0.0)?;
+        let x2 = scale(iter.next().unwrap_or_default())?;
+        let points = points(values, "TABLEM3")?;
+        Ok(TABLEM3 {
+            tid,
+            x1,
+            x2,
+            points,
+        })
+    }
+}
+
+/// X2 of TABLEM3 and TABLEM4, which can't be zero
+fn scale(field: Field) -> Result<f64> {
+    match field.float()? {
+        0.0 => Err(Error::UnexpectedField("X2", field)),
+        x2 => Ok(x2),
+    }
+}
+
+/// A factor on a material property, as a polynomial in the temperature less
+/// X1 over X2, for temperatures between X3 and X4
+#[derive(Debug, Clone)]
+pub struct TABLEM4 {
+    tid: u32,
+    x1: f64,
+    x2: f64,
+    x3: f64,
+    x4: f64,
+    a: Vec<f64>,
+}
+
+impl TABLEM4 {
+    /// The property at `x`, where `z` is its value on the material card.
+    /// Temperatures outside X3 to X4 use the value at the nearest limit
+    pub fn value(&self, x: f64, z: f64) -> f64 {
+        let x = (x.max(self.x3).min(self.x4) - self.x1) / self.x2;
+        z * self.a.iter().rev().fold(0.0, |sum, a| sum * x + a)
+    }
+}
+
+impl StorageItem for TABLEM4 {
+    type Id = u32;
+
+    fn id(&self) -> Self::Id {
+        self.tid
+    }
+}
+
+impl TryFrom<&BulkCard> for TABLEM4 {
+    type Error = Error;
+    fn try_from(card: &BulkCard) -> Result<Self> {
+        match card.card_type().as_ref() {
+            Some(b"TABLEM4") => {}
+            Some(c) => return Err(Error::UnexpectedCardType(*b"TABLEM4", *c)),
+            None => return Err(Error::UnexpectedCardType(*b"TABLEM4", *b"       ")),
+        }
+        use FieldKind::*;
+        let (header, values) = decode(card, &[Int, Real, Real, Real, Real])?;
+        let mut iter = header.into_iter();
+        let tid = iter.next().id()?;
+        let x1 = iter.next().float_or(0.0)?;
+        let x2 = scale(iter.next().unwrap_or_default())?;
+        let x3 = iter.next().float()?;
+        let x4 = iter.next().float()?;
+        let a = numbers(values, "TABLEM4")?
+            .into_iter()
+            .collect::<Option<_>>()
+            .ok_or(Error::UnexpectedField("TABLEM4", Field::Blank))?;
+        Ok(TABLEM4 {
+            tid,
+            x1,
+            x2,
+            x3,
+            x4,
+            a,
+        })
+    }
+}
+
+/// A table for temperature dependent materials of any type. They all share
+/// the same ids, so a MATT1 or MATT8 can refer to any of them
+#[derive(Debug, Clone, Copy)]
+pub enum MaterialTable<'a> {
+    Tablem1(&'a TABLEM1),
+    Tablem2(&'a TABLEM2),
+    Tablem3(&'a TABLEM3),
+    Tablem4(&'a TABLEM4),
+}
+
+impl MaterialTable<'_> {
+    /// The value of a property at `x`, where `z` is its value on the material
+    /// card. A TABLEM1 gives the value itself, while the others give a factor
+    /// on `z`
+    pub fn value(&self, x: f64, z: f64) -> f64 {
+        match self {
+            MaterialTable::Tablem1(t) => t.value(x),
+            MaterialTable::Tablem2(t) => t.value(x, z),
+            MaterialTable::Tablem3(t) => t.value(x, z),
+            MaterialTable::Tablem4(t) => t.value(x, z),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdf::deck::Deck;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6 * b.abs().max(1.)
+    }
+
+    #[test]
+    fn test_tablem1() {
+        let input = b"TABLEM1,1
+,0.,1.,10.,2.,10.,4.,20.,6.
+,30.,SKIP,ENDT
+TABLEM1,2,LOG,LOG
+,1.,1.,100.,10000.,ENDT
+TABLEM1,3
+,20.,6.,10.,2.,0.,1.,ENDT
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let table = bulk.get_tablem1(1).unwrap();
+        assert!(close(table.value(5.), 1.5));
+        assert!(close(table.value(15.), 5.));
+        // The average at the discontinuity
+        assert!(close(table.value(10.), 3.));
+        // Extrapolated from the first or last two points
+        assert!(close(table.value(-10.), 0.));
+        assert!(close(table.value(30.), 8.));
+
+        // y is x squared, which is a straight line on log axes
+        assert!(close(bulk.get_tablem1(2).unwrap().value(10.), 100.));
+        assert!(close(bulk.get_tablem1(3).unwrap().value(15.), 4.));
+
+        assert!(Deck::from_buffer(b"TABLEM1,1\n,0.,1.,10.").is_err());
+        assert!(Deck::from_buffer(b"TABLEM1,1\n,0.,1.,10.,ENDT").is_err());
+    }
+
+    #[test]
+    fn test_scaled_tables() {
+        let input = b"TABLEM2,1,100.
+,0.,1.,100.,0.5,ENDT
+TABLEM3,2,100.,50.
+,0.,1.,1.,2.,ENDT
+TABLEM4,3,100.,100.,0.,300.
+,1.,2.,3.,ENDT
+";
+        let deck = Deck::from_buffer(input).unwrap();
+        let bulk = deck.main();
+        let value = |tid, x| bulk.get_material_table(tid).unwrap().value(x, 10.);
+        assert!(close(value(1, 150.), 7.5));
+        assert!(close(value(2, 125.), 15.));
+        // 1 + 2 x + 3 x^2 for x of 0.5, and of 2 at the upper limit
+        assert!(close(value(3, 150.), 27.5));
+        assert!(close(value(3, 500.), 170.));
+        assert!(Deck::from_buffer(b"TABLEM3,1,0.,0.\n,0.,1.,ENDT").is_err());
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use bstr::ByteSlice;
+
+use crate::bdf::parser::{
+    parse_source, source, BulkCard, CardWriter, Eol, FieldFormat, ParserOptions,
+};
+use crate::bdf::sections::{partition, Partition, Sections};
+use crate::bdf::Result;
+
+/// An input file kept in a form that can be written back out byte for byte.
+/// Cards are parsed as by `parse_buffer` and can be edited, removed or added.
+/// When written, only the lines of edited cards are replaced. Everything else,
+/// including comments, blank lines, the sections before `BEGIN BULK` and the
+/// layout of untouched cards, is written exactly as it was read
+///
+/// INCLUDE statements are kept as text and the included files aren't read
+#[derive(Debug)]
+pub struct Document {
+    /// Every physical line of the input, including its line ending
+    lines: Vec<Vec<u8>>,
+    /// The group of cards each line belongs to, if any
+    owners: Vec<Option<usize>>,
+    groups: Vec<Group>,
+    /// Cards that have been added, written before `ENDDATA`
+    added: Vec<BulkCard>,
+    /// The index of the `ENDDATA` line or the number of lines if there isn't one
+    end: usize,
+    /// The index of the line following the last lines of each partition,
+    /// where cards added to it are written
+    partitions: Vec<(Partition, usize)>,
+    format: FieldFormat,
+    /// The line ending of the first line, used for added cards
+    eol: Eol,
+}
+
+/// Cards read from the same lines. This is usually a single card but an `=n`
+/// replication entry generates several cards from one line
+#[derive(Debug)]
+struct Group {
+    first: usize,
+    cards: Vec<BulkCard>,
+    edited: bool,
+}
+
+impl Document {
+    pub fn from_filename(filename: impl AsRef<Path>) -> Result<Self> {
+        let filename = filename.as_ref();
+        let buffer = std::fs::read(filename)?;
+        Self::from_source(&buffer, Some(filename.into()))
+    }
+
+    pub fn from_buffer(buffer: &[u8]) -> Result<Self> {
+        Self::from_source(buffer, None)
+    }
+
+    fn from_source(buffer: &[u8], file: Option<std::sync::Arc<Path>>) -> Result<Self> {
+        let lines = buffer
+            .split_inclusive(|&c| c == b'\n')
+            .map(<[u8]>::to_vec)
+            .collect::<Vec<_>>();
+        let mut sections = Sections::from_lines(source::read_buffer_without_includes(buffer, file));
+        let end = match &sections.enddata {
+            Some(enddata) => enddata.line() - 1,
+            None => lines.len(),
+        };
+        let bulk = sections.take_bulk();
+        let mut partitions: Vec<(Partition, usize)> = Vec::new();
+        let mut current = Partition::Main;
+        for line in &bulk {
+            if let Some(Ok(partition)) = partition(&line.text) {
+                set_end(&mut partitions, current, line.line() - 1);
+                current = partition;
+            }
+        }
+        set_end(&mut partitions, current, end);
+        let eol = match lines.first() {
+            Some(line) if line.ends_with(b"\r\n") => Eol::CrLf,
+            _ => Eol::Lf,
+        };
+        let mut owners = vec![None; lines.len()];
+        let mut groups: Vec<Group> = Vec::new();
+        let mut by_line = HashMap::new();
+        for card in parse_source(bulk, &ParserOptions::default())? {
+            let card = card?;
+            let i = *by_line.entry(card.line()).or_insert_with(|| {
+                groups.push(Group {
+                    first: card.line() - 1,
+                    cards: Vec::new(),
+                    edited: false,
+                });
+                groups.len() - 1
+            });
+            for line in card.lines() {
+                if let Some(owner) = owners.get_mut(line - 1) {
+                    *owner = Some(i);
+                }
+            }
+            groups[i].cards.push(card);
+        }
+        Ok(Self {
+            lines,
+            owners,
+            groups,
+            added: Vec::new(),
+            end,
+            partitions,
+            format: FieldFormat::Small,
+            eol,
+        })
+    }
+
+    /// All cards in the order they were read followed by any added cards.
+    /// Removed cards are skipped
+    pub fn cards(&self) -> impl Iterator<Item = &BulkCard> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.cards.iter())
+            .chain(self.added.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards().next().is_none()
+    }
+
+    /// Gets a card for editing. The card is rewritten when the document is
+    /// written even if it isn't changed
+    pub fn card_mut(&mut self, index: usize) -> Option<&mut BulkCard> {
+        let mut index = index;
+        for group in &mut self.groups {
+            if index < group.cards.len() {
+                group.edited = true;
+                return group.cards.get_mut(index);
+            }
+            index -= group.cards.len();
+        }
+        self.added.get_mut(index)
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<BulkCard> {
+        let mut index = index;
+        for group in &mut self.groups {
+            if index < group.cards.len() {
+                group.edited = true;
+                return Some(group.cards.remove(index));
+            }
+            index -= group.cards.len();
+        }
+        if index < self.added.len() {
+            Some(self.added.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Adds a card to the end of its partition, using the line ending of the
+    /// rest of the file. Cards in a partition the file doesn't have are
+    /// written before `ENDDATA` following a new `BEGIN` statement
+    pub fn push(&mut self, mut card: BulkCard) {
+        card.set_eol(self.eol);
+        self.added.push(card)
+    }
+
+    /// The format used for added cards. Edited cards are written in the format
+    /// they were read in
+    pub fn set_format(&mut self, format: FieldFormat) {
+        self.format = format
+    }
+
+    pub fn write(&self, writer: impl io::Write) -> io::Result<()> {
+        let mut writer = CardWriter::new(writer, self.format);
+        for (i, line) in self.lines.iter().enumerate() {
+            self.write_added(&mut writer, i)?;
+            match self.owners[i].map(|g| &self.groups[g]) {
+                Some(group) if group.edited => {
+                    if group.first == i {
+                        for card in &group.cards {
+                            writer.write_card_with(card, original_format(card))?;
+                        }
+                    }
+                }
+                _ => writer.get_mut().write_all(line)?,
+            }
+        }
+        self.write_added(&mut writer, self.lines.len())?;
+        Ok(())
+    }
+
+    /// Writes the document to memory. This only fails for edited cards with
+    /// values that can't be written, such as a NaN
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Writes the added cards that go before line `i`
+    fn write_added<W: io::Write>(&self, writer: &mut CardWriter<W>, i: usize) -> io::Result<()> {
+        let ending = self
+            .partitions
+            .iter()
+            .filter(|(_, end)| *end == i)
+            .map(|(partition, _)| *partition);
+        let mut cards = ending
+            .flat_map(|p| self.added.iter().filter(move |c| c.partition() == p))
+            .peekable();
+        let mut missing = Vec::new();
+        if i == self.end {
+            for card in &self.added {
+                let p = card.partition();
+                if !self.partitions.iter().any(|(q, _)| *q == p) && !missing.contains(&p) {
+                    missing.push(p);
+                }
+            }
+        }
+        if cards.peek().is_none() && missing.is_empty() {
+            return Ok(());
+        }
+        // The last line of a file may not have a line ending
+        if let Some(previous) = i.checked_sub(1).and_then(|i| self.lines.get(i)) {
+            if !previous.ends_with(b"\n") {
+                writer.get_mut().write_all(b"\n")?;
+            }
+        }
+        for card in cards {
+            writer.write_card(card)?;
+        }
+        for p in missing {
+            write!(writer.get_mut(), "{}{}", p, self.eol)?;
+            for card in self.added.iter().filter(|c| c.partition() == p) {
+                writer.write_card(card)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn set_end(partitions: &mut Vec<(Partition, usize)>, partition: Partition, end: usize) {
+    match partitions.iter_mut().find(|(p, _)| *p == partition) {
+        Some((_, e)) => *e = end,
+        None => partitions.push((partition, end)),
+    }
+}
+
+/// The format a card was read in, so that edited cards keep their layout
+fn original_format(card: &BulkCard) -> FieldFormat {
+    let original = card.original();
+    let first = &original[..std::cmp::min(original.len(), 10)];
+    if first.contains(&b',') {
+        FieldFormat::Free
+    } else if first[..std::cmp::min(first.len(), 8)]
+        .trim_end()
+        .ends_with(b"*")
+    {
+        FieldFormat::Large
+    } else {
+        FieldFormat::Small
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::parser::Field;
+
+    const INPUT: &[u8] = b"SOL 101\r\nCEND\r\nBEGIN BULK\r\n$ grids\r\nGRID    1       0       1.0     0.0     0.0\r\n\r\nGRID*   2               0               1.0             2.0\r\n*       3.0\r\nCTETRA,1,1,1,2,3,4\r\nENDDATA\r\n";
+
+    #[test]
+    fn test_round_trip() {
+        let document = Document::from_buffer(INPUT).unwrap();
+        assert_eq!(document.to_bytes().unwrap().as_bstr(), INPUT.as_bstr());
+        let types = document
+            .cards()
+            .filter_map(|c| c.card_type())
+            .collect::<Vec<_>>();
+        assert_eq!(types, vec![*b"GRID   ", *b"GRID   ", *b"CTETRA "]);
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut document = Document::from_buffer(INPUT).unwrap();
+        let index = |document: &Document, id| {
+            document
+                .cards()
+                .position(|c| c.card_type() == Some(*b"GRID   ") && c.fields()[0] == id)
+                .unwrap()
+        };
+        let i = index(&document, Field::IntOrId(2));
+        document
+            .card_mut(i)
+            .unwrap()
+            .set_field(2, Field::Float(5.0));
+        let i = index(&document, Field::IntOrId(1));
+        document.remove(i).unwrap();
+        document.push(BulkCard::new(b"grid", vec![Field::IntOrId(3)]).unwrap());
+        let expected = b"SOL 101\r\nCEND\r\nBEGIN BULK\r\n$ grids\r\n\r\nGRID*   2               0               5.              2.              *1\r\n*1      3.\r\nCTETRA,1,1,1,2,3,4\r\nGRID    3\r\nENDDATA\r\n";
+        assert_eq!(document.to_bytes().unwrap().as_bstr(), expected.as_bstr());
+    }
+
+    #[test]
+    fn test_partitions() {
+        let input = b"BEGIN BULK\nGRID,1\nBEGIN SUPER=2 $ part\nGRID,1\nENDDATA\n";
+        let mut document = Document::from_buffer(input).unwrap();
+        assert_eq!(document.to_bytes().unwrap().as_bstr(), input.as_bstr());
+        let partitions = document
+            .cards()
+            .filter(|c| c.card_type().is_some())
+            .map(|c| c.partition())
+            .collect::<Vec<_>>();
+        assert_eq!(partitions, vec![Partition::Main, Partition::Super(2)]);
+        for (id, partition) in [(2, Partition::Main), (3, Partition::Super(4))] {
+            let mut card = BulkCard::new(b"GRID", vec![Field::IntOrId(id)]).unwrap();
+            card.set_partition(partition);
+            document.push(card);
+        }
+        let expected = b"BEGIN BULK\nGRID,1\nGRID    2\nBEGIN SUPER=2 $ part\nGRID,1\nBEGIN SUPER=4\nGRID    3\nENDDATA\n";
+        assert_eq!(document.to_bytes().unwrap().as_bstr(), expected.as_bstr());
+    }
+}
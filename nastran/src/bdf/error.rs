@@ -1,6 +1,9 @@
+use std::fmt;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::bdf::parser::Field;
+use crate::bdf::parser::{Field, FieldKind};
 
 use bstr::ByteSlice;
 use thiserror::Error;
@@ -9,7 +12,7 @@ use thiserror::Error;
 pub enum Error {
     #[error("Embedded Space in field")]
     EmbeddedSpace,
-    #[error("Unexpected character {}",(&[*.0][..]).as_bstr())]
+    #[error("Unexpected character {}",[*.0][..].as_bstr())]
     UnexpectedChar(u8),
     #[error("Text field greater than 8 chars '{}'",.0.as_bstr())]
     TextTooLong(Vec<u8>),
@@ -27,10 +30,246 @@ pub enum Error {
     UnexpectedDOF(Field),
     #[error("Invalid specification of e ({0}),g ({1}) and nu ({2}) for material card ")]
     InvalidMaterialCard(Field, Field, Field),
+    #[error("Two of bulk ({0}), rho ({1}) and c ({2}) are needed for a fluid material card")]
+    InvalidFluidMaterial(Field, Field, Field),
     #[error("Duplicate Card")]
     DuplicateCard,
+    #[error("Unsupported card type '{}' skipped",.0.as_bstr())]
+    UnsupportedCard([u8; 7]),
+    #[error("Could not read include file '{}' : {1}",.0.display())]
+    IncludeNotFound(PathBuf, #[source] io::Error),
+    #[error("Include file '{}' includes itself",.0.display())]
+    IncludeCycle(PathBuf),
+    #[error("Include statement filename not terminated '{}'",.0.as_bstr())]
+    UnterminatedInclude(Vec<u8>),
+    #[error("Line longer than {0} characters")]
+    LineTooLong(usize),
+    #[error("Invalid replication shorthand '{}'",.0.as_bstr())]
+    InvalidShorthand(Vec<u8>),
+    #[error("Can't increment '{0:?}' by '{1:?}'")]
+    InvalidIncrement(Field, Field),
+    #[error("Replication shorthand without a previous card")]
+    NoReplicationSource,
+    #[error("Expected {0} but found '{}'",.1.as_bstr())]
+    UnexpectedKind(FieldKind, Vec<u8>),
+    #[error("Invalid statement '{}'",.0.as_bstr())]
+    InvalidStatement(Vec<u8>),
+    #[error("Unsupported section type '{}'",.0.as_bstr())]
+    UnknownSection(Vec<u8>),
+    #[error("Invalid dimensions for a {0} section")]
+    InvalidSection(&'static str),
     #[error("Error reading datfile : {0}")]
     IO(#[from] io::Error),
+    #[error("{1}\n{0}")]
+    Located(Box<Location>, Box<Error>),
+}
+
+impl Error {
+    /// Where in the input this error occurred, if known
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::Located(location, _) => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The underlying error without any location information
+    pub fn kind(&self) -> &Error {
+        match self {
+            Error::Located(_, error) => error.kind(),
+            error => error,
+        }
+    }
+
+    /// Attaches a location to this error. If the error already has a location
+    /// any missing line information is filled in from `location`
+    pub fn at(self, location: Location) -> Self {
+        match self {
+            Error::Located(mut inner, error) => {
+                if inner.line == 0 {
+                    inner.file = location.file;
+                    inner.line = location.line;
+                    inner.text = location.text;
+                    inner.tab_width = location.tab_width;
+                }
+                Error::Located(inner, error)
+            }
+            error => Error::Located(Box::new(location), Box::new(error)),
+        }
+    }
+
+    /// Marks the field and columns this error occurred in. The line is filled
+    /// in later by `at`
+    pub(crate) fn at_columns(self, field: Option<usize>, column: usize, width: usize) -> Self {
+        match self {
+            Error::Located(..) => self,
+            error => Error::Located(
+                Box::new(Location {
+                    field,
+                    column: Some(column),
+                    width,
+                    ..Default::default()
+                }),
+                Box::new(error),
+            ),
+        }
+    }
+}
+
+/// The position in the input that an error refers to. Lines and columns are
+/// 1-based with tabs expanded to the tab width the line was parsed with
+#[derive(Debug, Clone)]
+pub struct Location {
+    file: Option<Arc<Path>>,
+    line: usize,
+    column: Option<usize>,
+    width: usize,
+    field: Option<usize>,
+    text: Vec<u8>,
+    tab_width: usize,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self {
+            file: None,
+            line: 0,
+            column: None,
+            width: 0,
+            field: None,
+            text: Vec::new(),
+            tab_width: 8,
+        }
+    }
+}
+
+impl Location {
+    pub(crate) fn new(file: Option<Arc<Path>>, line: usize, text: &[u8]) -> Self {
+        Self {
+            file,
+            line,
+            text: text.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn with_field(mut self, field: usize, column: usize, width: usize) -> Self {
+        self.field = Some(field);
+        self.column = Some(column);
+        self.width = width;
+        self
+    }
+
+    /// The column multiple tabs are expanded to when showing the line, which
+    /// should match the one the columns were counted with
+    pub(crate) fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width.max(1);
+        self
+    }
+
+    /// Marks the field without a column, for fields of a card that may be on
+    /// a continuation line
+    pub(crate) fn with_field_number(mut self, field: usize) -> Self {
+        self.field = Some(field);
+        self
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// The NASTRAN field number, where the card name is field 1
+    pub fn field(&self) -> Option<usize> {
+        self.field
+    }
+
+    /// The original text of the line
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, " --> {}:{}", file.display(), self.line)?,
+            None => write!(f, " --> <input>:{}", self.line)?,
+        }
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        let line = self.line.to_string();
+        let gutter = " ".repeat(line.len());
+        let mut text = String::with_capacity(self.text.len());
+        for c in self.text.trim_end_with(|c| c == '\r' || c == '\n').chars() {
+            if c == '\t' {
+                text.push(' ');
+                while !text.chars().count().is_multiple_of(self.tab_width) {
+                    text.push(' ');
+                }
+            } else {
+                text.push(c);
+            }
+        }
+        write!(f, "\n{} |\n{} | {}", gutter, line, text)?;
+        if let Some(column) = self.column {
+            let padding = " ".repeat(column.saturating_sub(1));
+            let marker = "^".repeat(std::cmp::max(self.width, 1));
+            write!(f, "\n{} | {}{}", gutter, padding, marker)?;
+            if let Some(field) = self.field {
+                write!(f, " field {}", field)?;
+            }
+        } else if let Some(field) = self.field {
+            write!(f, "\n{} | field {}", gutter, field)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// An error or warning collected while parsing in lenient mode
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub error: Error,
+}
+
+impl Diagnostic {
+    pub fn error(error: Error) -> Self {
+        Self {
+            severity: Severity::Error,
+            error,
+        }
+    }
+
+    pub fn warning(error: Error) -> Self {
+        Self {
+            severity: Severity::Warning,
+            error,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: {}", self.error),
+            Severity::Error => write!(f, "error: {}", self.error),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -1,14 +1,28 @@
 mod lines;
+mod options;
+mod raw;
+pub(crate) mod source;
+mod stream;
+mod writer;
 
 use bstr::ByteSlice;
 use smallvec::SmallVec;
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
 
-use crate::bdf::{Error, Result};
+use crate::bdf::sections::{bulk_lines, partition, Partition};
+use crate::bdf::{Diagnostic, Error, Location, Result};
 
-use lines::{NastranLine, NastranLineIter};
+use lines::NastranLine;
+pub use options::{LongLines, ParserOptions, TrailingField};
+pub use raw::{FieldKind, RawField};
+pub use source::SourceLine;
+pub use stream::{parse_file_streaming, parse_reader_streaming, StreamOptions};
+pub use writer::{CardWriter, FieldFormat};
 
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct Comment(SmallVec<[u8; 8]>);
@@ -38,7 +52,7 @@ impl fmt::Display for Comment {
 impl TryFrom<NastranLine> for UnparsedBulkLine {
     type Error = Error;
     fn try_from(mut line: NastranLine) -> Result<UnparsedBulkLine> {
-        let first = parse_first_field(line.take8())?;
+        let first = parse_first_field(line.take8()).map_err(|e| e.at_columns(Some(1), 1, 8))?;
         let first = match first {
             Some(field) => field,
             None => {
@@ -60,7 +74,8 @@ impl TryFrom<NastranLine> for UnparsedBulkLine {
             let field2 = line.take16();
             let field3 = line.take16();
             let field4 = line.take16();
-            let trailing = parse_trailing_field(line.take8())?;
+            let trailing = parse_trailing_field(line.take_trailing())
+                .map_err(|e| e.at_columns(Some(10), 73, 8))?;
             let (comment, eol) = line.comment_and_eol()?;
             Ok(UnparsedBulkLine {
                 original: line.original(),
@@ -86,7 +101,8 @@ impl TryFrom<NastranLine> for UnparsedBulkLine {
             let field6 = line.take8();
             let field7 = line.take8();
             let field8 = line.take8();
-            let trailing = parse_trailing_field(line.take8())?;
+            let trailing = parse_trailing_field(line.take_trailing())
+                .map_err(|e| e.at_columns(Some(10), 73, 8))?;
             let (comment, eol) = line.comment_and_eol()?;
             Ok(UnparsedBulkLine {
                 original: line.original(),
@@ -111,276 +127,311 @@ impl TryFrom<NastranLine> for UnparsedBulkLine {
     }
 }
 
+/// A single field of a free field line along with its column and width, with
+/// tabs expanded
 #[derive(Debug)]
-struct CommaField(SmallVec<[u8; 16]>);
-
-impl TryFrom<CommaField> for [u8; 8] {
-    type Error = Error;
-    fn try_from(field: CommaField) -> Result<Self> {
-        if field.0.len() > 8 {
-            Err(Error::TextTooLong(field.0.into_vec()))
-        } else {
-            let mut out = [b' '; 8];
-            let n = std::cmp::min(field.0.len(), 8);
-            out[..n].copy_from_slice(&field.0[..n]);
-            Ok(out)
-        }
-    }
+struct CommaField {
+    text: SmallVec<[u8; 16]>,
+    column: usize,
+    width: usize,
 }
 
-impl TryFrom<CommaField> for [u8; 16] {
-    type Error = Error;
-    fn try_from(field: CommaField) -> Result<Self> {
-        if field.0.len() > 16 {
-            Err(Error::TextTooLong(field.0.into_vec()))
-        } else {
-            let mut out = [b' '; 16];
-            let n = std::cmp::min(field.0.len(), 16);
-            out[..n].copy_from_slice(&field.0[..n]);
-            Ok(out)
-        }
+impl CommaField {
+    fn error(&self, error: Error, field: usize) -> Error {
+        error.at_columns(Some(field), self.column, self.width)
     }
-}
 
-impl TryFrom<CommaField> for Option<FirstField> {
-    type Error = Error;
-    fn try_from(field: CommaField) -> Result<Self> {
-        if field.0.len() > 8 {
-            Err(Error::TextTooLong(field.0.into_vec()))
-        } else {
-            let mut array = [b' '; 8];
-            let n = std::cmp::min(field.0.len(), 8);
-            array[..n].copy_from_slice(&field.0[..n]);
-            parse_first_field(array)
+    fn padded(&self) -> Result<[u8; 8]> {
+        if self.text.len() > 8 {
+            return Err(Error::TextTooLong(self.text.to_vec()));
         }
+        let mut out = [b' '; 8];
+        out[..self.text.len()].copy_from_slice(&self.text);
+        Ok(out)
     }
-}
 
-impl TryFrom<CommaField> for UnparsedSingleField {
-    type Error = Error;
-    fn try_from(field: CommaField) -> Result<Self> {
-        field.try_into().map(Self)
+    fn first_field(&self) -> Result<FirstField> {
+        Ok(parse_first_field(self.padded()?)?.unwrap_or_default())
     }
-}
 
-impl TryFrom<CommaField> for UnparsedDoubleField {
-    type Error = Error;
-    fn try_from(field: CommaField) -> Result<Self> {
-        field.try_into().map(Self)
+    fn continuation(&self) -> Result<ContinuationField> {
+        parse_trailing_field(self.padded()?)
     }
-}
 
-impl TryFrom<CommaField> for ContinuationField {
-    type Error = Error;
-    fn try_from(field: CommaField) -> Result<Self> {
-        if field.0.len() > 8 {
-            Err(Error::TextTooLong(field.0.into_vec()))
-        } else {
-            let mut array = [b' '; 8];
-            let n = std::cmp::min(field.0.len(), 8);
-            array[..n].copy_from_slice(&field.0[..n]);
-            parse_trailing_field(array)
+    /// Data fields may be up to 16 characters long in either small or large
+    /// field format since there are no columns to overflow
+    fn field(&self, index: usize, shorthands: &mut Shorthands) -> Result<Field> {
+        if self.text.len() > 16 {
+            return Err(Error::TextTooLong(self.text.to_vec()));
         }
+        parse_data_field(&self.text, index, shorthands)
     }
 }
 
+/// A free field line split up into its fields. Free field data is
+/// uppercased and has no column limit
 struct NastranCommaLine {
-    original: Vec<u8>,
-    iter: NastranLineIter,
-    secondline: bool,
+    fields: Vec<CommaField>,
+    comment: Comment,
+    eol: Option<Eol>,
 }
 
-impl NastranCommaLine {
-    fn new(line: Vec<u8>) -> Self {
-        // Add comma check here?
-        NastranCommaLine {
-            original: line.clone(),
-            iter: NastranLineIter::new(line.into_iter()),
-            secondline: false,
-        }
-    }
+/// Where the next field of a free field line belongs
+#[derive(Debug, Clone, Copy)]
+enum CommaState {
+    /// The data fields of a logical line
+    Data,
+    /// The continuation field following the last data field
+    Trailing,
+    /// After an explicit continuation marker. Any further fields belong to an
+    /// implied continuation line
+    Continued,
+}
 
-    fn next_field(&mut self) -> Option<CommaField> {
-        use std::iter::FromIterator;
-        let mut field = SmallVec::from_iter(
-            (&mut self.iter)
-                .skip_while(|c| *c == b' ')
-                .take_while(|c| *c != b','),
-        );
-        if field.is_empty() {
-            if self.iter.peek().is_none() {
-                None
-            } else {
-                Some(CommaField(SmallVec::new()))
+impl NastranCommaLine {
+    fn new(line: &[u8], options: &ParserOptions) -> Self {
+        let mut fields = Vec::new();
+        let mut comment = Comment::new();
+        let mut eol = None;
+        let mut field = CommaField {
+            text: SmallVec::new(),
+            column: 1,
+            width: 0,
+        };
+        let mut column = 0;
+        let mut chars = line.iter().copied();
+        while let Some(c) = chars.next() {
+            column += 1;
+            match c {
+                b',' => {
+                    let next = CommaField {
+                        text: SmallVec::new(),
+                        column: column + 1,
+                        width: 0,
+                    };
+                    fields.push(std::mem::replace(&mut field, next));
+                    continue;
+                }
+                b'$' => {
+                    comment.push(c);
+                    for c in &mut chars {
+                        match c {
+                            b'\r' => {
+                                eol = Some(Eol::CrLf);
+                                break;
+                            }
+                            c => comment.push(c),
+                        }
+                    }
+                    break;
+                }
+                b'\r' => {
+                    eol = Some(Eol::CrLf);
+                    break;
+                }
+                b'\t' => {
+                    while !column.is_multiple_of(options.tab_width) {
+                        column += 1;
+                    }
+                    if field.text.is_empty() {
+                        field.column = column + 1;
+                    } else {
+                        field.text.push(b' ');
+                    }
+                }
+                b' ' if field.text.is_empty() => field.column = column + 1,
+                c if options.fold_lowercase => {
+                    field.text.push(c.to_ascii_uppercase());
+                    field.width = column + 1 - field.column;
+                }
+                c => {
+                    field.text.push(c);
+                    field.width = column + 1 - field.column;
+                }
             }
-        } else {
-            let mut j = field.len();
-            while j > 0 && field[j - 1] == b' ' {
-                j -= 1;
+        }
+        fields.push(field);
+        for field in &mut fields {
+            while field.text.last() == Some(&b' ') {
+                field.text.pop();
             }
-            field.truncate(j);
-            Some(CommaField(field))
         }
-    }
-
-    fn next_single_field(&mut self) -> Result<UnparsedSingleField> {
-        self.next_field()
-            .map(TryInto::try_into)
-            .unwrap_or(Ok(UnparsedSingleField([b' '; 8])))
-    }
-
-    fn next_double_field(&mut self) -> Result<UnparsedDoubleField> {
-        self.next_field()
-            .map(TryInto::try_into)
-            .unwrap_or(Ok(UnparsedDoubleField([b' '; 16])))
-    }
-
-    fn next_trailing_field(&mut self) -> Result<ContinuationField> {
-        match self.iter.peek() {
-            Some(b'+') | Some(b'\r') | Some(b'\n') => self
-                .next_field()
-                .map(TryInto::try_into)
-                .unwrap_or(Ok(ContinuationField([b' '; 7]))),
-            _ => Ok(ContinuationField([b' '; 7])),
+        if fields.len() == 1 && fields[0].text.is_empty() {
+            fields.clear();
+        }
+        NastranCommaLine {
+            fields,
+            comment,
+            eol,
         }
     }
 
-    fn comment_and_eol(&mut self) -> Option<(Comment, Option<Eol>)> {
-        self.iter.comment_and_eol()
+    /// The first field of the line, which is all that's needed to recover
+    /// from a line that couldn't be parsed
+    fn first_field(&self) -> Option<FirstField> {
+        self.fields.first().and_then(|f| f.first_field().ok())
     }
-}
 
-impl Iterator for NastranCommaLine {
-    type Item = Result<UnparsedBulkLine>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let first = self.next_field();
-        if first.is_none() {
-            if let Some((comment, eol)) = self.comment_and_eol() {
-                let original = std::mem::take(&mut self.original);
-                return Some(Ok(UnparsedBulkLine {
+    /// Splits the line into logical lines. A line with more data fields than
+    /// fit before the continuation field is continued on an implied
+    /// continuation line. An explicit `+` or `*` marker in the continuation
+    /// field is used as the first field of the implied line, otherwise the
+    /// continuation is blank. Blank fields following an explicit marker are
+    /// ignored so that trailing commas don't create an extra line
+    fn into_lines(self, original: Vec<u8>) -> Result<Vec<BulkLine>> {
+        let NastranCommaLine {
+            fields,
+            comment,
+            eol,
+        } = self;
+        let mut iter = fields.iter().enumerate();
+        let first = match iter.next() {
+            Some((_, field)) => field.first_field().map_err(|e| field.error(e, 1))?,
+            None => {
+                return Ok(vec![BulkLine {
+                    file: None,
+                    line: 0,
                     original,
                     comment,
                     eol,
                     data: None,
-                }));
-            } else {
-                return None;
+                    invalid: false,
+                    shorthands: Shorthands::new(),
+                    raw: Vec::new(),
+                    partition: None,
+                }])
             }
-        }
-        let res = move || -> Self::Item {
-            if self.secondline {
-                let field1 = first.unwrap().try_into()?;
-                let first = FirstField {
-                    kind: FirstFieldKind::Continuation(Default::default()),
-                    double: false,
-                };
-                let field2 = self.next_single_field()?;
-                let field3 = self.next_single_field()?;
-                let field4 = self.next_single_field()?;
-                let field5 = self.next_single_field()?;
-                let field6 = self.next_single_field()?;
-                let field7 = self.next_single_field()?;
-                let field8 = self.next_single_field()?;
-                let trailing = self.next_trailing_field()?;
-                let mut original = vec![];
-                let (comment, eol) = match self.comment_and_eol() {
-                    Some((comment, eol)) => {
-                        std::mem::swap(&mut original, &mut self.original);
-                        (comment, eol)
+        };
+        let mut logical = vec![LogicalLine::new(first)];
+        let mut state = CommaState::Data;
+        for (i, field) in iter {
+            let line = logical.last_mut().unwrap();
+            let width = line.width();
+            match state {
+                CommaState::Data => {
+                    line.push(field, i)?;
+                    if line.fields.len() == width {
+                        state = CommaState::Trailing;
                     }
-                    None => (Default::default(), None),
-                };
-                Ok(UnparsedBulkLine {
-                    original,
-                    comment,
-                    eol,
-                    data: Some(UnparsedFieldData::Single(
-                        first,
-                        [
-                            field1, field2, field3, field4, field5, field6, field7, field8,
-                        ],
-                        trailing,
-                    )),
-                })
-            } else {
-                self.secondline = true;
-                let first: Option<FirstField> = first.unwrap().try_into()?;
-                let first = first.unwrap_or_default();
-                if first.double {
-                    let field1 = self.next_double_field()?;
-                    let field2 = self.next_double_field()?;
-                    let field3 = self.next_double_field()?;
-                    let field4 = self.next_double_field()?;
-                    let trailing = self.next_trailing_field()?;
-                    let mut original = vec![];
-                    let (comment, eol) = match self.comment_and_eol() {
-                        Some((comment, eol)) => {
-                            std::mem::swap(&mut original, &mut self.original);
-                            (comment, eol)
-                        }
-                        None => (Default::default(), None),
-                    };
-
-                    Ok(UnparsedBulkLine {
-                        original,
-                        comment,
-                        eol,
-                        data: Some(UnparsedFieldData::Double(
-                            first,
-                            [field1, field2, field3, field4],
-                            trailing,
-                        )),
-                    })
-                } else {
-                    let field1 = self.next_single_field()?;
-                    let field2 = self.next_single_field()?;
-                    let field3 = self.next_single_field()?;
-                    let field4 = self.next_single_field()?;
-                    let field5 = self.next_single_field()?;
-                    let field6 = self.next_single_field()?;
-                    let field7 = self.next_single_field()?;
-                    let field8 = self.next_single_field()?;
-                    let trailing = self.next_trailing_field()?;
-                    let mut original = vec![];
-                    let (comment, eol) = match self.comment_and_eol() {
-                        Some((comment, eol)) => {
-                            std::mem::swap(&mut original, &mut self.original);
-                            (comment, eol)
+                }
+                CommaState::Trailing => {
+                    let double = match field.text.first() {
+                        Some(b'+') => false,
+                        Some(b'*') => true,
+                        None => line.first.double,
+                        Some(_) => {
+                            // The line carries on with an implied continuation
+                            let mut next = LogicalLine::new(FirstField {
+                                kind: FirstFieldKind::Continuation(Default::default()),
+                                double: line.first.double,
+                            });
+                            next.push(field, i)?;
+                            logical.push(next);
+                            state = CommaState::Data;
+                            continue;
                         }
-                        None => (Default::default(), None),
                     };
-                    Ok(UnparsedBulkLine {
-                        original,
-                        comment,
-                        eol,
-                        data: Some(UnparsedFieldData::Single(
-                            first,
-                            [
-                                field1, field2, field3, field4, field5, field6, field7, field8,
-                            ],
-                            trailing,
-                        )),
-                    })
+                    let trailing = field.continuation().map_err(|e| field.error(e, i + 1))?;
+                    line.trailing = trailing;
+                    logical.push(LogicalLine::new(FirstField {
+                        kind: FirstFieldKind::Continuation(trailing),
+                        double,
+                    }));
+                    state = CommaState::Continued;
+                }
+                CommaState::Continued => {
+                    line.push(field, i)?;
+                    if line.fields.len() == width {
+                        state = CommaState::Trailing;
+                    } else if !field.text.is_empty() {
+                        state = CommaState::Data;
+                    }
                 }
             }
-        }();
-        Some(res)
+        }
+        if let CommaState::Continued = state {
+            logical.pop();
+        }
+        let n = logical.len();
+        let lines = logical
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut line)| {
+                line.fields.resize(line.width(), Field::Blank);
+                line.raw.resize(line.width(), RawField::default());
+                // The original text is kept with the first line and the
+                // comment with the last
+                let last = i + 1 == n;
+                BulkLine {
+                    file: None,
+                    line: 0,
+                    original: if i == 0 { original.clone() } else { vec![] },
+                    comment: if last {
+                        comment.clone()
+                    } else {
+                        Comment::new()
+                    },
+                    eol: if last { eol } else { None },
+                    data: Some((line.first, line.fields, line.trailing)),
+                    invalid: false,
+                    shorthands: line.shorthands,
+                    raw: line.raw,
+                    partition: None,
+                }
+            })
+            .collect();
+        Ok(lines)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Eol {
-    CrLf,
-    Lf,
+/// A logical line of a free field entry, which has the same fields as a
+/// fixed format line
+struct LogicalLine {
+    first: FirstField,
+    fields: Vec<Field>,
+    trailing: ContinuationField,
+    shorthands: Shorthands,
+    raw: Vec<RawField>,
 }
 
-impl Default for Eol {
-    fn default() -> Self {
-        Eol::CrLf
+impl LogicalLine {
+    fn new(first: FirstField) -> Self {
+        Self {
+            first,
+            fields: Vec::with_capacity(8),
+            trailing: ContinuationField::default(),
+            shorthands: Shorthands::new(),
+            raw: Vec::with_capacity(8),
+        }
+    }
+
+    fn width(&self) -> usize {
+        if self.first.double {
+            4
+        } else {
+            8
+        }
+    }
+
+    /// Adds a data field. `i` is the index of the field on the physical line
+    fn push(&mut self, field: &CommaField, i: usize) -> Result<()> {
+        let index = self.fields.len();
+        let value = field
+            .field(index, &mut self.shorthands)
+            .map_err(|e| field.error(e, i + 1))?;
+        self.fields.push(value);
+        self.raw.push(RawField::new(&field.text));
+        Ok(())
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Eol {
+    #[default]
+    CrLf,
+    Lf,
+}
+
 impl fmt::Display for Eol {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         #[allow(clippy::write_with_newline)]
@@ -402,34 +453,40 @@ pub enum UnparsedFieldData {
     Double(FirstField, [UnparsedDoubleField; 4], ContinuationField),
 }
 
-impl std::convert::TryFrom<UnparsedFieldData> for (FirstField, Vec<Field>, ContinuationField) {
-    type Error = Error;
-    fn try_from(field: UnparsedFieldData) -> Result<Self> {
-        match field {
-            UnparsedFieldData::Single(first, fields, trailing) => Ok((
-                first,
-                vec![
-                    (&fields[0]).try_into()?,
-                    (&fields[1]).try_into()?,
-                    (&fields[2]).try_into()?,
-                    (&fields[3]).try_into()?,
-                    (&fields[4]).try_into()?,
-                    (&fields[5]).try_into()?,
-                    (&fields[6]).try_into()?,
-                    (&fields[7]).try_into()?,
-                ],
-                trailing,
-            )),
-            UnparsedFieldData::Double(first, fields, trailing) => Ok((
-                first,
-                vec![
-                    (&fields[0]).try_into()?,
-                    (&fields[1]).try_into()?,
-                    (&fields[2]).try_into()?,
-                    (&fields[3]).try_into()?,
-                ],
-                trailing,
-            )),
+impl UnparsedFieldData {
+    /// Parses each field, attaching the position of the field to any error.
+    /// Replication shorthand is collected in `shorthands` and the text of each
+    /// field in `raw`
+    fn parse(
+        self,
+        shorthands: &mut Shorthands,
+        raw: &mut Vec<RawField>,
+    ) -> Result<(FirstField, Vec<Field>, ContinuationField)> {
+        match self {
+            UnparsedFieldData::Single(first, fields, trailing) => {
+                raw.extend(fields.iter().map(|f| RawField::new(&f.0)));
+                let fields = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        parse_data_field(&field.0, i, shorthands)
+                            .map_err(|e| e.at_columns(Some(i + 2), 9 + 8 * i, 8))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok((first, fields, trailing))
+            }
+            UnparsedFieldData::Double(first, fields, trailing) => {
+                raw.extend(fields.iter().map(|f| RawField::new(&f.0)));
+                let fields = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        parse_data_field(&field.0, i, shorthands)
+                            .map_err(|e| e.at_columns(Some(2 * i + 2), 9 + 16 * i, 16))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok((first, fields, trailing))
+            }
         }
     }
 }
@@ -495,6 +552,9 @@ impl fmt::Display for CardType {
 pub enum FirstFieldKind {
     Text(CardType),
     Continuation(ContinuationField),
+    /// `=` replicates the previous card using the shorthand in the data
+    /// fields. `=n` generates n more cards by applying the previous increments
+    Replicate(Option<u32>),
 }
 
 impl Default for FirstFieldKind {
@@ -518,6 +578,8 @@ impl fmt::Display for FirstFieldKind {
                 FirstFieldKind::Continuation(continuation) => {
                     write!(f, "+{}", continuation.0.as_bstr())
                 }
+                FirstFieldKind::Replicate(None) => write!(f, "{:<8}", "="),
+                FirstFieldKind::Replicate(Some(n)) => write!(f, "{:<8}", format!("={}", n)),
             }
         } else if width == 16 {
             match *self {
@@ -525,6 +587,7 @@ impl fmt::Display for FirstFieldKind {
                 FirstFieldKind::Continuation(continuation) => {
                     write!(f, "*{}", continuation.0.as_bstr())
                 }
+                replicate @ FirstFieldKind::Replicate(_) => write!(f, "{:8}", replicate),
             }
         } else {
             unreachable!()
@@ -558,6 +621,10 @@ impl fmt::Display for FirstField {
                 kind: Continuation(ContinuationField(t)),
                 double: true,
             } => write!(f, "*{}", t.as_bstr()),
+            FirstField {
+                kind: replicate @ Replicate(_),
+                ..
+            } => write!(f, "{:8}", replicate),
         }
     }
 }
@@ -590,8 +657,9 @@ impl fmt::Display for ContinuationField {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Field {
+    #[default]
     Blank,
     Int(i32),
     IntOrId(u32),
@@ -607,33 +675,8 @@ impl fmt::Display for Field {
             Some(16) => 16,
             Some(_) => return Err(fmt::Error),
         };
-        if width == 8 {
-            match *self {
-                Field::Blank => write!(f, "        "),
-                Field::Int(i) => write!(f, "{:<8}", i),
-                Field::IntOrId(i) => write!(f, "{:<8}", i),
-                Field::Float(d) => write!(f, "{:8}", float_to_8(d)),
-                Field::Double(d) => write!(f, "{:8}", float_to_8(d)),
-                Field::Text(s) => write!(f, "{:<8}", s.as_bstr()),
-            }
-        } else if width == 16 {
-            match *self {
-                Field::Blank => write!(f, "                "),
-                Field::Int(i) => write!(f, "{:<16}", i),
-                Field::IntOrId(i) => write!(f, "{:<16}", i),
-                Field::Float(d) => write!(f, "{:16}", float_to_16(d)),
-                Field::Double(d) => write!(f, "{:16}", float_to_16(d)),
-                Field::Text(s) => write!(f, "{:<16}", s.as_bstr()),
-            }
-        } else {
-            unreachable!()
-        }
-    }
-}
-
-impl Default for Field {
-    fn default() -> Self {
-        Field::Blank
+        let text = writer::format_field(self, width).map_err(|_| fmt::Error)?;
+        write!(f, "{:<width$}", text, width = width)
     }
 }
 
@@ -666,7 +709,7 @@ impl FieldConv for Field {
     }
     fn id(&self) -> Result<u32> {
         match self {
-            Field::IntOrId(v) => Ok(*v as u32),
+            Field::IntOrId(v) => Ok(*v),
             _ => Err(Error::UnexpectedField("id", *self)),
         }
     }
@@ -754,70 +797,99 @@ impl FieldConv for Option<Field> {
     }
 }
 
-fn float_to_8<T>(f: T) -> String
-where
-    T: Into<f64> + Copy + fmt::Display + fmt::LowerExp + dtoa::Floating + std::cmp::PartialOrd,
-{
-    // FIXME: can be improved
-    let mut buf = vec![b' '; 8];
-    let f = f.into();
-    if let Ok(_n) = dtoa::write(&mut buf[..], f) {
-        unsafe { String::from_utf8_unchecked(buf) }
-    } else {
-        let s = if f <= -1e+100 {
-            format!("{:<8.1e}", f)
-        } else if f < -1e+10 {
-            format!("{:<8.2e}", f)
-        } else if f < -1e+0 {
-            format!("{:<8.3e}", f)
-        } else if f < -1e-9 {
-            format!("{:<8.2e}", f)
-        } else if f < -1e-99 {
-            format!("{:<8.1e}", f)
-        } else if f < 0.0 {
-            format!("{:<8.0e}", f)
-        } else if f <= 1e-99 {
-            format!("{:<8.1e}", f)
-        } else if f <= 1e-9 {
-            format!("{:<8.2e}", f)
-        } else if f < 1e+0 {
-            format!("{:<8.3e}", f)
-        } else if f < 1e+10 {
-            format!("{:<8.4e}", f)
-        } else if f < 1e+100 {
-            format!("{:<8.3e}", f)
-        } else {
-            format!("{:<8.2e}", f)
-        };
-        if s.len() > 8 {
-            panic!("help '{}'", s)
+/// Replication shorthand in a data field, which refers to the same field of
+/// the previous card
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Shorthand {
+    /// `=` copies the field
+    Duplicate,
+    /// `==` copies the field and all of the fields following it
+    DuplicateRest,
+    /// `*x` or `*(x)` adds x to the field
+    Increment(Field),
+}
+
+/// Shorthand fields of a line or card by field index
+type Shorthands = SmallVec<[(usize, Shorthand); 2]>;
+
+fn parse_shorthand(field: &[u8]) -> Result<Option<Shorthand>> {
+    let field = field.trim();
+    let shorthand = match field.first() {
+        Some(b'=') if field == b"=" => Shorthand::Duplicate,
+        Some(b'=') if field == b"==" => Shorthand::DuplicateRest,
+        Some(b'*') => {
+            let value = &field[1..];
+            let value = value
+                .strip_prefix(b"(")
+                .and_then(|v| v.strip_suffix(b")"))
+                .unwrap_or(value);
+            match parse_inner_field(value.iter().copied()) {
+                Ok(
+                    value
+                    @ (Field::Int(_) | Field::IntOrId(_) | Field::Float(_) | Field::Double(_)),
+                ) => Shorthand::Increment(value),
+                _ => return Err(Error::InvalidShorthand(field.to_vec())),
+            }
         }
-        s
-    }
+        Some(b'=') => return Err(Error::InvalidShorthand(field.to_vec())),
+        _ => return Ok(None),
+    };
+    Ok(Some(shorthand))
 }
 
-fn float_to_16<T>(f: T) -> String
-where
-    T: Copy + fmt::Display + fmt::LowerExp + dtoa::Floating,
-{
-    // FIXME: can be improved
-    let mut buf = [b' '; 16];
-    if let Ok(n) = dtoa::write(&mut buf[..], f) {
-        unsafe { String::from_utf8_unchecked(buf[..n].to_vec()) }
-    } else {
-        let s = format!("{:16.8e}", f);
-        if s.len() > 16 {
-            panic!("Couldn't write {} in less than 16 chars '{}'", f, s)
+/// Parses a data field, recording any shorthand in `shorthands` and leaving
+/// the field blank until the card is expanded
+fn parse_data_field(field: &[u8], index: usize, shorthands: &mut Shorthands) -> Result<Field> {
+    match parse_shorthand(field)? {
+        Some(shorthand) => {
+            shorthands.push((index, shorthand));
+            Ok(Field::Blank)
         }
-        s
+        None => parse_inner_field(field.iter().copied()),
     }
 }
 
+/// Adds an increment to the value of a field from the previous card
+fn increment(value: Field, by: Field) -> Result<Field> {
+    let int = |v: Field| match v {
+        Field::Int(i) => Some(i as i64),
+        Field::IntOrId(i) => Some(i as i64),
+        _ => None,
+    };
+    let real = |v: Field| match v {
+        Field::Float(f) => Some(f as f64),
+        Field::Double(d) => Some(d),
+        v => int(v).map(|i| i as f64),
+    };
+    let incremented = match value {
+        Field::Float(v) => real(by).map(|by| Field::Float(v + by as f32)),
+        Field::Double(v) => real(by).map(|by| Field::Double(v + by)),
+        value => match (int(value), int(by)) {
+            // Unsigned integers parse as ids so keep the results consistent
+            (Some(v), Some(by)) => i32::try_from(v + by).ok().map(|i| match u32::try_from(i) {
+                Ok(id) => Field::IntOrId(id),
+                Err(_) => Field::Int(i),
+            }),
+            _ => None,
+        },
+    };
+    incremented.ok_or(Error::InvalidIncrement(value, by))
+}
+
+#[derive(Debug)]
 pub struct BulkLine {
+    pub file: Option<Arc<Path>>,
+    pub line: usize,
     pub original: Vec<u8>,
     pub comment: Comment,
     pub eol: Option<Eol>,
     pub data: Option<(FirstField, Vec<Field>, ContinuationField)>,
+    /// Set for lines that failed to parse and were only partially recovered
+    pub(crate) invalid: bool,
+    shorthands: Shorthands,
+    raw: Vec<RawField>,
+    /// Set for `BEGIN` statements, which start a new partition
+    pub partition: Option<Partition>,
 }
 
 enum ZeroOneTwo {
@@ -827,6 +899,23 @@ enum ZeroOneTwo {
 }
 
 fn parse_first_field(field: [u8; 8]) -> Result<Option<FirstField>> {
+    if field[0] == b'=' {
+        let count = field[1..].trim();
+        let count = count
+            .strip_prefix(b"(")
+            .and_then(|c| c.strip_suffix(b")"))
+            .unwrap_or(count);
+        let count = if count.is_empty() {
+            None
+        } else {
+            let count = count.to_str().ok().and_then(|c| c.parse().ok());
+            Some(count.ok_or_else(|| Error::InvalidShorthand(field.to_vec()))?)
+        };
+        return Ok(Some(FirstField {
+            kind: FirstFieldKind::Replicate(count),
+            double: false,
+        }));
+    }
     enum State {
         Start,
         Blank,
@@ -852,7 +941,7 @@ fn parse_first_field(field: [u8; 8]) -> Result<Option<FirstField>> {
             (Blank, b' ', _) => (Blank, Zero),
             (Alpha, c @ b'A'..=b'Z', _) => (Alpha, One(c)),
             (Alpha, c @ b'0'..=b'9', _) => (Alpha, One(c)),
-            (Alpha, c @ b' ', _) => (EndAlpha, One(c)),
+            (Alpha, b' ', _) => (EndAlpha, Zero),
             (Alpha, b'*', _) => {
                 double = true;
                 (EndAlpha, Zero)
@@ -925,7 +1014,8 @@ where
     use State::*;
     use ZeroOneTwo::*;
     let mut state = State::Start;
-    let mut contents = [b' '; 16];
+    // A 16 character field may grow by one when the implied exponent is added
+    let mut contents = [b' '; 17];
     let mut i = 0;
     for c in field {
         let (s, c) = match (state, c, i) {
@@ -991,6 +1081,14 @@ where
             (_, c, _) => return Err(Error::UnexpectedChar(c)),
         };
         state = s;
+        let n = match c {
+            Zero => 0,
+            One(_) => 1,
+            Two(_, _) => 2,
+        };
+        if i + n > contents.len() {
+            return Err(Error::TextTooLong(contents[..i].to_vec()));
+        }
         match c {
             Zero => {}
             One(c1) => {
@@ -1068,7 +1166,7 @@ fn parse_trailing_field(field: [u8; 8]) -> Result<ContinuationField> {
             (Start, b' ', _) => (Blank, Zero),
             (Start, c @ b'A'..=b'Z', _) => (Middle, One(c)),
             (Start, c @ b'0'..=b'9', _) => (Middle, One(c)),
-            (Start, b'+', _) => (Middle, Zero),
+            (Start, b'+' | b'*', _) => (Middle, Zero),
             (Middle, c @ b'A'..=b'Z', 6) => (End, One(c)),
             (Middle, c @ b'0'..=b'9', 6) => (End, One(c)),
             (Middle, c @ b'A'..=b'Z', _) => (Middle, One(c)),
@@ -1126,15 +1224,23 @@ impl std::convert::TryFrom<UnparsedBulkLine> for BulkLine {
             eol,
             data,
         } = unparsed;
+        let mut shorthands = Shorthands::new();
+        let mut raw = Vec::new();
         let data = match data {
             None => None,
-            Some(field) => Some(field.try_into()?),
+            Some(field) => Some(field.parse(&mut shorthands, &mut raw)?),
         };
         Ok(BulkLine {
+            file: None,
+            line: 0,
             original,
             comment,
             eol,
             data,
+            invalid: false,
+            shorthands,
+            raw,
+            partition: None,
         })
     }
 }
@@ -1143,21 +1249,109 @@ impl std::convert::TryFrom<UnparsedBulkLine> for BulkLine {
 pub struct BulkCardData {
     first: CardType,
     fields: Vec<Field>,
+    raw: Vec<RawField>,
 }
 
+/// Placeholder card type for `=` entries until they're expanded
+const REPLICATED: CardType = CardType(*b"=      ");
+
 #[derive(Debug, PartialEq)]
 pub struct BulkCard {
     data: Option<BulkCardData>,
     comment: Comment,
     eol: Eol,
     original: Vec<u8>,
+    file: Option<Arc<Path>>,
+    line: usize,
+    invalid: bool,
+    /// Replication shorthand waiting to be expanded
+    shorthands: Shorthands,
+    /// The count from an `=n` entry waiting to be expanded
+    repeat: Option<u32>,
+    replication: Option<Replication>,
+    /// Lines of any continuations, which may not follow the first line
+    continuation_lines: SmallVec<[usize; 2]>,
+    partition: Partition,
+    /// Set for the `BEGIN` statement that starts a partition
+    begin: bool,
+}
+
+/// Where a card generated from replication shorthand got its values from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replication {
+    file: Option<Arc<Path>>,
+    line: usize,
+    index: usize,
+}
+
+impl Replication {
+    /// The file of the card that was replicated
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// The line of the card that was replicated
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Which of the cards generated by an `=n` entry this is, starting from
+    /// 1. Cards with their own shorthand fields have an index of 0
+    pub fn index(&self) -> usize {
+        self.index
+    }
 }
 
 impl BulkCard {
+    /// Creates a card that wasn't read from a file. The card type is
+    /// uppercased and must be at most 7 alphanumeric characters
+    pub fn new(card_type: &[u8], fields: Vec<Field>) -> Result<Self> {
+        if card_type.len() > 7 {
+            return Err(Error::TextTooLong(card_type.to_vec()));
+        }
+        if card_type.is_empty() || !card_type.iter().all(u8::is_ascii_alphanumeric) {
+            return Err(Error::InvalidField);
+        }
+        let mut first = CardType::default();
+        first.0[..card_type.len()].copy_from_slice(card_type);
+        first.0.make_ascii_uppercase();
+        let raw = fields.iter().map(RawField::from_field).collect();
+        Ok(Self {
+            data: Some(BulkCardData { first, fields, raw }),
+            comment: Comment::new(),
+            eol: Eol::Lf,
+            original: Vec::new(),
+            file: None,
+            line: 0,
+            invalid: false,
+            shorthands: Shorthands::new(),
+            repeat: None,
+            replication: None,
+            continuation_lines: SmallVec::new(),
+            partition: Partition::Main,
+            begin: false,
+        })
+    }
+
     pub fn original(&self) -> &[u8] {
         &self.original
     }
 
+    /// The file the first line of this card was read from, if it was read from a file
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// The 1-based line number of the first line of this card within its file
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The location of the first line of this card, for use in error messages
+    pub fn location(&self) -> Location {
+        Location::new(self.file.clone(), self.line, &self.original)
+    }
+
     pub fn card_type(&self) -> Option<[u8; 7]> {
         self.data.as_ref().map(|d| d.first.0)
     }
@@ -1168,60 +1362,92 @@ impl BulkCard {
             None => &[],
         }
     }
+
+    /// Sets the field at `index`, where 0 is the first field following the
+    /// card type, adding blank fields as needed. Does nothing for blank or
+    /// comment lines
+    pub fn set_field(&mut self, index: usize, field: Field) {
+        if let Some(data) = self.data.as_mut() {
+            if data.fields.len() <= index {
+                data.fields.resize(index + 1, Field::Blank);
+                data.raw.resize(index + 1, RawField::default());
+            }
+            data.fields[index] = field;
+            data.raw[index] = RawField::from_field(&field);
+        }
+    }
+
+    /// The text of each field as it was written, in the same order as
+    /// `fields`
+    pub fn raw_fields(&self) -> &[RawField] {
+        match self.data.as_ref() {
+            Some(data) => data.raw.as_slice(),
+            None => &[],
+        }
+    }
+
+    /// Decodes the fields according to what each one is allowed to hold, as
+    /// given by `schema` starting from field 2. Fields past the end of
+    /// `schema` may hold any value
+    pub fn decode(&self, schema: &[FieldKind]) -> Result<Vec<Field>> {
+        self.raw_fields()
+            .iter()
+            .enumerate()
+            .map(|(i, raw)| {
+                let kind = schema.get(i).copied().unwrap_or(FieldKind::Any);
+                raw.decode(kind)
+                    .map_err(|e| e.at(self.location().with_field_number(i + 2)))
+            })
+            .collect()
+    }
+
+    pub fn eol(&self) -> Eol {
+        self.eol
+    }
+
+    pub fn set_eol(&mut self, eol: Eol) {
+        self.eol = eol
+    }
+
+    /// The line numbers of every line this card was read from, starting with
+    /// the first line
+    pub fn lines(&self) -> impl Iterator<Item = usize> + '_ {
+        std::iter::once(self.line).chain(self.continuation_lines.iter().copied())
+    }
+
+    /// For cards generated from replication shorthand, the card they were
+    /// replicated from. The location of the card itself is the line with the
+    /// shorthand
+    pub fn replication(&self) -> Option<&Replication> {
+        self.replication.as_ref()
+    }
+
+    /// The partition of the bulk data the card is in, set by the last `BEGIN`
+    /// statement before it
+    pub fn partition(&self) -> Partition {
+        self.partition
+    }
+
+    pub fn set_partition(&mut self, partition: Partition) {
+        self.partition = partition
+    }
+
+    /// Whether this is the `BEGIN` statement that starts its partition rather
+    /// than a card. These have no data
+    pub fn is_begin(&self) -> bool {
+        self.begin
+    }
 }
 
 impl fmt::Display for BulkCard {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.data {
-            Some(BulkCardData { first, fields }) => {
-                let mut first = FirstFieldKind::Text(*first);
-                let mut fields = &fields[..];
-                loop {
-                    let n8 = std::cmp::min(8, fields.len());
-                    let (next8, fields_) = fields.split_at(n8);
-                    fields = fields_;
-                    if next8.iter().any(|f| matches!(f, Field::Double(_))) {
-                        let n4 = std::cmp::min(4, n8);
-                        let (first4, last4) = next8.split_at(n4);
-                        write!(f, "{:16}", first)?;
-                        for c in first4 {
-                            write!(f, "{:16}", c)?;
-                        }
-                        if n8 > 4 {
-                            // Using 8 here makes it output a plus
-                            // write!(f, "{:8}{}", ContinuationField::default(), self.eol)?;
-                            writeln!(f, "{:8}", ContinuationField::default())?;
-                            write!(f, "{:16}", ContinuationField::default())?;
-                            for c in last4 {
-                                write!(f, "{:16}", c)?;
-                            }
-                            if fields.is_empty() {
-                                // break write!(f, "{:8}{}{}", "", self.comment, self.eol);
-                                break writeln!(f, "{:8}{}", "", self.comment);
-                            } else {
-                                // write!(f, "{:8}{}", ContinuationField::default(), self.eol)?;
-                                write!(f, "{:8}", ContinuationField::default())?;
-                            }
-                        }
-                    } else {
-                        write!(f, "{:8}", first)?;
-                        for c in next8 {
-                            write!(f, "{:8}", c)?;
-                        }
-                        if fields.is_empty() {
-                            // break write!(f, "{:8}{}{}", "", self.comment, self.eol);
-                            break writeln!(f, "{:8}{}", "", self.comment);
-                        } else {
-                            // write!(f, "{:8}{}", ContinuationField::default(), self.eol)?;
-                            writeln!(f, "{:8}", ContinuationField::default())?;
-                        }
-                    }
-                    first = FirstFieldKind::Continuation(ContinuationField::default());
-                }
-            }
-            // None => write!(f, "{}{}", self.comment, self.eol),
-            None => writeln!(f, "{}", self.comment),
-        }
+        let mut continuations = 0;
+        let text = writer::format_card(self, FieldFormat::Auto, &mut || {
+            continuations += 1;
+            continuations
+        })
+        .map_err(|_| fmt::Error)?;
+        f.write_str(&text)
     }
 }
 
@@ -1236,20 +1462,60 @@ impl CardState {
     }
 }
 
+/// The most recent card, which replication shorthand refers to
+struct PreviousCard {
+    first: CardType,
+    fields: Vec<Field>,
+    raw: Vec<RawField>,
+    file: Option<Arc<Path>>,
+    line: usize,
+    /// The shorthand used to generate this card, which `=n` repeats
+    shorthands: Shorthands,
+}
+
+/// An `=n` entry whose copies are generated one at a time
+struct Repeat {
+    card: BulkCard,
+    /// The index of the next copy, counting from 1
+    index: usize,
+    count: usize,
+}
+
 struct BulkCardIter<I> {
     lines: I,
+    /// When set, continuations must directly follow the card they continue
+    /// and at most this many cards and comment lines are held waiting for
+    /// continuations
+    pending_limit: Option<usize>,
     counter: usize,
     continuations: HashMap<ContinuationField, usize>,
     deque: std::collections::VecDeque<CardState>,
+    previous: Option<PreviousCard>,
+    expanded: Option<BulkCard>,
+    repeat: Option<Repeat>,
+    /// The partition set by the last `BEGIN` statement
+    partition: Partition,
 }
 
 impl<I> BulkCardIter<I> {
     fn new(lines: I) -> Self {
         Self {
             lines,
+            pending_limit: None,
             continuations: HashMap::new(),
             counter: 0,
             deque: std::collections::VecDeque::new(),
+            previous: None,
+            expanded: None,
+            repeat: None,
+            partition: Partition::Main,
+        }
+    }
+
+    fn streaming(lines: I, pending_limit: usize) -> Self {
+        Self {
+            pending_limit: Some(pending_limit),
+            ..Self::new(lines)
         }
     }
 
@@ -1274,11 +1540,16 @@ impl<I> BulkCardIter<I> {
         self.next_counter()
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn append_continuation(
         &mut self,
         continuation: ContinuationField,
         new_fields: &[Field],
+        new_raw: &[RawField],
+        new_shorthands: &Shorthands,
         trailing: ContinuationField,
+        invalid: bool,
+        line: usize,
     ) -> Result<()> {
         match self.continuations.remove(&continuation) {
             Some(i) => {
@@ -1286,11 +1557,24 @@ impl<I> BulkCardIter<I> {
                     Some(CardState {
                         card:
                             BulkCard {
-                                data: Some(BulkCardData { fields, .. }),
+                                data: Some(BulkCardData { fields, raw, .. }),
+                                invalid: card_invalid,
+                                shorthands,
+                                continuation_lines,
+                                line: card_line,
                                 ..
                             },
                         ..
-                    }) => fields.extend_from_slice(new_fields),
+                    }) => {
+                        let offset = fields.len();
+                        shorthands.extend(new_shorthands.iter().map(|&(i, s)| (i + offset, s)));
+                        fields.extend_from_slice(new_fields);
+                        raw.extend_from_slice(new_raw);
+                        *card_invalid |= invalid;
+                        if line != *card_line && continuation_lines.last() != Some(&line) {
+                            continuation_lines.push(line);
+                        }
+                    }
                     _ => unreachable!(),
                 }
                 if let Some(i) = self.continuations.insert(trailing, i) {
@@ -1309,13 +1593,30 @@ impl<I> BulkCardIter<I> {
         }
     }
 
-    fn insert_blank(&mut self, original: Vec<u8>, comment: Comment, eol: Option<Eol>) {
+    fn insert_blank(
+        &mut self,
+        original: Vec<u8>,
+        comment: Comment,
+        eol: Option<Eol>,
+        file: Option<Arc<Path>>,
+        line: usize,
+        invalid: bool,
+    ) {
         self.deque.push_back(CardState {
             card: BulkCard {
                 data: None,
                 original,
                 comment,
-                eol: eol.unwrap_or_default(),
+                eol: eol.unwrap_or(Eol::Lf),
+                file,
+                line,
+                invalid,
+                shorthands: Shorthands::new(),
+                repeat: None,
+                replication: None,
+                continuation_lines: SmallVec::new(),
+                partition: self.partition,
+                begin: false,
             },
             complete: true,
         });
@@ -1328,6 +1629,17 @@ impl<I> BulkCardIter<I> {
         }
     }
 
+    /// Completes all pending cards when streaming and either a new card has
+    /// started or too many lines are pending
+    fn complete_pending(&mut self, new_card: bool) {
+        if let Some(limit) = self.pending_limit {
+            if new_card || self.deque.len() > limit {
+                self.complete();
+                self.continuations.clear();
+            }
+        }
+    }
+
     fn next_complete(&mut self) -> Option<BulkCard> {
         match self.deque.pop_front() {
             Some(CardState {
@@ -1343,12 +1655,13 @@ impl<I> BulkCardIter<I> {
     }
 }
 
-impl<I> Iterator for BulkCardIter<I>
+impl<I> BulkCardIter<I>
 where
     I: Iterator<Item = Result<BulkLine>>,
 {
-    type Item = Result<BulkCard>;
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Returns the next card with its continuations merged but without any
+    /// replication shorthand expanded
+    fn next_card(&mut self) -> Option<Result<BulkCard>> {
         if let Some(c) = self.next_complete() {
             return Some(Ok(c));
         }
@@ -1358,97 +1671,555 @@ where
                 Err(e) => return Some(Err(e)),
             };
             let BulkLine {
+                file,
+                line,
                 data,
                 original,
                 comment,
                 eol,
+                invalid,
+                shorthands,
+                raw,
+                partition,
             } = line;
-            match data {
-                Some((first, fields, trailing)) => match first.kind {
-                    FirstFieldKind::Text(first) => self.insert(
+            if let Some(partition) = partition {
+                // Continuations don't carry over into another partition
+                self.complete();
+                self.continuations.clear();
+                self.partition = partition;
+            }
+            let (first, fields, trailing) = match data {
+                Some(data) => data,
+                None => {
+                    self.insert_blank(original, comment, eol, file, line, invalid);
+                    if let (Some(state), Some(_)) = (self.deque.back_mut(), partition) {
+                        state.card.begin = true;
+                    }
+                    self.complete_pending(false);
+                    if let Some(c) = self.next_complete() {
+                        return Some(Ok(c));
+                    }
+                    continue;
+                }
+            };
+            let (first, repeat) = match first.kind {
+                FirstFieldKind::Text(first) => (first, None),
+                FirstFieldKind::Replicate(repeat) => (REPLICATED, repeat),
+                FirstFieldKind::Continuation(field) => {
+                    let appended = self.append_continuation(
+                        field,
+                        &fields,
+                        &raw,
+                        &shorthands,
                         trailing,
-                        BulkCard {
-                            data: Some(BulkCardData { first, fields }),
-                            original,
-                            comment,
-                            eol: eol.unwrap_or_default(),
-                        },
-                    ),
-                    FirstFieldKind::Continuation(field) => {
-                        if let Err(e) = self.append_continuation(field, &fields, trailing) {
-                            return Some(Err(e));
-                        }
+                        invalid,
+                        line,
+                    );
+                    if let Err(e) = appended {
+                        let location = Location::new(file, line, &original);
+                        return Some(Err(e.at(location.with_field(1, 1, 8))));
+                    }
+                    if let Some(c) = self.next_complete() {
+                        return Some(Ok(c));
                     }
+                    continue;
+                }
+            };
+            self.complete_pending(true);
+            self.insert(
+                trailing,
+                BulkCard {
+                    data: Some(BulkCardData { first, fields, raw }),
+                    original,
+                    comment,
+                    eol: eol.unwrap_or(Eol::Lf),
+                    file,
+                    line,
+                    invalid,
+                    shorthands,
+                    repeat,
+                    replication: None,
+                    continuation_lines: SmallVec::new(),
+                    partition: self.partition,
+                    begin: false,
                 },
-                None => self.insert_blank(original, comment, eol),
+            );
+            if let Some(c) = self.next_complete() {
+                return Some(Ok(c));
             }
         }
         self.complete();
         self.next_complete().map(Ok)
     }
+
+    /// Expands any replication shorthand in `card` using the previous card
+    fn expand(&mut self, card: BulkCard) -> Result<()> {
+        let data = match &card.data {
+            Some(data) => data,
+            None => {
+                self.expanded = Some(card);
+                return Ok(());
+            }
+        };
+        if data.first != REPLICATED && card.shorthands.is_empty() {
+            self.previous = Some(PreviousCard {
+                first: data.first,
+                fields: data.fields.clone(),
+                raw: data.raw.clone(),
+                file: card.file.clone(),
+                line: card.line,
+                shorthands: Shorthands::new(),
+            });
+            self.expanded = Some(card);
+            return Ok(());
+        }
+        let location = card.location();
+        let previous = match self.previous.as_mut() {
+            Some(previous) => previous,
+            None => return Err(Error::NoReplicationSource.at(location)),
+        };
+        if let Some(n) = card.repeat {
+            if n > 0 {
+                self.repeat = Some(Repeat {
+                    card,
+                    index: 1,
+                    count: n as usize,
+                });
+            }
+            return Ok(());
+        }
+        let first = if data.first == REPLICATED {
+            previous.first
+        } else {
+            data.first
+        };
+        let mut fields = data.fields.clone();
+        let mut raw = data.raw.clone();
+        for &(i, shorthand) in &card.shorthands {
+            let from = previous.fields.get(i).copied().unwrap_or_default();
+            match shorthand {
+                Shorthand::Duplicate => {
+                    fields[i] = from;
+                    raw[i] = previous.raw.get(i).cloned().unwrap_or_default();
+                }
+                Shorthand::Increment(by) => {
+                    fields[i] = increment(from, by).map_err(|e| e.at(location.clone()))?;
+                    raw[i] = RawField::from_field(&fields[i]);
+                }
+                Shorthand::DuplicateRest => {
+                    fields.truncate(i);
+                    fields.extend_from_slice(previous.fields.get(i..).unwrap_or_default());
+                    raw.truncate(i);
+                    raw.extend_from_slice(previous.raw.get(i..).unwrap_or_default());
+                    break;
+                }
+            }
+        }
+        let replication = Some(Replication {
+            file: previous.file.clone(),
+            line: previous.line,
+            index: 0,
+        });
+        *previous = PreviousCard {
+            first,
+            fields: fields.clone(),
+            raw: raw.clone(),
+            file: card.file.clone(),
+            line: card.line,
+            shorthands: card.shorthands,
+        };
+        self.expanded = Some(BulkCard {
+            data: Some(BulkCardData { first, fields, raw }),
+            shorthands: Shorthands::new(),
+            replication,
+            ..card
+        });
+        Ok(())
+    }
+
+    /// Generates the next copy of the previous card for an `=n` entry, so
+    /// that a large count doesn't hold every copy in memory
+    fn next_repeated(&mut self) -> Option<Result<BulkCard>> {
+        let mut repeat = self.repeat.take()?;
+        let card = &repeat.card;
+        let index = repeat.index;
+        // The previous card is always set before an `=n` entry is accepted
+        let previous = self.previous.as_mut()?;
+        let mut fields = previous.fields.clone();
+        let mut raw = previous.raw.clone();
+        for &(i, shorthand) in &previous.shorthands {
+            if let (Shorthand::Increment(by), Some(field)) = (shorthand, fields.get_mut(i)) {
+                *field = match increment(*field, by) {
+                    Ok(field) => field,
+                    Err(e) => return Some(Err(e.at(card.location()))),
+                };
+                raw[i] = RawField::from_field(field);
+            }
+        }
+        let copy = BulkCard {
+            data: Some(BulkCardData {
+                first: previous.first,
+                fields: fields.clone(),
+                raw: raw.clone(),
+            }),
+            comment: if index == 1 {
+                card.comment.clone()
+            } else {
+                Comment::new()
+            },
+            eol: card.eol,
+            original: card.original.clone(),
+            file: card.file.clone(),
+            line: card.line,
+            invalid: card.invalid,
+            shorthands: Shorthands::new(),
+            repeat: None,
+            replication: Some(Replication {
+                file: previous.file.clone(),
+                line: previous.line,
+                index,
+            }),
+            continuation_lines: card.continuation_lines.clone(),
+            partition: card.partition,
+            begin: false,
+        };
+        previous.fields = fields;
+        previous.raw = raw;
+        if index < repeat.count {
+            repeat.index += 1;
+            self.repeat = Some(repeat);
+        }
+        Some(Ok(copy))
+    }
 }
 
-#[cfg(feature = "parallel")]
+impl<I> Iterator for BulkCardIter<I>
+where
+    I: Iterator<Item = Result<BulkLine>>,
+{
+    type Item = Result<BulkCard>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(card) = self.expanded.take() {
+                return Some(Ok(card));
+            }
+            if self.repeat.is_some() {
+                return self.next_repeated();
+            }
+            let expanded = match self.next_card()? {
+                Ok(card) => self.expand(card),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = expanded {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Parses a single physical line. Free field lines may produce more than one
+/// `BulkLine`
+fn parse_line(line: &SourceLine, options: &ParserOptions) -> Result<Vec<BulkLine>> {
+    let SourceLine { file, line, text } = line;
+    let line = *line;
+    if let Some(partition) = partition(text) {
+        return begin_line(file, line, text, partition, options);
+    }
+    let n = std::cmp::min(text.len(), 10);
+    let lines = if text[..n].contains(&b',') {
+        NastranCommaLine::new(text, options).into_lines(text.clone())
+    } else {
+        NastranLine::new(text, options)
+            .try_into()
+            .and_then(|line: UnparsedBulkLine| Ok(vec![line.try_into()?]))
+    };
+    let mut lines = lines.map_err(|e| {
+        e.at(Location::new(file.clone(), line, text).with_tab_width(options.tab_width))
+    })?;
+    for l in &mut lines {
+        l.file = file.clone();
+        l.line = line;
+        if options.eol.is_some() {
+            l.eol = options.eol;
+        }
+    }
+    Ok(lines)
+}
+
+/// A line for a `BEGIN` statement, with any comment following it
+fn begin_line(
+    file: &Option<Arc<Path>>,
+    line: usize,
+    text: &[u8],
+    partition: Result<Partition>,
+    options: &ParserOptions,
+) -> Result<Vec<BulkLine>> {
+    let partition = partition.map_err(|e| e.at(Location::new(file.clone(), line, text)))?;
+    let (statement, crlf) = match text.strip_suffix(b"\r") {
+        Some(statement) => (statement, true),
+        None => (text, false),
+    };
+    let mut comment = Comment::new();
+    if let Some(i) = statement.find_byte(b'$') {
+        statement[i..].iter().for_each(|&c| comment.push(c));
+    }
+    let eol = options.eol.or(Some(if crlf { Eol::CrLf } else { Eol::Lf }));
+    Ok(vec![BulkLine {
+        file: file.clone(),
+        line,
+        original: text.to_vec(),
+        comment,
+        eol,
+        data: None,
+        invalid: false,
+        shorthands: Shorthands::new(),
+        raw: Vec::new(),
+        partition: Some(partition),
+    }])
+}
+
+/// Parses a bulk data file, expanding any INCLUDE statements in place. If the
+/// file has a `BEGIN BULK` statement only the cards following it are parsed.
+/// Parsing stops at `ENDDATA`
 pub fn parse_file(
     filename: impl AsRef<std::path::Path>,
+) -> Result<impl Iterator<Item = Result<BulkCard>>> {
+    parse_file_with(filename, &ParserOptions::default())
+}
+
+/// Like `parse_file` but with settings for how lines are read
+pub fn parse_file_with(
+    filename: impl AsRef<std::path::Path>,
+    options: &ParserOptions,
+) -> Result<impl Iterator<Item = Result<BulkCard>>> {
+    let t = std::time::Instant::now();
+    let source = source::read_with_includes(filename)?;
+    tracing::debug!("Read file took {} ms", t.elapsed().as_millis());
+    parse_source(bulk_lines(source), options)
+}
+
+/// Parses bulk data held in memory. Relative INCLUDE paths are resolved
+/// against the current directory
+pub fn parse_buffer(buffer: &[u8]) -> Result<impl Iterator<Item = Result<BulkCard>>> {
+    parse_buffer_with(buffer, &ParserOptions::default())
+}
+
+/// Like `parse_buffer` but with settings for how lines are read
+pub fn parse_buffer_with(
+    buffer: &[u8],
+    options: &ParserOptions,
+) -> Result<impl Iterator<Item = Result<BulkCard>>> {
+    let source = source::read_buffer_with_includes(buffer)?;
+    parse_source(bulk_lines(source), options)
+}
+
+/// Parses bulk data from any reader, such as stdin or a pipe. The whole input
+/// is read before parsing starts
+pub fn parse_reader(reader: impl io::Read) -> Result<impl Iterator<Item = Result<BulkCard>>> {
+    parse_reader_with(reader, &ParserOptions::default())
+}
+
+/// Like `parse_reader` but with settings for how lines are read
+pub fn parse_reader_with(
+    mut reader: impl io::Read,
+    options: &ParserOptions,
+) -> Result<impl Iterator<Item = Result<BulkCard>>> {
+    let mut buffer = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_buffer_with(&buffer, options)
+}
+
+#[cfg(feature = "parallel")]
+pub(crate) fn parse_source(
+    source: Vec<SourceLine>,
+    options: &ParserOptions,
 ) -> Result<impl Iterator<Item = Result<BulkCard>>> {
     use rayon::prelude::*;
     let t = std::time::Instant::now();
-    let bytes = std::fs::read(filename)?;
-    println!("Read file took {} ms", t.elapsed().as_millis());
+    let lines = source
+        .into_par_iter()
+        .map(|line| parse_line(&line, options))
+        .collect::<Result<Vec<_>>>()?;
+    tracing::debug!("Line parsing took {} ms", t.elapsed().as_millis());
+    Ok(BulkCardIter::new(lines.into_iter().flatten().map(Ok)))
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn parse_source(
+    source: Vec<SourceLine>,
+    options: &ParserOptions,
+) -> Result<impl Iterator<Item = Result<BulkCard>>> {
     let t = std::time::Instant::now();
-    let lines = bytes.par_split(|&c| c == b'\n').map(|line| {
-        let original = line.to_vec();
-        let n = std::cmp::min(original.len(), 10);
-        if original[..n].contains(&b',') {
-            // FIXME this is currently dropping continuations for commas
-            let mut lines = NastranCommaLine::new(line.to_vec())
-                .map(|r| r.and_then(TryInto::try_into))
-                .collect::<Result<Vec<BulkLine>>>()?;
-            Ok(lines.pop().unwrap())
-        } else {
-            let line: Result<UnparsedBulkLine> = NastranLine::new(line.to_vec()).try_into();
-            line.and_then(TryInto::try_into)
+    let lines = source
+        .into_iter()
+        .map(|line| parse_line(&line, options))
+        .collect::<Result<Vec<_>>>()?;
+    tracing::debug!("Line parsing took {} ms", t.elapsed().as_millis());
+    Ok(BulkCardIter::new(lines.into_iter().flatten().map(Ok)))
+}
+
+/// Parses a line in lenient mode. If the line can't be parsed the error is
+/// returned along with a line containing only the card name and continuation
+/// so that the rest of the card can still be matched up with it
+fn parse_line_lenient(
+    line: &SourceLine,
+    options: &ParserOptions,
+) -> (Vec<BulkLine>, Option<Error>) {
+    match parse_line(line, options) {
+        Ok(lines) => (lines, None),
+        Err(e) => (vec![salvage_line(line, options)], Some(e)),
+    }
+}
+
+fn salvage_line(line: &SourceLine, options: &ParserOptions) -> BulkLine {
+    let text = &line.text;
+    let n = std::cmp::min(text.len(), 10);
+    let (first, trailing) = if text[..n].contains(&b',') {
+        let first = NastranCommaLine::new(text, options).first_field();
+        (first, ContinuationField::default())
+    } else {
+        let mut line = NastranLine::new(text, options);
+        let first = parse_first_field(line.take8()).ok().flatten();
+        for _ in 0..8 {
+            line.take8();
         }
-    });
-    let lines = lines.collect::<Result<Vec<_>>>()?;
-    println!("Line parsing took {} ms", t.elapsed().as_millis());
-    Ok(BulkCardIter::new(lines.into_iter().map(Ok)))
+        let trailing = parse_trailing_field(line.take8()).unwrap_or_default();
+        (first, trailing)
+    };
+    // A blank first field is an implicit continuation
+    let first = first.or_else(|| Some(FirstField::default()).filter(|_| !text.trim().is_empty()));
+    BulkLine {
+        file: line.file.clone(),
+        line: line.line,
+        original: text.clone(),
+        comment: Comment::new(),
+        eol: None,
+        data: first.map(|first| (first, vec![], trailing)),
+        invalid: true,
+        shorthands: Shorthands::new(),
+        raw: Vec::new(),
+        partition: None,
+    }
+}
+
+/// The result of parsing a file in lenient mode
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    /// Cards that were parsed without any errors
+    pub cards: Vec<BulkCard>,
+    /// Cards that had at least one line that couldn't be parsed. Only the
+    /// fields from the valid lines are kept
+    pub quarantined: Vec<BulkCard>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[cfg(feature = "parallel")]
+fn parse_lines_lenient(
+    source: &[SourceLine],
+    options: &ParserOptions,
+) -> Vec<(Vec<BulkLine>, Option<Error>)> {
+    use rayon::prelude::*;
+    source
+        .par_iter()
+        .map(|line| parse_line_lenient(line, options))
+        .collect()
 }
 
 #[cfg(not(feature = "parallel"))]
-pub fn parse_file(
+fn parse_lines_lenient(
+    source: &[SourceLine],
+    options: &ParserOptions,
+) -> Vec<(Vec<BulkLine>, Option<Error>)> {
+    source
+        .iter()
+        .map(|line| parse_line_lenient(line, options))
+        .collect()
+}
+
+/// Parses a bulk data file without stopping at the first error. Lines that
+/// can't be parsed are reported as diagnostics and the cards they belong to
+/// are quarantined. Only failing to read `filename` itself is an error
+pub fn parse_file_lenient(filename: impl AsRef<std::path::Path>) -> Result<ParseReport> {
+    parse_file_lenient_with(filename, &ParserOptions::default())
+}
+
+/// Like `parse_file_lenient` but with settings for how lines are read
+pub fn parse_file_lenient_with(
     filename: impl AsRef<std::path::Path>,
-) -> Result<impl Iterator<Item = Result<BulkCard>>> {
-    // FIXME this is awkward. Either the bulk card iter should open the file
-    let t = std::time::Instant::now();
-    let bytes = std::fs::read(filename)?;
-    println!("Read file took {} ms", t.elapsed().as_millis());
-    let t = std::time::Instant::now();
-    let lines = bytes.split(|&c| c == b'\n').flat_map(|line| {
-        let n = std::cmp::min(line.len(), 10);
-        if line[..n].contains(&b',') {
-            let lines = NastranCommaLine::new(line.to_vec())
-                .map(|r| r.and_then(TryInto::try_into))
-                .collect::<Result<Vec<BulkLine>>>()?;
-            rayon::iter::Either::Left(lines.into_par_iter())
-        } else {
-            let line: Result<UnparsedBulkLine> = NastranLine::new(line.to_vec()).try_into();
-            rayon::iter::Either::Right(rayon::iter::once(line.and_then(TryInto::try_into)))
+    options: &ParserOptions,
+) -> Result<ParseReport> {
+    let (source, errors) = source::read_with_includes_lenient(filename)?;
+    Ok(report_lenient(source, errors, options))
+}
+
+/// Parses bulk data held in memory without stopping at the first error, like
+/// `parse_file_lenient`
+pub fn parse_buffer_lenient(buffer: &[u8]) -> Result<ParseReport> {
+    parse_buffer_lenient_with(buffer, &ParserOptions::default())
+}
+
+/// Like `parse_buffer_lenient` but with settings for how lines are read
+pub fn parse_buffer_lenient_with(buffer: &[u8], options: &ParserOptions) -> Result<ParseReport> {
+    let (source, errors) = source::read_buffer_with_includes_lenient(buffer)?;
+    Ok(report_lenient(source, errors, options))
+}
+
+/// Parses bulk data from any reader without stopping at the first error. The
+/// whole input is read before parsing starts
+pub fn parse_reader_lenient(reader: impl io::Read) -> Result<ParseReport> {
+    parse_reader_lenient_with(reader, &ParserOptions::default())
+}
+
+/// Like `parse_reader_lenient` but with settings for how lines are read
+pub fn parse_reader_lenient_with(
+    mut reader: impl io::Read,
+    options: &ParserOptions,
+) -> Result<ParseReport> {
+    let mut buffer = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    parse_buffer_lenient_with(&buffer, options)
+}
+
+/// Parses the bulk data lines of `source`, collecting any errors along with
+/// those from reading include files
+fn report_lenient(
+    source: Vec<SourceLine>,
+    errors: Vec<Error>,
+    options: &ParserOptions,
+) -> ParseReport {
+    let source = bulk_lines(source);
+    let mut report = ParseReport {
+        diagnostics: errors.into_iter().map(Diagnostic::error).collect(),
+        ..Default::default()
+    };
+    let mut lines = Vec::with_capacity(source.len());
+    for (parsed, error) in parse_lines_lenient(&source, options) {
+        lines.extend(parsed);
+        if let Some(error) = error {
+            report.diagnostics.push(Diagnostic::error(error));
         }
-    });
-    println!("Line parsing took {} ms", t.elapsed().as_millis());
-    let cards = BulkCardIter::new(lines)
-        .collect::<Result<Vec<BulkCard>>>()?
-        .into_iter()
-        .map(Ok);
-    Ok(cards)
+    }
+    for card in BulkCardIter::new(lines.into_iter().map(Ok)) {
+        match card {
+            Ok(card) if card.invalid => report.quarantined.push(card),
+            Ok(card) => report.cards.push(card),
+            Err(e) => report.diagnostics.push(Diagnostic::error(e)),
+        }
+    }
+    report
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_parse_first_field() {
+        let first = parse_first_field(*b"MOMENT1 ").unwrap().unwrap();
+        assert!(matches!(first.kind, FirstFieldKind::Text(t) if &t.0 == b"MOMENT1"));
+        assert!(parse_first_field(*b"MOMENT12").is_err());
+    }
+
     #[test]
     fn test_parse_field() {
         assert_eq!(
@@ -1487,4 +2258,237 @@ mod test {
         //assert_eq!((),parse_inner_field(b"1D+3"));
         //assert_eq!((),parse_inner_field(b"1D-3"));
     }
+
+    fn source_line(line: usize, text: &[u8]) -> SourceLine {
+        SourceLine {
+            file: Some(Path::new("model.bdf").into()),
+            line,
+            text: text.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_error_location() {
+        let options = ParserOptions::default();
+        let error = parse_line(
+            &source_line(12, b"GRID    1       0       1.0     2#0"),
+            &options,
+        )
+        .unwrap_err();
+        assert!(matches!(error.kind(), Error::UnexpectedChar(b'#')));
+        let location = error.location().unwrap();
+        assert_eq!(location.file(), Some(Path::new("model.bdf")));
+        assert_eq!(location.line(), 12);
+        assert_eq!(location.column(), Some(33));
+        assert_eq!(location.field(), Some(5));
+        assert_eq!(
+            error.to_string(),
+            "Unexpected character #
+ --> model.bdf:12:33
+   |
+12 | GRID    1       0       1.0     2#0
+   |                                 ^^^^^^^^ field 5"
+        );
+
+        let error = parse_line(&source_line(3, b"GRID,1,,1.0,2..0"), &options).unwrap_err();
+        let location = error.location().unwrap();
+        assert_eq!(location.line(), 3);
+        assert_eq!((location.column(), location.field()), (Some(13), Some(5)));
+
+        let lines = vec![source_line(1, b"+C1     1"), source_line(2, b"")];
+        let lines = lines
+            .into_iter()
+            .map(|l| parse_line(&l, &options).map(|mut l| l.remove(0)));
+        let error = BulkCardIter::new(lines).next().unwrap().unwrap_err();
+        assert!(matches!(error.kind(), Error::UnmatchedContinuation(_)));
+        assert_eq!(error.location().unwrap().line(), 1);
+    }
+
+    #[test]
+    fn test_parse_file_lenient() {
+        let path = std::env::temp_dir().join(format!("nastran-lenient-{}.bdf", std::process::id()));
+        let deck = format!(
+            "GRID    1       0       1.0\n{:72}+A\n+A      1\nGRID    3       0       3.0\n+B      1\n",
+            "GRID    2       0       1#0"
+        );
+        std::fs::write(&path, &deck).unwrap();
+        let report = parse_file_lenient(&path).unwrap();
+        let lines: Vec<_> = report
+            .diagnostics
+            .iter()
+            .map(|d| d.error.location().unwrap().line())
+            .collect();
+        assert_eq!(lines, vec![2, 5]);
+        assert_eq!(report.quarantined.len(), 1);
+        assert_eq!(report.quarantined[0].line(), 2);
+        assert_eq!(report.quarantined[0].fields().len(), 8);
+        let cards: Vec<_> = report
+            .cards
+            .iter()
+            .filter(|c| c.card_type().is_some())
+            .map(|c| c.line())
+            .collect();
+        assert_eq!(cards, vec![1, 4]);
+        let from_buffer = parse_buffer_lenient(deck.as_bytes()).unwrap();
+        let fields = |cards: &[BulkCard]| {
+            cards
+                .iter()
+                .map(|c| c.fields().to_vec())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fields(&from_buffer.cards), fields(&report.cards));
+        assert_eq!(from_buffer.quarantined.len(), 1);
+        assert_eq!(from_buffer.diagnostics.len(), 2);
+        assert!(parse_file(&path)
+            .and_then(|cards| cards.collect::<Result<Vec<_>>>())
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_buffer() {
+        let deck = b"GRID    1       0       1.0\nGRID    2       0       2.0\n";
+        let cards = parse_buffer(deck)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(cards[0].card_type(), Some(*b"GRID   "));
+        assert_eq!(cards[1].fields()[2], Field::Float(2.0));
+        assert_eq!(cards[1].file(), None);
+        let from_reader = parse_reader(&deck[..])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(cards, from_reader);
+    }
+
+    fn fields(input: &[u8]) -> Vec<(Option<[u8; 7]>, Vec<Field>)> {
+        parse_buffer(input)
+            .unwrap()
+            .map(|c| c.unwrap())
+            .filter(|c| c.card_type().is_some())
+            .map(|c| (c.card_type(), c.fields().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_replication() {
+        let input = b"GRID,101,17,1.0,10.5,,17,3456
+=,*1,=,*(0.2),==
+=(2)
+GRID    201     17      1.0
+GRID    =       =       *-1     =
+";
+        let cards = parse_buffer(input)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let cards: Vec<_> = cards.iter().filter(|c| c.card_type().is_some()).collect();
+        assert_eq!(cards.len(), 6);
+        for (i, card) in cards[..4].iter().enumerate() {
+            assert_eq!(card.card_type(), Some(*b"GRID   "));
+            assert_eq!(card.fields()[0], Field::IntOrId(101 + i as u32));
+            assert_eq!(card.fields()[1], Field::IntOrId(17));
+            let x = card.fields()[2].float().unwrap();
+            assert!((x - (1.0 + 0.2 * i as f64)).abs() < 1e-5);
+            assert_eq!(&card.fields()[3..7], &cards[0].fields()[3..7]);
+        }
+        assert!(cards[0].replication().is_none());
+        let replication = cards[1].replication().unwrap();
+        assert_eq!(
+            (cards[1].line(), replication.line(), replication.index()),
+            (2, 1, 0)
+        );
+        let replication = cards[3].replication().unwrap();
+        assert_eq!(
+            (cards[3].line(), replication.line(), replication.index()),
+            (3, 2, 2)
+        );
+        assert_eq!(
+            cards[5].fields()[..3],
+            [Field::IntOrId(201), Field::IntOrId(17), Field::Float(0.0)]
+        );
+
+        let error = parse_buffer(b"=,*1\n")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert!(matches!(error.kind(), Error::NoReplicationSource));
+        let error = parse_buffer(b"GRID,1,,A\n=,=,=,*1\n")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert!(matches!(error.kind(), Error::InvalidIncrement(..)));
+        assert_eq!(error.location().unwrap().line(), 2);
+
+        // Copies are generated as they're read rather than all at once
+        let mut cards = parse_buffer(b"GRID,1\n=,*1\n=9999999\n").unwrap();
+        let ids: Vec<_> = (&mut cards)
+            .map(|c| c.unwrap())
+            .filter(|c| c.card_type().is_some())
+            .take(4)
+            .map(|c| c.fields()[0])
+            .collect();
+        assert_eq!(ids, (1..=4).map(Field::IntOrId).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_comma_matches_fixed() {
+        let fixed = fields(
+            b"CHEXA   1       2       3       4       5       6       7       8       +A
++A      9       10
+GRID*   1                               1.0             2.0             *G1
+*G1     3.0             0
+CBAR    1       2       3       4       0.      1.      0.              +B
++B              1
+",
+        );
+        // Explicit continuations, both free and fixed
+        let free = fields(
+            b"CHEXA,1,2,3,4,5,6,7,8,+A
++A,9,10
+GRID*,1,,1.0,2.0,*G1
+*G1,3.0,0
+cbar,1,2,3,4,0.,1.,0.,,+B
++B,,1
+",
+        );
+        assert_eq!(fixed, free);
+        // Implied continuations from long lines
+        let long = fields(
+            b"CHEXA,1,2,3,4,5,6,7,8,9,10
+GRID*,1,,1.0,2.0,3.0,0
+CBAR,1,2,3,4,0.,1.,0.,,+B,,1,
+",
+        );
+        assert_eq!(fixed, long);
+        let marker = fields(b"CHEXA,1,2,3,4,5,6,7,8,+A,9,10\nGRID*,1,,1.0,2.0,*,3.0,0\n");
+        assert_eq!(fixed[..2], marker[..]);
+        // Mixed free and fixed continuation lines
+        let mixed = fields(
+            b"CHEXA   1       2       3       4       5       6       7       8       +A
++A,9,10
+GRID*,1,,1.0,2.0,*G1
+*G1     3.0             0
+CBAR,1,2,3,4,0.,1.,0.,,+B
++B              1
+",
+        );
+        assert_eq!(fixed, mixed);
+    }
+
+    #[test]
+    fn test_free_field_exponent() {
+        let grid = fields(b"GRID,1,,1.234567890123-5\n");
+        let x = grid[0].1[2].float().unwrap();
+        assert!((x - 1.234567890123e-5).abs() < 1e-11);
+        let error = parse_buffer(b"GRID,1,,123456789012345+\n")
+            .and_then(|cards| cards.collect::<Result<Vec<_>>>())
+            .unwrap_err();
+        assert!(matches!(error.kind(), Error::InvalidField));
+        let long = b"12345678901234567-5".iter().copied();
+        assert!(matches!(
+            parse_inner_field(long),
+            Err(Error::TextTooLong(_))
+        ));
+    }
 }
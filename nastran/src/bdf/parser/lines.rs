@@ -1,19 +1,23 @@
 use super::super::error::{Error, Result};
-use super::{Comment, Eol};
+use super::{Comment, Eol, LongLines, ParserOptions, TrailingField};
 
+use bstr::ByteSlice;
 use std::io;
 
 pub(crate) struct NastranLine {
     original: Vec<u8>,
     iter: NastranLineIter,
+    trailing_field: TrailingField,
 }
 
 impl NastranLine {
-    pub(crate) fn new(line: Vec<u8>) -> Self {
+    pub(crate) fn new(line: &[u8], options: &ParserOptions) -> Self {
         // Add comma check here?
+        let original = line.to_vec();
         NastranLine {
-            original: line.clone(),
-            iter: NastranLineIter::new(line.into_iter()),
+            iter: NastranLineIter::new(original.clone().into_iter(), options),
+            original,
+            trailing_field: options.trailing_field,
         }
     }
 
@@ -41,10 +45,25 @@ impl NastranLine {
         field
     }
 
+    /// Takes columns 73-80, which are blank if they're set to be ignored
+    pub(crate) fn take_trailing(&mut self) -> [u8; 8] {
+        let field = self.take8();
+        match self.trailing_field {
+            TrailingField::Continuation => field,
+            TrailingField::Ignore => [b' '; 8],
+        }
+    }
+
     pub(crate) fn comment_and_eol(&mut self) -> Result<(Comment, Option<Eol>)> {
+        if let Some(chars) = self.iter.overflow.take() {
+            let width = chars.len();
+            return Err(Error::UnparsedChars(chars).at_columns(None, 81, width));
+        }
         self.iter.comment_and_eol().ok_or_else(|| {
-            let chars = (&mut self.iter).collect();
-            Error::UnparsedChars(chars)
+            let column = self.iter.position() + 1;
+            let chars: Vec<u8> = (&mut self.iter).collect();
+            let width = chars.len();
+            Error::UnparsedChars(chars).at_columns(None, column, width)
         })
     }
 
@@ -67,18 +86,30 @@ pub(crate) enum NastranLineIterState {
 pub(crate) struct NastranLineIter {
     iter: std::iter::Peekable<std::iter::Enumerate<ExpandTabs<std::vec::IntoIter<u8>>>>,
     state: NastranLineIterState,
+    position: usize,
+    fold_lowercase: bool,
+    long_lines: LongLines,
+    /// Characters past column 80 that aren't allowed by `LongLines::Error`
+    overflow: Option<Vec<u8>>,
 }
 
 impl NastranLineIter {
-    pub(crate) fn new(iter: std::vec::IntoIter<u8>) -> Self {
+    pub(crate) fn new(iter: std::vec::IntoIter<u8>, options: &ParserOptions) -> Self {
         Self {
-            iter: ExpandTabs::new(iter).enumerate().peekable(),
+            iter: ExpandTabs::new(iter, options.tab_width)
+                .enumerate()
+                .peekable(),
             state: NastranLineIterState::Parsing,
+            position: 0,
+            fold_lowercase: options.fold_lowercase,
+            long_lines: options.long_lines,
+            overflow: None,
         }
     }
 
-    pub(crate) fn peek(&mut self) -> Option<u8> {
-        self.iter.peek().map(|c| c.1)
+    /// Number of columns consumed so far, with tabs expanded
+    pub(crate) fn position(&self) -> usize {
+        self.position
     }
 
     pub(crate) fn comment_and_eol(&mut self) -> Option<(Comment, Option<Eol>)> {
@@ -113,13 +144,17 @@ impl Iterator for NastranLineIter {
         }
         use Res::*;
         // Be careful here. The ordering matters so that the Eol is processed
-        let result = match self.iter.next() {
+        let next = self.iter.next();
+        if let Some((i, _)) = next {
+            self.position = i + 1;
+        }
+        let result = match next {
             Some((_, b'$')) => DollarSign(b'$'),
             Some((_, b'\n')) => Lf,
             Some((_, b'\r')) => CrLf,
-            Some((79, c @ b'a'..=b'z')) => CharAndEol(c - 32),
+            Some((79, c)) if self.fold_lowercase => CharAndEol(c.to_ascii_uppercase()),
             Some((79, c)) => CharAndEol(c),
-            Some((_, c @ b'a'..=b'z')) => Char(c - 32),
+            Some((_, c)) if self.fold_lowercase => Char(c.to_ascii_uppercase()),
             Some((_, c)) => Char(c),
             None => Eol,
         };
@@ -132,7 +167,7 @@ impl Iterator for NastranLineIter {
                 for (_, c) in &mut self.iter {
                     match c {
                         b'\r' => {
-                            eol = Some(self::Eol::Lf);
+                            eol = Some(self::Eol::CrLf);
                             break;
                         }
                         b'\n' => {
@@ -142,6 +177,12 @@ impl Iterator for NastranLineIter {
                         _ => comment.push(c),
                     }
                 }
+                if self.long_lines == LongLines::Error {
+                    let rest = comment.0.trim_start_with(|c| c == ' ');
+                    if !rest.is_empty() && !rest.starts_with(b"$") {
+                        self.overflow = Some(comment.0.to_vec());
+                    }
+                }
                 self.state = NastranLineIterState::Comment(comment, eol);
                 Some(c)
             }
@@ -185,17 +226,19 @@ struct ExpandTabs<I> {
     iter: I,
     col: usize,
     tab_active: bool,
+    width: usize,
 }
 
 impl<I> ExpandTabs<I>
 where
     I: Sized,
 {
-    fn new(iter: I) -> Self {
+    fn new(iter: I, width: usize) -> Self {
         ExpandTabs {
             iter,
             col: 0,
             tab_active: false,
+            width,
         }
     }
 }
@@ -209,10 +252,10 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         // col tracks the column number as if the tabs are already expanded.
         // tab_active indicates that we've seen a tab and haven't fully expanded it. If
-        // the column number is not divisible by 8 then return a space since we still need
-        // to expand the tab
+        // the column number is not divisible by the tab width then return a space since we
+        // still need to expand the tab
         if self.tab_active {
-            if self.col % 8 != 0 {
+            if !self.col.is_multiple_of(self.width) {
                 self.col += 1;
                 return Some(b' ');
             } else {
@@ -231,6 +274,7 @@ where
     }
 }
 
+#[allow(dead_code)]
 pub(crate) struct SplitLines<I> {
     iter: I,
 }
@@ -263,6 +307,7 @@ where
     }
 }
 
+#[allow(dead_code)]
 pub(crate) trait NastranFileIter: Iterator<Item = io::Result<u8>> + Sized {
     fn split_lines(self) -> SplitLines<Self> {
         SplitLines { iter: self }
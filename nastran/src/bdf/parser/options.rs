@@ -0,0 +1,179 @@
+use super::Eol;
+
+/// How characters past column 80 of a fixed field line are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LongLines {
+    /// Everything past column 80 is kept as a comment, as NASTRAN ignores it
+    Truncate,
+    /// Anything past column 80 other than blanks or a `$` comment is an error
+    Error,
+}
+
+/// How columns 73-80 of a fixed field line are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingField {
+    /// Columns 73-80 hold the continuation marker
+    Continuation,
+    /// Columns 73-80 are ignored, such as for decks with sequence numbers in
+    /// them. Continuation lines must then have a blank first field
+    Ignore,
+}
+
+/// Settings for how bulk data lines are read. The defaults match NASTRAN
+///
+/// ```
+/// use nastran::bdf::parser::{Eol, LongLines, ParserOptions};
+///
+/// let options = ParserOptions::new()
+///     .tab_width(4)
+///     .long_lines(LongLines::Error)
+///     .eol(Eol::Lf);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    pub(crate) tab_width: usize,
+    pub(crate) long_lines: LongLines,
+    pub(crate) trailing_field: TrailingField,
+    pub(crate) fold_lowercase: bool,
+    pub(crate) eol: Option<Eol>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            tab_width: 8,
+            long_lines: LongLines::Truncate,
+            trailing_field: TrailingField::Continuation,
+            fold_lowercase: true,
+            eol: None,
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The column multiple that tabs expand to. Defaults to 8. A width of 0 is
+    /// treated as 1
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width.max(1);
+        self
+    }
+
+    pub fn long_lines(mut self, long_lines: LongLines) -> Self {
+        self.long_lines = long_lines;
+        self
+    }
+
+    pub fn trailing_field(mut self, trailing_field: TrailingField) -> Self {
+        self.trailing_field = trailing_field;
+        self
+    }
+
+    /// Whether lowercase letters are read as uppercase. When disabled,
+    /// lowercase letters in data fields are an error. Defaults to true
+    pub fn fold_lowercase(mut self, fold: bool) -> Self {
+        self.fold_lowercase = fold;
+        self
+    }
+
+    /// Uses `eol` for every card instead of detecting the line ending of each
+    /// line
+    pub fn eol(mut self, eol: Eol) -> Self {
+        self.eol = Some(eol);
+        self
+    }
+
+    /// Detects the line ending of each line, which is the default
+    pub fn detect_eol(mut self) -> Self {
+        self.eol = None;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::deck::Deck;
+    use crate::bdf::parser::{
+        parse_buffer_lenient_with, parse_buffer_with, parse_file_lenient_with,
+        parse_reader_lenient_with, parse_reader_with, BulkCard, Field,
+    };
+    use crate::bdf::Result;
+
+    fn parse(input: &[u8], options: &ParserOptions) -> Result<Vec<BulkCard>> {
+        parse_buffer_with(input, options)?.collect()
+    }
+
+    #[test]
+    fn test_options() {
+        let input = b"GRID\t1\t\t1.0\n";
+        let cards = parse(input, &ParserOptions::new().tab_width(4)).unwrap();
+        assert_eq!(
+            cards[0].fields()[..2],
+            [Field::IntOrId(1), Field::Float(1.0)]
+        );
+        let cards = parse(input, &ParserOptions::new()).unwrap();
+        assert_eq!(cards[0].fields()[2], Field::Float(1.0));
+        let tab4 = ParserOptions::new().tab_width(4);
+        let cards: Vec<_> = parse_reader_with(&input[..], &tab4)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(cards[0].fields()[1], Field::Float(1.0));
+        let deck = Deck::from_reader_with(&b"GRID\t\t1\t\t\t\t1.0\n"[..], &tab4).unwrap();
+        assert_eq!(deck.global_locations().get_grid(1).unwrap().x(), 1.0);
+
+        let long = b"GRID    1       0       1.0     2.0     3.0                             +A      extra\n+A      \n";
+        assert!(parse(long, &ParserOptions::new()).is_ok());
+        let strict = ParserOptions::new().long_lines(LongLines::Error);
+        assert!(parse(long, &strict).is_err());
+
+        let numbered = b"GRID    1       0       1.0     2.0     3.0                             00000001\n        \n";
+        assert!(parse(numbered, &ParserOptions::new()).is_err());
+        let ignore = ParserOptions::new().trailing_field(TrailingField::Ignore);
+        assert!(parse(numbered, &ignore).is_ok());
+
+        let lower = b"grid,1,,1.0\n";
+        assert!(parse(lower, &ParserOptions::new()).is_ok());
+        assert!(parse(lower, &ParserOptions::new().fold_lowercase(false)).is_err());
+
+        let cards = parse(b"GRID,1\r\n", &ParserOptions::new()).unwrap();
+        assert_eq!(cards[0].eol(), Eol::CrLf);
+        let cards = parse(b"GRID,1\r\n", &ParserOptions::new().eol(Eol::Lf)).unwrap();
+        assert_eq!(cards[0].eol(), Eol::Lf);
+    }
+
+    #[test]
+    fn test_tab_width_errors() {
+        let options = ParserOptions::new().tab_width(4);
+        let error = parse(b"GRID\t1\t\t1#0\n", &options).unwrap_err();
+        assert_eq!(error.location().unwrap().column(), Some(17));
+        assert!(error
+            .to_string()
+            .ends_with("| GRID    1       1#0\n  |                 ^^^^^^^^ field 3"));
+
+        let path = std::env::temp_dir().join(format!("nastran-options-{}.bdf", std::process::id()));
+        std::fs::write(&path, "GRID\t1\t\t1#0\nGRID\t2\t\t2.0\n").unwrap();
+        let report = parse_file_lenient_with(&path, &options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report.diagnostics.len(), 1);
+        let location = report.diagnostics[0].error.location().unwrap();
+        assert_eq!((location.line(), location.column()), (1, Some(17)));
+        let grid = report.cards.iter().find(|c| c.card_type().is_some());
+        assert_eq!(grid.unwrap().fields()[1], Field::Float(2.0));
+
+        let input = b"GRID\t\t1\t\t\t\t1#0\nGRID\t\t2\t\t\t\t2.0\n";
+        let from_buffer = parse_buffer_lenient_with(input, &options).unwrap();
+        let from_reader = parse_reader_lenient_with(&input[..], &options).unwrap();
+        for report in [from_buffer, from_reader] {
+            let location = report.diagnostics[0].error.location().unwrap();
+            assert_eq!((location.line(), location.column()), (1, Some(25)));
+        }
+        let (deck, diagnostics) = Deck::from_buffer_lenient_with(input, &options).unwrap();
+        assert_eq!(diagnostics[0].error.location().unwrap().column(), Some(25));
+        assert_eq!(deck.global_locations().get_grid(2).unwrap().x(), 2.0);
+    }
+}
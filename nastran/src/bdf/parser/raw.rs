@@ -0,0 +1,174 @@
+use std::fmt;
+
+use bstr::ByteSlice;
+use smallvec::SmallVec;
+
+use super::{parse_inner_field, writer, Field};
+use crate::bdf::{Error, Result};
+
+/// What a field of a card is allowed to hold. Blank is always allowed since
+/// whether a field is required is up to the card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Int,
+    Real,
+    Char,
+    IntOrReal,
+    IntOrChar,
+    RealOrChar,
+    Any,
+}
+
+impl FieldKind {
+    pub fn allows(self, field: &Field) -> bool {
+        use FieldKind::*;
+        match field {
+            Field::Blank => true,
+            Field::Int(_) | Field::IntOrId(_) => matches!(self, Int | IntOrReal | IntOrChar | Any),
+            Field::Float(_) | Field::Double(_) => {
+                matches!(self, Real | IntOrReal | RealOrChar | Any)
+            }
+            Field::Text(_) => matches!(self, Char | IntOrChar | RealOrChar | Any),
+        }
+    }
+
+    fn allows_char(self) -> bool {
+        matches!(
+            self,
+            FieldKind::Char | FieldKind::IntOrChar | FieldKind::RealOrChar
+        )
+    }
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            FieldKind::Int => "an integer",
+            FieldKind::Real => "a real",
+            FieldKind::Char => "a character value",
+            FieldKind::IntOrReal => "an integer or real",
+            FieldKind::IntOrChar => "an integer or character value",
+            FieldKind::RealOrChar => "a real or character value",
+            FieldKind::Any => "any value",
+        };
+        f.write_str(text)
+    }
+}
+
+/// The text of a data field as it was written, uppercased and without
+/// surrounding blanks. This keeps the exact form of values, such as `1.+5`
+/// versus `100000.`, and can be decoded according to what the card allows in
+/// the field
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RawField(SmallVec<[u8; 16]>);
+
+impl RawField {
+    pub(crate) fn new(text: &[u8]) -> Self {
+        Self(text.trim().iter().map(u8::to_ascii_uppercase).collect())
+    }
+
+    /// The text of a field that was generated rather than read
+    pub(crate) fn from_field(field: &Field) -> Self {
+        Self::new(
+            writer::format_field(field, 16)
+                .unwrap_or_default()
+                .as_bytes(),
+        )
+    }
+
+    pub fn text(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Decodes the field by its form alone, which is how `BulkCard::fields`
+    /// is decoded
+    pub fn parse(&self) -> Result<Field> {
+        parse_inner_field(self.0.iter().copied())
+    }
+
+    /// Decodes the field as `kind`. Fields that allow character values accept
+    /// any text of up to 8 characters, so that a value like `1` is read as
+    /// text where an integer isn't allowed
+    pub fn decode(&self, kind: FieldKind) -> Result<Field> {
+        if self.is_blank() {
+            return Ok(Field::Blank);
+        }
+        if let Ok(field) = self.parse() {
+            if kind.allows(&field) {
+                return Ok(field);
+            }
+        }
+        if kind.allows_char() && self.0.len() <= 8 && !self.0.contains(&b' ') {
+            let mut text = [b' '; 8];
+            text[..self.0.len()].copy_from_slice(&self.0);
+            return Ok(Field::Text(text));
+        }
+        Err(Error::UnexpectedKind(kind, self.0.to_vec()))
+    }
+}
+
+impl fmt::Display for RawField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_bstr())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let raw = RawField::new(b" 1.+5   ");
+        assert_eq!(raw.text(), b"1.+5");
+        assert_eq!(raw.decode(FieldKind::Real).unwrap(), Field::Float(1e5));
+        assert!(raw.decode(FieldKind::Int).is_err());
+        assert_eq!(
+            raw.decode(FieldKind::RealOrChar).unwrap(),
+            Field::Float(1e5)
+        );
+        let raw = RawField::new(b"12");
+        assert_eq!(
+            raw.decode(FieldKind::IntOrChar).unwrap(),
+            Field::IntOrId(12)
+        );
+        assert_eq!(
+            raw.decode(FieldKind::Char).unwrap(),
+            Field::Text(*b"12      ")
+        );
+        assert!(raw.decode(FieldKind::Real).is_err());
+        assert_eq!(
+            RawField::new(b"  ").decode(FieldKind::Int).unwrap(),
+            Field::Blank
+        );
+    }
+
+    #[test]
+    fn test_card_decode() {
+        use crate::bdf::parser::parse_buffer;
+        let cards = parse_buffer(b"PSOLID,1,2,,,,,12\nGRID,1,,1,2.,3.\n")
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let fields = cards[0].decode(&[FieldKind::Int, FieldKind::Int]).unwrap();
+        assert_eq!(fields[6], Field::IntOrId(12));
+        let fields = cards[0]
+            .decode(
+                &[FieldKind::Int; 6]
+                    .iter()
+                    .copied()
+                    .chain([FieldKind::Char])
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap();
+        assert_eq!(fields[6], Field::Text(*b"12      "));
+        let error = cards[1]
+            .decode(&[FieldKind::Int, FieldKind::Int, FieldKind::Real])
+            .unwrap_err();
+        assert_eq!(error.location().unwrap().field(), Some(4));
+    }
+}
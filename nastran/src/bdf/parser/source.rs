@@ -0,0 +1,301 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bstr::ByteSlice;
+
+use crate::bdf::{Error, Location, Result};
+
+/// A single physical line of input along with the file it was read from
+#[derive(Debug, Clone)]
+pub struct SourceLine {
+    pub(crate) file: Option<Arc<Path>>,
+    /// 1-based line number within `file`
+    pub(crate) line: usize,
+    pub(crate) text: Vec<u8>,
+}
+
+impl SourceLine {
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// The 1-based line number within `file`
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The text of the line without the trailing newline
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    pub fn location(&self) -> Location {
+        Location::new(self.file.clone(), self.line, &self.text)
+    }
+}
+
+/// Reads `path` and returns all of its lines with any INCLUDE statements
+/// recursively replaced by the contents of the included file.
+pub(crate) fn read_with_includes(path: impl AsRef<Path>) -> Result<Vec<SourceLine>> {
+    let mut expander = Expander::default();
+    expander.expand_file(path.as_ref())?;
+    Ok(expander.lines)
+}
+
+/// Splits an in memory buffer into lines, expanding INCLUDE statements
+/// relative to the current directory
+pub(crate) fn read_buffer_with_includes(bytes: &[u8]) -> Result<Vec<SourceLine>> {
+    let mut expander = Expander::default();
+    expander.expand(bytes, None)?;
+    Ok(expander.lines)
+}
+
+/// Splits an in memory buffer into lines without reading any included files.
+/// INCLUDE statements are left out of the returned lines
+pub(crate) fn read_buffer_without_includes(
+    bytes: &[u8],
+    file: Option<Arc<Path>>,
+) -> Vec<SourceLine> {
+    let mut lines = bytes.split(|&c| c == b'\n').enumerate();
+    let mut source = Vec::new();
+    while let Some((i, line)) = lines.next() {
+        match include_statement(line) {
+            Some(rest) => {
+                // Only consumes any continuation lines of the filename
+                let _ = include_filename(rest, &mut (&mut lines).map(|(_, l)| l));
+            }
+            None => source.push(SourceLine {
+                file: file.clone(),
+                line: i + 1,
+                text: line.to_vec(),
+            }),
+        }
+    }
+    source
+}
+
+/// Like `read_with_includes` but include files that can't be read are skipped
+/// and their errors returned alongside the lines that could be read
+pub(crate) fn read_with_includes_lenient(
+    path: impl AsRef<Path>,
+) -> Result<(Vec<SourceLine>, Vec<Error>)> {
+    let mut expander = Expander {
+        errors: Some(Vec::new()),
+        ..Default::default()
+    };
+    expander.expand_file(path.as_ref())?;
+    Ok((expander.lines, expander.errors.unwrap_or_default()))
+}
+
+/// Like `read_buffer_with_includes` but include files that can't be read are
+/// skipped and their errors returned alongside the lines
+pub(crate) fn read_buffer_with_includes_lenient(
+    bytes: &[u8],
+) -> Result<(Vec<SourceLine>, Vec<Error>)> {
+    let mut expander = Expander {
+        errors: Some(Vec::new()),
+        ..Default::default()
+    };
+    expander.expand(bytes, None)?;
+    Ok((expander.lines, expander.errors.unwrap_or_default()))
+}
+
+#[derive(Default)]
+struct Expander {
+    stack: Vec<PathBuf>,
+    lines: Vec<SourceLine>,
+    /// Collects include errors instead of failing when set
+    errors: Option<Vec<Error>>,
+}
+
+impl Expander {
+    fn expand_file(&mut self, path: &Path) -> Result<()> {
+        let bytes = std::fs::read(path)?;
+        if let Ok(canonical) = path.canonicalize() {
+            self.stack.push(canonical);
+        }
+        self.expand(&bytes, Some(path.into()))
+    }
+
+    fn fail(&mut self, error: Error) -> Result<()> {
+        match &mut self.errors {
+            Some(errors) => {
+                errors.push(error);
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    fn expand(&mut self, bytes: &[u8], file: Option<Arc<Path>>) -> Result<()> {
+        let mut lines = bytes.split(|&c| c == b'\n').enumerate();
+        while let Some((i, line)) = lines.next() {
+            let rest = match include_statement(line) {
+                Some(rest) => rest,
+                None => {
+                    self.lines.push(SourceLine {
+                        file: file.clone(),
+                        line: i + 1,
+                        text: line.to_vec(),
+                    });
+                    continue;
+                }
+            };
+            let location = Location::new(file.clone(), i + 1, line);
+            let filename = match include_filename(rest, &mut (&mut lines).map(|(_, l)| l)) {
+                Ok(filename) => filename,
+                Err(e) => {
+                    self.fail(e.at(location))?;
+                    continue;
+                }
+            };
+            let path = resolve_include(&filename, file.as_deref());
+            let included = path
+                .canonicalize()
+                .and_then(|c| Ok((std::fs::read(&c)?, c)));
+            let (included, canonical) = match included {
+                Ok(included) => included,
+                Err(e) => {
+                    self.fail(Error::IncludeNotFound(path, e).at(location))?;
+                    continue;
+                }
+            };
+            if self.stack.contains(&canonical) {
+                self.fail(Error::IncludeCycle(path).at(location))?;
+                continue;
+            }
+            self.stack.push(canonical);
+            self.expand(&included, Some(path.into()))?;
+            self.stack.pop();
+        }
+        Ok(())
+    }
+}
+
+/// Returns the remainder of the line following the INCLUDE keyword if this
+/// line is an INCLUDE statement
+pub(crate) fn include_statement(line: &[u8]) -> Option<&[u8]> {
+    const KEYWORD: &[u8] = b"INCLUDE";
+    if line.len() < KEYWORD.len() || !line[..KEYWORD.len()].eq_ignore_ascii_case(KEYWORD) {
+        return None;
+    }
+    let rest = &line[KEYWORD.len()..];
+    match rest.first() {
+        None | Some(b' ') | Some(b'\t') | Some(b'\'') | Some(b'\r') => Some(rest),
+        _ => None,
+    }
+}
+
+/// Extracts the filename from an INCLUDE statement. A quoted filename may be
+/// continued across multiple lines, in which case the leading and trailing
+/// whitespace of each line is dropped before joining them.
+pub(crate) fn include_filename<'a>(
+    rest: &'a [u8],
+    lines: &mut impl Iterator<Item = &'a [u8]>,
+) -> Result<PathBuf> {
+    let rest = rest.trim();
+    let filename = match rest.strip_prefix(b"'") {
+        Some(quoted) => {
+            let mut filename = Vec::new();
+            let mut segment = quoted;
+            loop {
+                if let Some(end) = segment.find_byte(b'\'') {
+                    filename.extend_from_slice(&segment[..end]);
+                    break;
+                }
+                filename.extend_from_slice(segment.trim());
+                segment = match lines.next() {
+                    Some(line) => line.trim(),
+                    None => return Err(Error::UnterminatedInclude(filename)),
+                };
+            }
+            filename
+        }
+        None => rest
+            .split(|c| c.is_ascii_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_vec(),
+    };
+    if filename.is_empty() {
+        return Err(Error::UnterminatedInclude(filename));
+    }
+    Ok(filename.to_path_lossy().into_owned())
+}
+
+/// Relative include paths are resolved against the directory of the including
+/// file
+pub(crate) fn resolve_include(filename: &Path, parent: Option<&Path>) -> PathBuf {
+    match parent.and_then(Path::parent) {
+        Some(dir) if filename.is_relative() => dir.join(filename),
+        _ => filename.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("nastran-include-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_include_filename() {
+        let mut lines = vec![&b"   sub/model.bdf'"[..]].into_iter();
+        let filename = include_filename(b" '/abs/path/", &mut lines).unwrap();
+        assert_eq!(filename, PathBuf::from("/abs/path/sub/model.bdf"));
+        let mut lines = std::iter::empty();
+        let filename = include_filename(b" other.bdf\r", &mut lines).unwrap();
+        assert_eq!(filename, PathBuf::from("other.bdf"));
+        assert!(include_statement(b"INCLUDES").is_none());
+        assert!(include_statement(b"include 'a.bdf'").is_some());
+    }
+
+    #[test]
+    fn test_read_with_includes() {
+        let dir = temp_dir("nested");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(
+            dir.join("main.bdf"),
+            "GRID,1\nINCLUDE 'sub/\n  a.bdf'\nGRID,3\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("sub").join("a.bdf"), "GRID,2\n").unwrap();
+        let lines = read_with_includes(dir.join("main.bdf")).unwrap();
+        let text: Vec<_> = lines.iter().map(|l| l.text.as_slice()).collect();
+        assert_eq!(text, vec![&b"GRID,1"[..], b"GRID,2", b"", b"GRID,3", b""]);
+        assert_eq!(
+            lines[1].file.as_deref(),
+            Some(dir.join("sub/a.bdf").as_path())
+        );
+        assert_eq!(
+            lines[3].file.as_deref(),
+            Some(dir.join("main.bdf").as_path())
+        );
+    }
+
+    #[test]
+    fn test_include_errors() {
+        let dir = temp_dir("errors");
+        std::fs::write(dir.join("a.bdf"), "INCLUDE 'b.bdf'\n").unwrap();
+        std::fs::write(dir.join("b.bdf"), "INCLUDE 'a.bdf'\n").unwrap();
+        std::fs::write(dir.join("c.bdf"), "INCLUDE 'missing.bdf'\n").unwrap();
+        let error = read_with_includes(dir.join("a.bdf")).unwrap_err();
+        assert!(matches!(error.kind(), Error::IncludeCycle(_)));
+        let error = read_with_includes(dir.join("c.bdf")).unwrap_err();
+        match error.kind() {
+            Error::IncludeNotFound(path, _) => assert!(path.ends_with("missing.bdf")),
+            _ => panic!("expected missing include"),
+        }
+        assert_eq!(error.location().unwrap().line(), 1);
+
+        std::fs::write(dir.join("d.bdf"), "GRID,1\nINCLUDE 'missing.bdf'\nGRID,2").unwrap();
+        let (lines, errors) = read_with_includes_lenient(dir.join("d.bdf")).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bstr::ByteSlice;
+
+use super::source::{include_filename, include_statement, resolve_include, SourceLine};
+use super::{parse_line, BulkCard, BulkCardIter, BulkLine, ParserOptions};
+use crate::bdf::sections::{is_begin, is_begin_bulk, is_keyword, partition, Partition};
+use crate::bdf::{Error, Location, Result};
+
+/// Limits for `parse_file_streaming` and `parse_reader_streaming`. Memory use
+/// is bounded by the buffer size of each open file, the longest line, the
+/// header lookahead and the cards waiting for continuations. Copies from
+/// `=n` replication are generated one at a time as they're read
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    /// The buffer size used when reading each file
+    pub buffer_size: usize,
+    /// Lines longer than this are an error rather than being read into memory
+    pub max_line_length: usize,
+    /// How many lines are read looking for `CEND` or `BEGIN BULK` before the
+    /// input is treated as containing only bulk data
+    pub max_header_lines: usize,
+    /// How many cards and comment lines may be held waiting for a
+    /// continuation before they're returned without it
+    pub max_pending: usize,
+    pub parser: ParserOptions,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            buffer_size: 64 * 1024,
+            max_line_length: 64 * 1024,
+            max_header_lines: 10_000,
+            max_pending: 1024,
+            parser: ParserOptions::default(),
+        }
+    }
+}
+
+/// Parses a bulk data file without reading it all into memory. Cards are
+/// returned as soon as the next card starts, so continuation lines must
+/// directly follow the card they continue, with only comments in between.
+/// INCLUDE statements are expanded as they're reached
+pub fn parse_file_streaming(
+    filename: impl AsRef<Path>,
+    options: &StreamOptions,
+) -> Result<impl Iterator<Item = Result<BulkCard>>> {
+    let path = filename.as_ref();
+    let file = File::open(path)?;
+    let mut lines = StreamingLines::new(options);
+    lines.push(Box::new(file), Some(path))?;
+    Ok(parse_stream(lines, options))
+}
+
+/// Like `parse_file_streaming` but reads from any reader, such as stdin.
+/// Relative INCLUDE paths are resolved against the current directory
+pub fn parse_reader_streaming(
+    reader: impl Read + 'static,
+    options: &StreamOptions,
+) -> Result<impl Iterator<Item = Result<BulkCard>>> {
+    let mut lines = StreamingLines::new(options);
+    lines.push(Box::new(reader), None)?;
+    Ok(parse_stream(lines, options))
+}
+
+fn parse_stream(
+    lines: StreamingLines,
+    options: &StreamOptions,
+) -> impl Iterator<Item = Result<BulkCard>> {
+    let bulk = BulkLines::new(lines, options.max_header_lines);
+    let parser = options.parser.clone();
+    let lines = bulk.flat_map(move |line| {
+        let lines: Vec<Result<BulkLine>> = match line.and_then(|line| parse_line(&line, &parser)) {
+            Ok(lines) => lines.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        lines
+    });
+    BulkCardIter::streaming(lines, options.max_pending)
+}
+
+struct OpenFile {
+    reader: Box<dyn BufRead>,
+    file: Option<Arc<Path>>,
+    canonical: Option<PathBuf>,
+    line: usize,
+}
+
+/// Reads lines one at a time, following INCLUDE statements into the included
+/// files
+struct StreamingLines {
+    stack: Vec<OpenFile>,
+    buffer_size: usize,
+    max_line_length: usize,
+}
+
+impl StreamingLines {
+    fn new(options: &StreamOptions) -> Self {
+        Self {
+            stack: Vec::new(),
+            buffer_size: options.buffer_size,
+            max_line_length: options.max_line_length,
+        }
+    }
+
+    fn push(&mut self, reader: Box<dyn Read>, path: Option<&Path>) -> Result<()> {
+        let canonical = path.map(Path::canonicalize).transpose()?;
+        self.stack.push(OpenFile {
+            reader: Box::new(BufReader::with_capacity(self.buffer_size, reader)),
+            file: path.map(Into::into),
+            canonical,
+            line: 0,
+        });
+        Ok(())
+    }
+
+    /// Reads the next line of the innermost open file without its newline
+    fn read_line(&mut self) -> Result<Option<SourceLine>> {
+        let max = self.max_line_length;
+        let open = match self.stack.last_mut() {
+            Some(open) => open,
+            None => return Ok(None),
+        };
+        let mut text = Vec::new();
+        let n = (&mut open.reader)
+            .take(max as u64 + 1)
+            .read_until(b'\n', &mut text)?;
+        if n == 0 {
+            return Ok(None);
+        }
+        open.line += 1;
+        if text.last() == Some(&b'\n') {
+            text.pop();
+        } else if text.len() > max {
+            // Skip the rest of the line so the next read starts on the next one
+            open.reader.skip_until(b'\n')?;
+            let location = Location::new(open.file.clone(), open.line, &text[..80.min(max)]);
+            return Err(Error::LineTooLong(max).at(location));
+        }
+        Ok(Some(SourceLine {
+            file: open.file.clone(),
+            line: open.line,
+            text,
+        }))
+    }
+
+    fn include(&mut self, line: &SourceLine, rest: &[u8]) -> Result<()> {
+        // A quoted filename may continue onto the following lines
+        let mut continued = Vec::new();
+        let quoted = rest.trim_start().starts_with(b"'");
+        if quoted && rest.trim_start()[1..].find_byte(b'\'').is_none() {
+            while let Some(next) = self.read_line()? {
+                let done = next.text.contains(&b'\'');
+                continued.push(next.text);
+                if done {
+                    break;
+                }
+            }
+        }
+        let filename = include_filename(rest, &mut continued.iter().map(Vec::as_slice))?;
+        let path = resolve_include(&filename, line.file());
+        let file = File::open(&path).map_err(|e| Error::IncludeNotFound(path.clone(), e))?;
+        let canonical = path.canonicalize()?;
+        if self
+            .stack
+            .iter()
+            .any(|open| open.canonical.as_ref() == Some(&canonical))
+        {
+            return Err(Error::IncludeCycle(path));
+        }
+        self.push(Box::new(file), Some(&path))
+    }
+}
+
+impl Iterator for StreamingLines {
+    type Item = Result<SourceLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.read_line() {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    self.stack.pop();
+                    if self.stack.is_empty() {
+                        return None;
+                    }
+                    continue;
+                }
+                Err(e) => return Some(Err(e)),
+            };
+            match include_statement(&line.text) {
+                Some(rest) => {
+                    let rest = rest.to_vec();
+                    if let Err(e) = self.include(&line, &rest) {
+                        return Some(Err(e.at(line.location())));
+                    }
+                }
+                None => return Some(Ok(line)),
+            }
+        }
+    }
+}
+
+enum BulkState {
+    /// Holding lines until it's known whether the input has a header
+    Lookahead,
+    /// Skipping executive and case control lines
+    Header,
+    Bulk,
+    Done,
+}
+
+/// Passes on only the bulk data lines, like `Sections`, but decides where the
+/// bulk data starts by looking ahead at most `max_header_lines`
+struct BulkLines<I> {
+    lines: I,
+    state: BulkState,
+    held: VecDeque<SourceLine>,
+    max_header_lines: usize,
+}
+
+impl<I> BulkLines<I> {
+    fn new(lines: I, max_header_lines: usize) -> Self {
+        Self {
+            lines,
+            state: BulkState::Lookahead,
+            held: VecDeque::new(),
+            max_header_lines,
+        }
+    }
+}
+
+impl<I> Iterator for BulkLines<I>
+where
+    I: Iterator<Item = Result<SourceLine>>,
+{
+    type Item = Result<SourceLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let BulkState::Bulk | BulkState::Done = self.state {
+                if let Some(line) = self.held.pop_front() {
+                    return Some(Ok(line));
+                }
+            }
+            if let BulkState::Done = self.state {
+                return None;
+            }
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    // Without a header all of the input is bulk data
+                    self.state = BulkState::Done;
+                    continue;
+                }
+            };
+            match self.state {
+                BulkState::Lookahead if is_begin_bulk(&line.text) => {
+                    self.held.clear();
+                    self.state = BulkState::Bulk;
+                }
+                BulkState::Header if is_begin(&line.text) => {
+                    self.state = BulkState::Bulk;
+                    // The statement is kept if it starts a partition other
+                    // than the main one, so that its cards are assigned to it
+                    if !matches!(partition(&line.text), Some(Ok(Partition::Main))) {
+                        return Some(Ok(line));
+                    }
+                }
+                // Without a header the held lines are all bulk data
+                BulkState::Lookahead if is_keyword(&line.text, b"ENDDATA") => {
+                    self.state = BulkState::Done;
+                }
+                BulkState::Lookahead if is_keyword(&line.text, b"CEND") => {
+                    self.held.clear();
+                    self.state = BulkState::Header;
+                }
+                BulkState::Lookahead => {
+                    self.held.push_back(line);
+                    if self.held.len() > self.max_header_lines {
+                        self.state = BulkState::Bulk;
+                    }
+                }
+                BulkState::Header => {}
+                BulkState::Bulk if is_keyword(&line.text, b"ENDDATA") => {
+                    self.state = BulkState::Done;
+                }
+                BulkState::Bulk => return Some(Ok(line)),
+                BulkState::Done => unreachable!(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::parser::parse_buffer;
+    use std::io;
+
+    const INPUT: &[u8] = b"SOL 101
+CEND
+BEGIN BULK
+GRID,1,,1.,2.,3.
+$ comment
+CHEXA,1,2,3,4,5,6,7,8,+A
+$ between
++A,9,10
+GRID,2
+=,*1,,=
+ENDDATA
+GRID,99
+";
+
+    fn parse_both(input: &[u8], options: &StreamOptions) -> (Vec<BulkCard>, Vec<BulkCard>) {
+        let expected = parse_buffer(input)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let cards = parse_reader_streaming(io::Cursor::new(input.to_vec()), options)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        (cards, expected)
+    }
+
+    #[test]
+    fn test_streaming() {
+        let expected = parse_buffer(INPUT)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let options = StreamOptions {
+            buffer_size: 16,
+            ..Default::default()
+        };
+        let cards = parse_reader_streaming(io::Cursor::new(INPUT), &options)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(cards, expected);
+
+        // Continuations that don't follow their card aren't matched
+        let input = b"GRID,1,,,,,,,,+A\nGRID,2\n+A,1\n";
+        let mut cards = parse_reader_streaming(io::Cursor::new(&input[..]), &options).unwrap();
+        assert!(cards.any(|c| c.is_err()));
+
+        // Bulk only input stops at ENDDATA like the whole file parsers
+        let (cards, expected) = parse_both(b"GRID,1\n=,*1\nENDDATA\nGARBAGE!!\n", &options);
+        assert_eq!(cards, expected);
+        assert_eq!(cards.len(), 2);
+
+        // Large replication counts don't hold every copy in memory
+        let input = b"GRID,1\n=,*1\n=9999999\n";
+        let cards = parse_reader_streaming(io::Cursor::new(&input[..]), &options).unwrap();
+        let ids: Vec<_> = cards.take(5).map(|c| c.unwrap().fields()[0]).collect();
+        assert_eq!(ids[4], crate::bdf::parser::Field::IntOrId(5));
+
+        // A partition in bulk only input keeps the main cards before it
+        let (cards, expected) = parse_both(b"GRID,1\nBEGIN SUPER=1\nGRID,1", &options);
+        assert_eq!(cards, expected);
+        let partitions: Vec<_> = cards
+            .iter()
+            .filter(|c| c.card_type().is_some())
+            .map(|c| c.partition())
+            .collect();
+        assert_eq!(partitions, vec![Partition::Main, Partition::Super(1)]);
+
+        let options = StreamOptions {
+            max_line_length: 8,
+            ..Default::default()
+        };
+        let mut cards = parse_reader_streaming(io::Cursor::new(INPUT), &options).unwrap();
+        assert!(cards.any(|c| c.is_err()));
+
+        // Reading carries on from the line after one that's too long
+        let input = b"GRID,1\nGRID,2,,1.,2.,3.\nGRID,3\n";
+        let mut lines = StreamingLines::new(&options);
+        lines
+            .push(Box::new(io::Cursor::new(&input[..])), None)
+            .unwrap();
+        let lines: Vec<_> = lines.map(|line| line.map(|l| l.line())).collect();
+        assert_eq!(lines[0].as_ref().unwrap(), &1);
+        let error = lines[1].as_ref().unwrap_err();
+        assert!(matches!(error.kind(), Error::LineTooLong(8)));
+        assert_eq!(lines[2].as_ref().unwrap(), &3);
+        assert_eq!(lines.len(), 3);
+    }
+}
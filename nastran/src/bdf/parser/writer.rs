@@ -0,0 +1,388 @@
+use std::fmt::Write as _;
+use std::io;
+
+use bstr::ByteSlice;
+
+use super::{BulkCard, Field};
+
+/// The field format used when writing cards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldFormat {
+    /// 8 character fields. Cards with integers that don't fit in 8
+    /// characters are written in large field format instead
+    #[default]
+    Small,
+    /// 16 character fields
+    Large,
+    /// Comma separated fields, using the large field layout under the same
+    /// conditions as `Small`
+    Free,
+    /// Large field format for cards with double precision values and small
+    /// field format otherwise
+    Auto,
+}
+
+/// Writes cards to `writer` in a chosen field format. Each continuation is
+/// given a unique marker
+pub struct CardWriter<W> {
+    writer: W,
+    format: FieldFormat,
+    continuations: u64,
+}
+
+impl<W: io::Write> CardWriter<W> {
+    pub fn new(writer: W, format: FieldFormat) -> Self {
+        Self {
+            writer,
+            format,
+            continuations: 0,
+        }
+    }
+
+    pub fn format(&self) -> FieldFormat {
+        self.format
+    }
+
+    /// Sets the format used by `write_card` for any following cards
+    pub fn set_format(&mut self, format: FieldFormat) {
+        self.format = format
+    }
+
+    pub fn write_card(&mut self, card: &BulkCard) -> io::Result<()> {
+        self.write_card_with(card, self.format)
+    }
+
+    pub fn write_cards<'a>(
+        &mut self,
+        cards: impl IntoIterator<Item = &'a BulkCard>,
+    ) -> io::Result<()> {
+        for card in cards {
+            self.write_card(card)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a single card in `format` regardless of the writer's format.
+    /// Fails without writing anything if the card has a value that can't be
+    /// written, such as a NaN
+    pub fn write_card_with(&mut self, card: &BulkCard, format: FieldFormat) -> io::Result<()> {
+        let text = format_card(card, format, &mut || {
+            self.continuations += 1;
+            self.continuations
+        })?;
+        self.writer.write_all(text.as_bytes())
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Formats a card, with each line ending in the card's `Eol`. `next_marker`
+/// provides the numbers used for continuation markers
+pub(crate) fn format_card(
+    card: &BulkCard,
+    format: FieldFormat,
+    next_marker: &mut impl FnMut() -> u64,
+) -> io::Result<String> {
+    let mut text = String::new();
+    let eol = card.eol.to_string();
+    let data = match &card.data {
+        Some(data) => data,
+        None if card.begin => {
+            let _ = write!(text, "{}", card.partition);
+            if !card.comment.0.is_empty() {
+                let _ = write!(text, " {}", card.comment);
+            }
+            let _ = write!(text, "{}", eol);
+            return Ok(text);
+        }
+        None => {
+            let _ = write!(text, "{}{}", card.comment, eol);
+            return Ok(text);
+        }
+    };
+    let fields = &data.fields;
+    let n = fields.len()
+        - fields
+            .iter()
+            .rev()
+            .take_while(|f| **f == Field::Blank)
+            .count();
+    let fields = &fields[..n];
+    let large = match format {
+        FieldFormat::Large => true,
+        FieldFormat::Auto => fields.iter().any(|f| matches!(f, Field::Double(_))),
+        FieldFormat::Small | FieldFormat::Free => false,
+    } || fields.iter().any(|f| !fits(f, 8));
+    let free = format == FieldFormat::Free;
+    let (width, per_line, prefix) = if large { (16, 4, '*') } else { (8, 8, '+') };
+    let values = fields
+        .iter()
+        .map(|f| format_field(f, width))
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut first = data.first.0.trim_end().to_str_lossy().into_owned();
+    if large {
+        first.push('*');
+    }
+    let lines = std::cmp::max(1, values.len().div_ceil(per_line));
+    let mut chunks = values.chunks(per_line);
+    for i in 0..lines {
+        let chunk = chunks.next().unwrap_or_default();
+        let marker = if i + 1 < lines {
+            Some(format!("{}{}", prefix, base36(next_marker())))
+        } else {
+            None
+        };
+        let start = text.len();
+        if free {
+            text.push_str(&first);
+            for value in chunk {
+                text.push(',');
+                text.push_str(value);
+            }
+            if let Some(marker) = &marker {
+                text.push_str(&",".repeat(per_line - chunk.len() + 1));
+                text.push_str(marker);
+            }
+        } else {
+            let _ = write!(text, "{:<8}", first);
+            for value in chunk {
+                let _ = write!(text, "{:<width$}", value, width = width);
+            }
+            if let Some(marker) = &marker {
+                let padding = (per_line - chunk.len()) * width;
+                let _ = write!(text, "{:padding$}{}", "", marker, padding = padding);
+            }
+            text.truncate(text.trim_end().len().max(start));
+        }
+        if marker.is_none() && !card.comment.0.is_empty() {
+            // Comments go past the last column so they can't be mistaken
+            // for data
+            if !free {
+                let padding = 80usize.saturating_sub(text.len() - start);
+                text.push_str(&" ".repeat(padding));
+            }
+            let _ = write!(text, "{}", card.comment);
+        }
+        text.push_str(&eol);
+        if let Some(marker) = marker {
+            first = marker;
+        }
+    }
+    Ok(text)
+}
+
+/// Continuation markers use base 36 to stay within 7 characters
+fn base36(mut n: u64) -> String {
+    const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    n %= 36u64.pow(7);
+    let mut digits = Vec::new();
+    loop {
+        digits.push(DIGITS[(n % 36) as usize]);
+        n /= 36;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+/// Whether a field can be written in `width` characters. Only integers can
+/// be too long since reals can always be written with less precision
+fn fits(field: &Field, width: usize) -> bool {
+    match field {
+        Field::Int(i) => i.to_string().len() <= width,
+        Field::IntOrId(i) => i.to_string().len() <= width,
+        _ => true,
+    }
+}
+
+pub(crate) fn format_field(field: &Field, width: usize) -> io::Result<String> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Can't write {:?} in {} characters", field, width),
+        )
+    };
+    let text = match *field {
+        Field::Blank => String::new(),
+        Field::Int(i) => i.to_string(),
+        Field::IntOrId(i) => i.to_string(),
+        Field::Float(f) => format_real(f as f64, width, false).ok_or_else(invalid)?,
+        Field::Double(d) => format_real(d, width, true).ok_or_else(invalid)?,
+        Field::Text(t) => t.trim_end().to_str_lossy().into_owned(),
+    };
+    if text.len() > width {
+        return Err(invalid());
+    }
+    Ok(text)
+}
+
+/// Formats a real number in at most `width` characters using whichever
+/// representation is closest to `value`, preferring shorter ones. Uses the
+/// NASTRAN shorthand exponent without an `E`, such as `1.5-3`. Doubles always
+/// have a `D` exponent so they're read back as doubles. Returns `None` for
+/// values that can't be represented, such as infinities
+pub(crate) fn format_real(value: f64, width: usize, double: bool) -> Option<String> {
+    if !value.is_finite() {
+        return None;
+    }
+    // Compare single precision values at single precision so that the exact
+    // representation is found
+    let error = |parsed: f64| {
+        if double {
+            (parsed - value).abs()
+        } else {
+            (parsed as f32 as f64 - value as f32 as f64).abs()
+        }
+    };
+    let mut best: Option<(f64, String)> = None;
+    let mut consider = |rust: &str, nastran: String| {
+        if nastran.len() > width {
+            return;
+        }
+        if let Ok(parsed) = rust.parse::<f64>() {
+            let error = error(parsed);
+            let better = match &best {
+                Some((e, s)) => error < *e || (error == *e && nastran.len() < s.len()),
+                None => true,
+            };
+            if better {
+                best = Some((error, nastran));
+            }
+        }
+    };
+    // Rounding to the nearest digit can overflow single precision near its
+    // limits so truncated mantissas are tried as well
+    let full = format!("{:.17e}", value);
+    for precision in 0..width {
+        let rounded = format!("{:.*e}", precision, value);
+        let truncated = full.split_once('e').map(|(mantissa, exponent)| {
+            let end = mantissa
+                .find('.')
+                .map_or(mantissa.len(), |i| i + precision + 1);
+            format!("{}e{}", mantissa[..end].trim_end_matches('.'), exponent)
+        });
+        for exponential in std::iter::once(rounded).chain(truncated) {
+            if let Some((mantissa, exponent)) = exponential.split_once('e') {
+                let mut nastran = mantissa.to_string();
+                if !nastran.contains('.') {
+                    nastran.push('.');
+                }
+                if double {
+                    nastran.push('D');
+                }
+                if !exponent.starts_with('-') {
+                    nastran.push('+');
+                }
+                nastran.push_str(exponent);
+                consider(&exponential, nastran);
+            }
+        }
+        if double {
+            continue;
+        }
+        let decimal = format!("{:.*}", precision, value);
+        let mut nastran = decimal.clone();
+        if !nastran.contains('.') {
+            nastran.push('.');
+        }
+        if let Some(rest) = nastran.strip_prefix("0.") {
+            nastran = format!(".{}", rest);
+        } else if let Some(rest) = nastran.strip_prefix("-0.") {
+            nastran = format!("-.{}", rest);
+        }
+        consider(&decimal, nastran);
+    }
+    best.map(|(_, s)| s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bdf::parser::{parse_buffer, parse_inner_field};
+
+    #[test]
+    fn test_format_real() {
+        let check = |value: f64, width: usize, double: bool, expected: &str| {
+            let text = format_real(value, width, double).unwrap();
+            assert_eq!(text, expected);
+            let parsed = parse_inner_field(text.bytes()).unwrap();
+            match parsed {
+                Field::Float(f) if !double => {
+                    assert!((f as f64 - value).abs() <= value.abs() * 1e-3)
+                }
+                Field::Double(d) if double => assert!((d - value).abs() <= value.abs() * 1e-6),
+                _ => panic!("{} parsed as {:?}", text, parsed),
+            }
+        };
+        check(1.0, 8, false, "1.");
+        check(0.5, 8, false, ".5");
+        check(-0.25, 8, false, "-.25");
+        check(1.5e-10, 8, false, "1.5-10");
+        check(1234567.0, 8, false, "1234567.");
+        check(12345678.0, 8, false, "1.2346+7");
+        check(1.5e30, 16, false, "1.5+30");
+        check(2.5, 16, true, "2.5D+0");
+        check(-1.0e-300, 8, true, "-1.D-300");
+        check(f32::MAX as f64, 8, false, "3.402+38");
+        assert!(format_real(f64::NAN, 8, false).is_none());
+        assert!(format_real(f64::INFINITY, 16, false).is_none());
+    }
+
+    #[test]
+    fn test_write_card() {
+        let input = b"CHEXA,1,2,3,4,5,6,7,8,+A\n+A,9,10\nGRID,1,,1.5,2.,-1234567,,,123456\nGRID,2,,1.D0 $ comment\n";
+        let cards = parse_buffer(input)
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        for format in [
+            FieldFormat::Small,
+            FieldFormat::Large,
+            FieldFormat::Free,
+            FieldFormat::Auto,
+        ] {
+            let mut writer = CardWriter::new(Vec::new(), format);
+            writer.write_cards(&cards).unwrap();
+            let output = writer.into_inner();
+            let reparsed = parse_buffer(&output)
+                .unwrap()
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .unwrap();
+            let fields = |cards: &[BulkCard]| {
+                cards
+                    .iter()
+                    .map(|c| (c.card_type(), c.fields().to_vec()))
+                    .filter(|(t, _)| t.is_some())
+                    .map(|(t, mut f)| {
+                        while f.last() == Some(&Field::Blank) {
+                            f.pop();
+                        }
+                        (t, f)
+                    })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(fields(&cards), fields(&reparsed), "{:?}", format);
+        }
+
+        let mut writer = CardWriter::new(Vec::new(), FieldFormat::Small);
+        writer.write_card(&cards[0]).unwrap();
+        writer
+            .write_card_with(&cards[0], FieldFormat::Free)
+            .unwrap();
+        assert_eq!(
+            writer.into_inner().as_bstr(),
+            "CHEXA   1       2       3       4       5       6       7       8       +1\n\
+             +1      9       10\n\
+             CHEXA,1,2,3,4,5,6,7,8,+2\n\
+             +2,9,10\n"
+        );
+    }
+}
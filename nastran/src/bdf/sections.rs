@@ -0,0 +1,459 @@
+use std::fmt;
+use std::path::Path;
+
+use bstr::ByteSlice;
+
+use crate::bdf::case_control::CaseControl;
+use crate::bdf::parser::{source, SourceLine};
+use crate::bdf::{Error, Result};
+
+/// The sections of a NASTRAN input file. Each section keeps the original
+/// lines so line numbers are preserved. The `BEGIN BULK` and `ENDDATA`
+/// delimiters are kept separately from the sections they delimit
+#[derive(Debug, Default)]
+pub struct Sections {
+    /// NASTRAN statements preceding the executive control section
+    pub nastran: Vec<SourceLine>,
+    /// Executive control statements, including the terminating `CEND`
+    pub executive: Vec<SourceLine>,
+    pub case_control: Vec<SourceLine>,
+    pub begin_bulk: Option<SourceLine>,
+    pub bulk: Vec<SourceLine>,
+    pub enddata: Option<SourceLine>,
+    /// Anything following `ENDDATA`, which NASTRAN ignores
+    pub trailing: Vec<SourceLine>,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Nastran,
+    Executive,
+    CaseControl,
+    Bulk,
+    Done,
+}
+
+impl Sections {
+    /// Reads and splits a file, expanding INCLUDE statements in place
+    pub fn from_filename(filename: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::from_lines(source::read_with_includes(filename)?))
+    }
+
+    /// Splits an input file held in memory
+    pub fn from_buffer(buffer: &[u8]) -> Result<Self> {
+        Ok(Self::from_lines(source::read_buffer_with_includes(buffer)?))
+    }
+
+    /// Splits lines into sections. A file that has neither a `CEND` nor a
+    /// `BEGIN BULK` is treated as containing only bulk data, where any
+    /// partition `BEGIN` statements switch partitions within it
+    pub fn from_lines(lines: Vec<SourceLine>) -> Self {
+        let has_cend = lines.iter().any(|l| is_keyword(&l.text, b"CEND"));
+        let has_begin_bulk = lines.iter().any(|l| is_begin_bulk(&l.text));
+        let mut state = match (has_cend, has_begin_bulk) {
+            (true, _) => State::Nastran,
+            (false, true) => State::CaseControl,
+            (false, false) => State::Bulk,
+        };
+        let mut sections = Sections::default();
+        for line in lines {
+            if let State::Nastran = state {
+                if is_keyword(&line.text, b"NASTRAN") || is_blank_or_comment(&line.text) {
+                    sections.nastran.push(line);
+                    continue;
+                }
+                state = State::Executive;
+            }
+            match state {
+                State::Nastran => unreachable!(),
+                State::Executive => {
+                    if is_keyword(&line.text, b"CEND") {
+                        state = State::CaseControl;
+                    }
+                    sections.executive.push(line);
+                }
+                State::CaseControl => {
+                    if is_begin(&line.text) {
+                        state = State::Bulk;
+                        sections.begin_bulk = Some(line);
+                    } else {
+                        sections.case_control.push(line);
+                    }
+                }
+                State::Bulk => {
+                    if is_keyword(&line.text, b"ENDDATA") {
+                        state = State::Done;
+                        sections.enddata = Some(line);
+                    } else {
+                        sections.bulk.push(line);
+                    }
+                }
+                State::Done => sections.trailing.push(line),
+            }
+        }
+        sections
+    }
+
+    /// Parses the executive control section into statements
+    pub fn executive_statements(&self) -> Result<Vec<ExecutiveStatement>> {
+        statements(&self.executive)
+            .map(|(line, text)| ExecutiveStatement::parse(&text).map_err(|e| e.at(line.location())))
+            .collect()
+    }
+
+    /// Parses the case control section into subcases
+    pub fn parse_case_control(&self) -> Result<CaseControl> {
+        CaseControl::from_lines(&self.case_control)
+    }
+
+    /// Takes the bulk data lines. If the bulk data starts with a partition
+    /// other than the main one, such as `BEGIN SUPER=1` directly after the
+    /// case control, its `BEGIN` statement is kept as the first line so that
+    /// the cards following it are assigned to that partition
+    pub(crate) fn take_bulk(&mut self) -> Vec<SourceLine> {
+        let mut bulk = std::mem::take(&mut self.bulk);
+        if let Some(begin) = &self.begin_bulk {
+            if !matches!(partition(&begin.text), Some(Ok(Partition::Main))) {
+                bulk.insert(0, begin.clone());
+            }
+        }
+        bulk
+    }
+
+    /// The solution sequence number from the `SOL` statement, if there is one
+    pub fn sol(&self) -> Result<Option<u32>> {
+        Ok(self.executive_statements()?.iter().find_map(|s| match s {
+            ExecutiveStatement::Sol(sol) => sol.number(),
+            _ => None,
+        }))
+    }
+}
+
+/// Returns only the bulk data lines of an input file
+pub(crate) fn bulk_lines(lines: Vec<SourceLine>) -> Vec<SourceLine> {
+    Sections::from_lines(lines).take_bulk()
+}
+
+/// Joins executive statements that are continued onto the next line with a
+/// trailing comma and strips comments. Yields the first line of each
+/// statement along with its text
+pub(crate) fn statements(lines: &[SourceLine]) -> impl Iterator<Item = (&SourceLine, Vec<u8>)> {
+    let mut lines = lines.iter();
+    std::iter::from_fn(move || loop {
+        let first = lines.next()?;
+        let mut text = strip_comment(&first.text).trim().to_vec();
+        if text.is_empty() {
+            continue;
+        }
+        while text.ends_with(b",") {
+            match lines.next() {
+                Some(next) => text.extend_from_slice(strip_comment(&next.text).trim()),
+                None => break,
+            }
+        }
+        return Some((first, text));
+    })
+}
+
+pub(crate) fn strip_comment(text: &[u8]) -> &[u8] {
+    match text.find_byte(b'$') {
+        Some(i) => &text[..i],
+        None => text,
+    }
+}
+
+fn is_blank_or_comment(text: &[u8]) -> bool {
+    strip_comment(text).trim().is_empty()
+}
+
+/// The length of the alphabetic word at the start of `text`
+pub(crate) fn keyword_len(text: &[u8]) -> usize {
+    text.iter().take_while(|c| c.is_ascii_alphabetic()).count()
+}
+
+/// Whether the leading word of a line is `word`, ignoring case
+pub(crate) fn is_keyword(text: &[u8], word: &[u8]) -> bool {
+    let text = text.trim_start();
+    text[..keyword_len(text)].eq_ignore_ascii_case(word)
+}
+
+/// Whether a line is the `BEGIN BULK` statement that starts the main bulk
+/// data. Only this statement shows that the input has a header
+pub(crate) fn is_begin_bulk(text: &[u8]) -> bool {
+    matches!(partition(text), Some(Ok(Partition::Main)))
+}
+
+/// Whether a line is a `BEGIN` statement that starts any partition, which
+/// ends the case control if the bulk data hasn't started yet
+pub(crate) fn is_begin(text: &[u8]) -> bool {
+    matches!(partition(text), Some(Ok(_)))
+}
+
+/// The partition of the bulk data that cards belong to, set by the `BEGIN`
+/// statement preceding them. Card IDs only need to be unique within a
+/// partition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Partition {
+    /// The main bulk data following `BEGIN BULK`
+    #[default]
+    Main,
+    /// A part superelement following `BEGIN SUPER=n` or `BEGIN BULK SUPER=n`
+    Super(u32),
+    /// An auxiliary model following `BEGIN BULK AUXMODEL=n`
+    AuxModel(u32),
+}
+
+impl Partition {
+    /// The superelement ID, where the main bulk data is the residual
+    /// structure, superelement 0. Auxiliary models aren't superelements
+    pub fn seid(self) -> Option<u32> {
+        match self {
+            Partition::Main => Some(0),
+            Partition::Super(seid) => Some(seid),
+            Partition::AuxModel(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Partition {
+    /// Formats the `BEGIN` statement that starts the partition
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Partition::Main => write!(f, "BEGIN BULK"),
+            Partition::Super(seid) => write!(f, "BEGIN SUPER={}", seid),
+            Partition::AuxModel(id) => write!(f, "BEGIN BULK AUXMODEL={}", id),
+        }
+    }
+}
+
+/// Parses a `BEGIN` statement into the partition it starts. Returns `None`
+/// for lines that aren't `BEGIN` statements
+pub(crate) fn partition(text: &[u8]) -> Option<Result<Partition>> {
+    let text = strip_comment(text).trim();
+    if !is_keyword(text, b"BEGIN") {
+        return None;
+    }
+    let invalid = || Error::InvalidStatement(text.to_vec());
+    let mut rest = text[5..].trim_start();
+    if is_keyword(rest, b"BULK") {
+        rest = rest[4..].trim_start();
+    }
+    if rest.is_empty() {
+        return Some(Ok(Partition::Main));
+    }
+    let name = &rest[..keyword_len(rest)];
+    let value = rest[name.len()..].trim_start();
+    let value = match value.strip_prefix(b"=") {
+        Some(value) => value.trim(),
+        None => return Some(Err(invalid())),
+    };
+    let id = match value.to_str().ok().and_then(|v| v.parse().ok()) {
+        Some(id) => id,
+        None => return Some(Err(invalid())),
+    };
+    let partition = if name.eq_ignore_ascii_case(b"SUPER") {
+        match id {
+            0 => Partition::Main,
+            id => Partition::Super(id),
+        }
+    } else if name.eq_ignore_ascii_case(b"AUXMODEL") {
+        Partition::AuxModel(id)
+    } else {
+        return Some(Err(invalid()));
+    };
+    Some(Ok(partition))
+}
+
+/// A solution sequence given either by number or by name
+#[derive(Debug, Clone, PartialEq)]
+pub enum Solution {
+    Number(u32),
+    Name(String),
+}
+
+impl Solution {
+    /// The solution sequence number, mapping the common structured solution
+    /// names to their numbers
+    pub fn number(&self) -> Option<u32> {
+        match self {
+            Solution::Number(n) => Some(*n),
+            Solution::Name(name) => match name.as_str() {
+                "SESTATIC" => Some(101),
+                "SEMODES" => Some(103),
+                "SEBUCKL" => Some(105),
+                "NLSTATIC" => Some(106),
+                "SEDCEIG" => Some(107),
+                "SEDFREQ" => Some(108),
+                "SEDTRAN" => Some(109),
+                "SEMCEIG" => Some(110),
+                "SEMFREQ" => Some(111),
+                "SEMTRAN" => Some(112),
+                "NLTRAN" => Some(129),
+                "NLSCSH" => Some(153),
+                "NLTCSH" => Some(159),
+                "DESOPT" => Some(200),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// A statement from the executive control section
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutiveStatement {
+    Sol(Solution),
+    /// Maximum CPU time in minutes
+    Time(f64),
+    /// The text following `ASSIGN`, which is left unparsed
+    Assign(String),
+    Diag(Vec<u32>),
+    Cend,
+    /// Any other statement, with its keyword and full text
+    Other(String, String),
+}
+
+impl ExecutiveStatement {
+    fn parse(text: &[u8]) -> Result<Self> {
+        let name = text.trim_start();
+        let name = name[..keyword_len(name)].to_ascii_uppercase();
+        let rest = text.trim_start()[name.len()..].trim();
+        let invalid = || Error::InvalidStatement(text.to_vec());
+        let statement = match name.as_slice() {
+            b"SOL" => {
+                let value = rest
+                    .split(|&c| c == b',' || c == b' ')
+                    .next()
+                    .unwrap_or_default();
+                if value.is_empty() {
+                    return Err(invalid());
+                }
+                let value = value.to_str_lossy().to_ascii_uppercase();
+                match value.parse() {
+                    Ok(n) => ExecutiveStatement::Sol(Solution::Number(n)),
+                    Err(_) => ExecutiveStatement::Sol(Solution::Name(value)),
+                }
+            }
+            b"TIME" => {
+                let value = rest.trim_start_with(|c| c == '=');
+                let value = value
+                    .split(|&c| c == b',' || c == b' ')
+                    .find(|v| !v.is_empty())
+                    .ok_or_else(invalid)?;
+                let value = value.to_str().map_err(|_| invalid())?;
+                ExecutiveStatement::Time(value.parse().map_err(|_| invalid())?)
+            }
+            b"ASSIGN" => ExecutiveStatement::Assign(rest.to_str_lossy().into_owned()),
+            b"DIAG" => {
+                let values = rest
+                    .split(|&c| c == b',' || c == b' ')
+                    .filter(|v| !v.is_empty())
+                    .map(|v| {
+                        v.to_str()
+                            .ok()
+                            .and_then(|v| v.parse().ok())
+                            .ok_or_else(invalid)
+                    })
+                    .collect::<Result<_>>()?;
+                ExecutiveStatement::Diag(values)
+            }
+            b"CEND" => ExecutiveStatement::Cend,
+            _ => ExecutiveStatement::Other(
+                name.to_str_lossy().into_owned(),
+                text.to_str_lossy().into_owned(),
+            ),
+        };
+        Ok(statement)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INPUT: &[u8] = b"NASTRAN SYSTEM(151)=1
+$ a comment
+ASSIGN OUTPUT2='model.op2',
+   UNIT=12
+SOL SESTATIC
+TIME 10
+DIAG 8,44
+CEND
+TITLE = TEST
+SUBCASE 1
+  LOAD = 1
+BEGIN BULK
+GRID    1       0       1.0
+ENDDATA
+GRID    2       0       1.0
+";
+
+    #[test]
+    fn test_split() {
+        let sections = Sections::from_buffer(INPUT).unwrap();
+        let lines = |l: &[SourceLine]| l.iter().map(|l| l.line()).collect::<Vec<_>>();
+        assert_eq!(lines(&sections.nastran), vec![1, 2]);
+        assert_eq!(lines(&sections.executive), vec![3, 4, 5, 6, 7, 8]);
+        assert_eq!(lines(&sections.case_control), vec![9, 10, 11]);
+        assert_eq!(sections.begin_bulk.as_ref().unwrap().line(), 12);
+        assert_eq!(lines(&sections.bulk), vec![13]);
+        assert_eq!(sections.enddata.as_ref().unwrap().line(), 14);
+        assert_eq!(lines(&sections.trailing), vec![15, 16]);
+
+        let bulk_only = Sections::from_buffer(b"GRID,1\nGRID,2\n").unwrap();
+        assert_eq!(lines(&bulk_only.bulk), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_executive_statements() {
+        let sections = Sections::from_buffer(INPUT).unwrap();
+        let statements = sections.executive_statements().unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                ExecutiveStatement::Assign("OUTPUT2='model.op2',UNIT=12".into()),
+                ExecutiveStatement::Sol(Solution::Name("SESTATIC".into())),
+                ExecutiveStatement::Time(10.0),
+                ExecutiveStatement::Diag(vec![8, 44]),
+                ExecutiveStatement::Cend,
+            ]
+        );
+        assert_eq!(sections.sol().unwrap(), Some(101));
+
+        let sections = Sections::from_buffer(b"SOL\nCEND\nBEGIN BULK\n").unwrap();
+        let error = sections.executive_statements().unwrap_err();
+        assert_eq!(error.location().unwrap().line(), 1);
+    }
+
+    #[test]
+    fn test_partition() {
+        assert_eq!(partition(b"begin bulk").unwrap().unwrap(), Partition::Main);
+        assert_eq!(
+            partition(b"BEGIN SUPER = 3 $ wing").unwrap().unwrap(),
+            Partition::Super(3)
+        );
+        assert_eq!(
+            partition(b"BEGIN BULK AUXMODEL=1").unwrap().unwrap(),
+            Partition::AuxModel(1)
+        );
+        assert!(partition(b"BEGIN BULK SUPER").unwrap().is_err());
+        assert!(partition(b"GRID,1").is_none());
+        assert!(partition(b"BEGINS BULK").is_none());
+        assert_eq!(
+            partition(b"begin auxmodel=2").unwrap().unwrap(),
+            Partition::AuxModel(2)
+        );
+        assert!(is_keyword(b"  enddata", b"ENDDATA"));
+        assert!(!is_keyword(b"ENDDATAX", b"ENDDATA"));
+
+        let mut sections = Sections::from_buffer(b"CEND\nBEGIN SUPER=1\nGRID,1\n").unwrap();
+        assert!(sections.case_control.is_empty());
+        let bulk = sections.take_bulk();
+        assert_eq!(bulk[0].text, b"BEGIN SUPER=1");
+
+        // Without a header, a partition doesn't drop the main cards before it
+        let sections = Sections::from_buffer(b"GRID,1\nBEGIN SUPER=1\nGRID,1\n").unwrap();
+        assert!(sections.begin_bulk.is_none());
+        let bulk: Vec<_> = sections.bulk.iter().map(|l| l.line()).collect();
+        assert_eq!(bulk, vec![1, 2, 3, 4]);
+    }
+}
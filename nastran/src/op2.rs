@@ -25,7 +25,7 @@ pub trait WordsDebugExt<'a, W: Word> {
 }
 
 impl<'a, W: Word> WordsDebugExt<'a, W> for &'a [W] {
-    fn debug_words(&self) -> WordsDebug<W> {
+    fn debug_words(&self) -> WordsDebug<'_, W> {
         WordsDebug(self)
     }
 }
@@ -383,7 +383,7 @@ impl Alignment for Aligned {
 impl Alignment for MaybeAligned {
     fn read_value<T: bytemuck::Pod>(buffer: &[u8]) -> T {
         // Use bytemuck if read is aligned
-        if (buffer.as_ptr() as usize) % std::mem::size_of::<T>() == 0 {
+        if (buffer.as_ptr() as usize).is_multiple_of(std::mem::size_of::<T>()) {
             *bytemuck::from_bytes(buffer)
         } else {
             debug_assert!(buffer.len() == std::mem::size_of::<T>());
@@ -478,7 +478,7 @@ impl IndexedByteSlice {
     }
 
     pub fn aligned_cast<U: bytemuck::Pod>(&self) -> Option<Indexed<Aligned, U>> {
-        debug_assert!(self.start % std::mem::align_of::<U>() == 0);
+        debug_assert!(self.start.is_multiple_of(std::mem::align_of::<U>()));
         if self.len() == std::mem::size_of::<U>() {
             Some(Indexed::new(self.start, self.end))
         } else {
@@ -554,7 +554,7 @@ where
     T: bytemuck::Pod,
 {
     fn new(start: usize, end: usize) -> Self {
-        debug_assert!((end - start) % std::mem::size_of::<T>() == 0);
+        debug_assert!((end - start).is_multiple_of(std::mem::size_of::<T>()));
         IndexedSlice {
             start,
             end,
@@ -577,7 +577,7 @@ where
     //}
 
     pub fn cast<U: bytemuck::Pod>(&self) -> Option<IndexedSlice<MaybeAligned, U>> {
-        if self.len() % std::mem::size_of::<U>() == 0 {
+        if self.len().is_multiple_of(std::mem::size_of::<U>()) {
             Some(IndexedSlice::new(self.start, self.end))
         } else {
             None
@@ -610,7 +610,7 @@ where
 
     pub fn read_value(&self, file_buffer: &[u8]) -> Vec<T> {
         let buf = &file_buffer[self.start..self.end];
-        if (buf.as_ptr() as usize) % std::mem::size_of::<T>() == 0 {
+        if (buf.as_ptr() as usize).is_multiple_of(std::mem::size_of::<T>()) {
             bytemuck::cast_slice(buf).to_vec()
         } else {
             let mut ret = Vec::with_capacity(self.len());
@@ -618,9 +618,7 @@ where
             for _ in 0..self.len() {
                 // SAFETY this slice is the same size the type. And this slice would
                 // only have been saved if it was valid to read it
-                ret.push(unsafe {
-                    std::ptr::read_unaligned((&buf[offset..]).as_ptr() as *const T)
-                });
+                ret.push(unsafe { std::ptr::read_unaligned(buf[offset..].as_ptr() as *const T) });
                 offset += std::mem::size_of::<T>();
             }
             ret
@@ -737,11 +735,11 @@ where
             return Err(ErrorCode::UnexpectedEOF);
         }
         let n = std::mem::size_of::<T>();
-        if n_bytes % n != 0 {
+        if !n_bytes.is_multiple_of(n) {
             return Err(ErrorCode::AlignmentError);
         }
         let buf = &self.buffer[self.index..];
-        if (buf.as_ptr() as usize) % std::mem::align_of::<T>() != 0 {
+        if !(buf.as_ptr() as usize).is_multiple_of(std::mem::align_of::<T>()) {
             return Err(ErrorCode::UnalignedValue);
         }
         let ret = IndexedSlice::new(self.index, self.index + n_bytes);
@@ -822,7 +820,7 @@ where
         if n < 1 {
             return Err(ErrorCode::NegativeRead(n));
         }
-        if n as usize % size != 0 {
+        if !(n as usize).is_multiple_of(size) {
             return Err(ErrorCode::AlignmentError);
         }
         let res = self.read_slice(n as usize)?;
@@ -868,7 +866,7 @@ where
             let nwords: i64 = nwords.into();
             let nbytes = (nwords as usize) * P::WORDSIZE;
             let size = std::mem::size_of::<T>();
-            if nbytes % size != 0 {
+            if !nbytes.is_multiple_of(size) {
                 return Err(ErrorCode::AlignmentError);
             }
             let nvalues = nbytes / size;
@@ -1168,6 +1166,81 @@ pub fn parse_file_double(
     parse_file(filename.as_ref())
 }
 
+pub enum OneOrTwo {
+    One,
+    Two,
+}
+
+pub struct RecordIterator<'buf, 'data, R> {
+    buffer: &'buf [u8],
+    current_index: usize,
+    data: &'data [IndexedByteSlice],
+    record_type: std::marker::PhantomData<R>,
+}
+
+impl<'buf, 'data, R: bytemuck::Pod> RecordIterator<'buf, 'data, R> {
+    fn new(buffer: &'buf [u8], data: &'data [IndexedByteSlice]) -> Self {
+        Self {
+            buffer,
+            current_index: 0,
+            data,
+            record_type: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'buf, 'data, R: bytemuck::Pod> Iterator for RecordIterator<'buf, 'data, R> {
+    type Item = R;
+    fn next(&mut self) -> Option<R> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let size = std::mem::size_of::<R>();
+        let mut data_len = 0;
+        let mut current_index = self.current_index;
+        for sl in self.data {
+            data_len += sl.len() - current_index;
+            if size <= data_len {
+                break;
+            }
+            current_index = 0;
+        }
+        if size > data_len {
+            return None;
+        }
+        let mut record = std::mem::MaybeUninit::<R>::uninit();
+        let mut dst = record.as_mut_ptr() as *mut u8;
+        let mut remaining = size;
+        let mut data_index = 0;
+        for sl in self.data {
+            let sl_rem = sl.len() - self.current_index;
+            let n = std::cmp::min(remaining, sl_rem);
+            // This check will return false if we're starting at the end of a slice
+            if n > 0 {
+                let src = self.buffer[sl.start + self.current_index..].as_ptr();
+                unsafe {
+                    std::ptr::copy_nonoverlapping(src, dst, n);
+                    dst = dst.add(n);
+                };
+                remaining -= n;
+            }
+            if remaining == 0 {
+                // All the data is copied. update the index
+                self.current_index += n;
+                break;
+            }
+            // Reset index back to start for next data slice
+            self.current_index = 0;
+            // This will cause the current slice to be discarded after this loop completes
+            data_index += 1;
+        }
+        debug_assert!(remaining == 0);
+        self.data = &self.data[data_index..];
+        let record = unsafe { record.assume_init() };
+        Some(record)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1276,78 +1349,3 @@ mod test {
         assert_eq!(op2.blocks[0].records.len(), 1);
     }
 }
-
-pub enum OneOrTwo {
-    One,
-    Two,
-}
-
-pub struct RecordIterator<'buf, 'data, R> {
-    buffer: &'buf [u8],
-    current_index: usize,
-    data: &'data [IndexedByteSlice],
-    record_type: std::marker::PhantomData<R>,
-}
-
-impl<'buf, 'data, R: bytemuck::Pod> RecordIterator<'buf, 'data, R> {
-    fn new(buffer: &'buf [u8], data: &'data [IndexedByteSlice]) -> Self {
-        Self {
-            buffer,
-            current_index: 0,
-            data,
-            record_type: std::marker::PhantomData,
-        }
-    }
-}
-
-impl<'buf, 'data, R: bytemuck::Pod> Iterator for RecordIterator<'buf, 'data, R> {
-    type Item = R;
-    fn next(&mut self) -> Option<R> {
-        if self.data.is_empty() {
-            return None;
-        }
-        let size = std::mem::size_of::<R>();
-        let mut data_len = 0;
-        let mut current_index = self.current_index;
-        for sl in self.data {
-            data_len += sl.len() - current_index;
-            if size <= data_len {
-                break;
-            }
-            current_index = 0;
-        }
-        if size > data_len {
-            return None;
-        }
-        let mut record = std::mem::MaybeUninit::<R>::uninit();
-        let mut dst = record.as_mut_ptr() as *mut u8;
-        let mut remaining = size;
-        let mut data_index = 0;
-        for sl in self.data {
-            let sl_rem = sl.len() - self.current_index;
-            let n = std::cmp::min(remaining, sl_rem);
-            // This check will return false if we're starting at the end of a slice
-            if n > 0 {
-                let src = self.buffer[sl.start + self.current_index..].as_ptr();
-                unsafe {
-                    std::ptr::copy_nonoverlapping(src, dst, n);
-                    dst = dst.add(n);
-                };
-                remaining -= n;
-            }
-            if remaining == 0 {
-                // All the data is copied. update the index
-                self.current_index += n;
-                break;
-            }
-            // Reset index back to start for next data slice
-            self.current_index = 0;
-            // This will cause the current slice to be discarded after this loop completes
-            data_index += 1;
-        }
-        debug_assert!(remaining == 0);
-        self.data = &self.data[data_index..];
-        let record = unsafe { record.assume_init() };
-        Some(record)
-    }
-}
@@ -95,6 +95,7 @@ impl<P: Precision> Oef<P> {
 
 #[derive(Debug)]
 pub enum Kind<P: Precision> {
+    #[allow(dead_code)]
     Sort1Statics { load_id: P::Int },
 }
 
@@ -1,4 +1,4 @@
 pub use crate::op2::{
-    Alignment, IndexedByteSlice, IndexedByteSlices, IndexedSlice, OneOrTwo, Precision,
-    RecordIterator, WordsDebugExt,
+    Alignment, IndexedByteSlice, IndexedByteSlices, OneOrTwo, Precision, RecordIterator,
+    WordsDebugExt,
 };
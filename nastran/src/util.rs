@@ -101,6 +101,11 @@ impl Mat3 {
     pub fn new(x: Vec3, y: Vec3, z: Vec3) -> Self {
         Self([x.0, y.0, z.0])
     }
+
+    pub fn transpose(&self) -> Self {
+        let m = self.0;
+        Self([0, 1, 2].map(|i| [m[0][i], m[1][i], m[2][i]]))
+    }
 }
 
 impl Mul<Vec3> for &Mat3 {
@@ -120,7 +125,7 @@ impl Mul<Vec3> for &Mat3 {
 pub struct Xyz(Vec3);
 
 #[derive(Debug, Clone, Copy)]
-pub struct DeltaXyz(Vec3);
+pub struct DeltaXyz(#[allow(dead_code)] Vec3);
 
 impl Xyz {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
@@ -178,6 +183,12 @@ impl CoordSys {
     }
 
     pub fn forward(&self, xyz: Xyz) -> Xyz {
-        Xyz(&self.m * xyz.0 + self.o)
+        Xyz(self.rotate(xyz.0) + self.o)
+    }
+
+    /// Turns a vector given in this system into the system it's defined in,
+    /// without moving it to the origin
+    pub fn rotate(&self, v: Vec3) -> Vec3 {
+        &self.m.transpose() * v
     }
 }